@@ -0,0 +1,19 @@
+use utils::generate::Rng;
+
+/// `size` pairs of five digit location IDs. About a third of the right list is copied from
+/// the left one, so the similarity score has something to count.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let left = (0..size)
+        .map(|_| rng.range(10000..100000))
+        .collect::<Vec<_>>();
+    let mut input = String::new();
+    for l in &left {
+        let r = if rng.chance(0.3) {
+            *rng.pick(&left)
+        } else {
+            rng.range(10000..100000)
+        };
+        input.push_str(&format!("{}   {}\n", l, r));
+    }
+    input
+}
@@ -1,7 +1,18 @@
 use utils::read_lines;
 
+mod generate;
+
 fn main() {
-    let (left, right): (Vec<u32>, Vec<u32>) = read_lines("src/input.txt")
+    // `day-1 generate <seed> [size]` prints a random input with `size` pairs
+    if utils::generate::command(1000, generate::input) {
+        return;
+    }
+    println!("Part 1: {}", part_1("src/input.txt"));
+    println!("Part 2: {}", part_2("src/input.txt"));
+}
+
+fn parse_lists(filename: &str) -> (Vec<u32>, Vec<u32>) {
+    read_lines(filename)
         .iter()
         .map(|line| {
             let tmp: Vec<u32> = line
@@ -10,14 +21,20 @@ fn main() {
                 .collect();
             (tmp[0], tmp[1])
         })
-        .unzip();
-    let part1 = find_distance(&left, &right);
-    let part2 = find_similarity(&left, &right);
-    println!("Part 1: {}", part1);
-    println!("Part 2: {}", part2);
+        .unzip()
+}
+
+fn part_1(filename: &str) -> u32 {
+    let (left, right) = parse_lists(filename);
+    find_distance(&left, &right)
 }
 
-fn find_distance(left_list: &Vec<u32>, right_list: &Vec<u32>) -> u32 {
+fn part_2(filename: &str) -> u32 {
+    let (left, right) = parse_lists(filename);
+    find_similarity(&left, &right)
+}
+
+fn find_distance(left_list: &[u32], right_list: &[u32]) -> u32 {
     let mut l = left_list.to_vec();
     let mut r = right_list.to_vec();
     l.sort_unstable();
@@ -26,7 +43,7 @@ fn find_distance(left_list: &Vec<u32>, right_list: &Vec<u32>) -> u32 {
     r.reverse();
 
     let mut result = 0;
-    while l.len() > 0 && r.len() > 0 {
+    while !l.is_empty() && !r.is_empty() {
         let left = l.pop().unwrap();
         let right = r.pop().unwrap();
         let distance = right.abs_diff(left);
@@ -35,29 +52,37 @@ fn find_distance(left_list: &Vec<u32>, right_list: &Vec<u32>) -> u32 {
     result
 }
 
-fn find_similarity(left_list: &Vec<u32>, right_list: &Vec<u32>) -> u32 {
-    left_list.iter().map(|location|
-        (right_list.iter().filter(|&x| *x == *location).count() as u32) * location
-    ).sum()
+fn find_similarity(left_list: &[u32], right_list: &[u32]) -> u32 {
+    left_list
+        .iter()
+        .map(|location| (right_list.iter().filter(|&x| *x == *location).count() as u32) * location)
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
 
-    #[test]
-    fn test_part_1() {
-        let left_list = vec![3, 4, 2, 1, 3, 3];
-        let right_list = vec![4, 3, 5, 3, 9, 3];
-        let result = find_distance(&left_list, &right_list);
-        assert_eq!(result, 11);
-    }
+    aoc_test!(test_sample, "sample.txt", part_1 => 11, part_2 => 31);
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
 
     #[test]
-    fn test_part_2() {
-        let left_list = vec![3, 4, 2, 1, 3, 3];
-        let right_list = vec![4, 3, 5, 3, 9, 3];
-        let result = find_similarity(&left_list, &right_list);
-        assert_eq!(result, 31);
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 200);
+            assert_eq!(input.lines().count(), 200);
+            // Pairing the lists up the other way round can't change the distance
+            let flipped = input
+                .lines()
+                .map(|l| l.split_whitespace().rev().collect::<Vec<_>>().join("   "))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let distance = with_input(&input, part_1);
+            assert_eq!(with_input(&flipped, part_1), distance);
+            assert!(with_input(&input, part_2) > 0);
+        }
     }
 }
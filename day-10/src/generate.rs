@@ -0,0 +1,31 @@
+use utils::generate::Rng;
+
+/// A `size` by `size` topographic map. Random heights hardly ever line up into a trail, so
+/// a few dozen hiking trails are walked into it at random first, and they cross and branch
+/// wherever they happen to run into each other.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(2);
+    let mut map = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| rng.range(0..10) as u8)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for _ in 0..size * size / 20 + 1 {
+        let (mut row, mut col) = (rng.below(size), rng.below(size));
+        for height in 0..10 {
+            map[row][col] = height;
+            let steps = [(0, 1), (1, 0), (0, -1), (-1, 0)]
+                .into_iter()
+                .map(|(dr, dc)| (row as isize + dr, col as isize + dc))
+                .filter(|&(r, c)| r >= 0 && c >= 0 && r < size as isize && c < size as isize)
+                .collect::<Vec<_>>();
+            let (r, c) = *rng.pick(&steps);
+            (row, col) = (r as usize, c as usize);
+        }
+    }
+    map.iter()
+        .map(|row| row.iter().map(|h| h.to_string()).collect::<String>() + "\n")
+        .collect()
+}
@@ -4,6 +4,8 @@ use array2d::Array2D;
 use itertools::Itertools;
 use utils::{read_lines, time};
 
+mod generate;
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct Tile {
     height: i32,
@@ -20,13 +22,17 @@ impl Tile {
 }
 
 fn main() {
-    let (score1, time1) = time(|| score_trailheads(build_map(read_lines("src/input.txt")), true));
+    // `day-10 generate <seed> [size]` prints a random `size` square map
+    if utils::generate::command(50, generate::input) {
+        return;
+    }
+    let (score1, time1) = time(|| part_1("src/input.txt"));
     println!(
         "Part 1 Score: {} (took {} seconds)",
         score1,
         time1.as_secs_f64()
     );
-    let (score2, time2) = time(|| score_trailheads(build_map(read_lines("src/input.txt")), false));
+    let (score2, time2) = time(|| part_2("src/input.txt"));
     println!(
         "Part 2 Score: {} (took {} seconds)",
         score2,
@@ -34,6 +40,16 @@ fn main() {
     );
 }
 
+/// How many peaks each trailhead can reach
+fn part_1(filename: &str) -> usize {
+    score_trailheads(build_map(read_lines(filename)), true)
+}
+
+/// How many distinct trails start at each trailhead
+fn part_2(filename: &str) -> usize {
+    score_trailheads(build_map(read_lines(filename)), false)
+}
+
 fn score_trailheads(map: Array2D<Tile>, distinct: bool) -> usize {
     let mut score = 0;
     // Starting from each trailhead...
@@ -42,12 +58,12 @@ fn score_trailheads(map: Array2D<Tile>, distinct: bool) -> usize {
         visited.push(trailhead);
         // First find all the neighbors of the trailhead tile
         let mut n: Vec<&Tile> = neighbors(&map, trailhead);
-        if n.len() == 0 {
+        if n.is_empty() {
             // bail out, this trailhead has no eligible neighbors
             continue;
         }
         let mut local_score = 0;
-        while n.len() > 0 {
+        while !n.is_empty() {
             visited.extend_from_slice(&n);
             let new_neighbors: Vec<&Tile> = if distinct {
                 n.iter()
@@ -69,7 +85,7 @@ fn score_trailheads(map: Array2D<Tile>, distinct: bool) -> usize {
     score
 }
 
-fn neighbors<'a, 'b>(map: &'a Array2D<Tile>, tile: &'b Tile) -> Vec<&'a Tile> {
+fn neighbors<'a>(map: &'a Array2D<Tile>, tile: &Tile) -> Vec<&'a Tile> {
     let mut n: Vec<&Tile> = Vec::new();
     if let Some(t) = map.get(
         (tile.position.1 + 1).try_into().unwrap_or(0),
@@ -122,21 +138,22 @@ fn build_map(raw_map: Vec<String>) -> Array2D<Tile> {
 
 #[cfg(test)]
 mod tests {
-    use utils::read_lines;
-
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
 
-    #[test]
-    fn test_part_1() {
-        let map = build_map(read_lines("src/test-input.txt"));
-        let score = score_trailheads(map, true);
-        assert_eq!(score, 36);
-    }
+    aoc_test!(test_sample, "test-input.txt", part_1 => 36, part_2 => 81);
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
 
     #[test]
-    fn test_part_2() {
-        let map = build_map(read_lines("src/test-input.txt"));
-        let score = score_trailheads(map, false);
-        assert_eq!(score, 81);
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 30);
+            let (score, rating) = with_input(&input, |f| (part_1(f), part_2(f)));
+            // Every peak a trailhead can reach has at least one trail to it
+            assert!(score > 0);
+            assert!(rating >= score);
+        }
     }
 }
@@ -0,0 +1,12 @@
+use utils::generate::Rng;
+
+/// `size` stones on one line, with numbers of up to seven digits like the real ones
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let stones = (0..size)
+        .map(|_| {
+            let digits = rng.range(1..8) as u32;
+            rng.range(0..10i64.pow(digits)).to_string()
+        })
+        .collect::<Vec<_>>();
+    stones.join(" ") + "\n"
+}
@@ -1,8 +1,14 @@
-use std::collections::HashMap;
 use memoize::memoize;
+use std::collections::HashMap;
 use utils::time;
 
+mod generate;
+
 fn main() {
+    // `day-11 generate <seed> [size]` prints `size` random stones
+    if utils::generate::command(8, generate::input) {
+        return;
+    }
     let stones = "64554 35 906 6 6960985 5755 975820 0";
     let (part1, time1) = time(|| blink(stones, 25));
     println!("Part 1: {} (took {} seconds)", part1, time1.as_secs_f64());
@@ -35,7 +41,7 @@ fn bl(n: u64) -> Vec<u64> {
         // If the stone is engraved with the number 0,
         // it is replaced by a stone engraved with the number 1.
         vec![1]
-    } else if n.to_string().len() % 2 == 0 {
+    } else if n.to_string().len().is_multiple_of(2) {
         // If the stone is engraved with a number that has an even number of digits,
         // it is replaced by two stones. The left half of the digits are engraved
         // on the new left stone, and the right half of the digits are engraved on
@@ -43,10 +49,7 @@ fn bl(n: u64) -> Vec<u64> {
         // 1000 would become stones 10 and 0.)
         let number_string = n.to_string();
         let (left, right) = number_string.split_at(number_string.len() / 2);
-        vec![
-            left.parse::<u64>().unwrap(),
-            right.parse::<u64>().unwrap()
-        ]
+        vec![left.parse::<u64>().unwrap(), right.parse::<u64>().unwrap()]
     } else {
         // If none of the other rules apply, the stone is replaced by a new stone;
         // the old stone's number multiplied by 2024 is engraved on the new stone.
@@ -57,10 +60,24 @@ fn bl(n: u64) -> Vec<u64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::generate::Rng;
+    use utils::{aoc_test, read_file};
+
+    aoc_test!(
+        test_sample,
+        "sample.txt",
+        |f| blink(&read_file(f), 25) => 55312,
+        |f| blink(&read_file(f), 75) => 65601038650482,
+    );
 
     #[test]
-    fn test_part_1() {
-        let result = blink("125 17", 25);
-        assert_eq!(result, 55312);
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 10);
+            assert_eq!(blink(&input, 0), 10);
+            // Stones never affect each other, so each one can be counted on its own
+            let each = input.split_whitespace().map(|s| blink(s, 40)).sum::<u64>();
+            assert_eq!(blink(&input, 40), each);
+        }
     }
 }
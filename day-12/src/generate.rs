@@ -0,0 +1,31 @@
+use utils::generate::Rng;
+
+/// A `size` by `size` garden. Plants are grown out from a scattering of seeds, each plot
+/// taking the plant of the nearest one, with the odd stray plant dropped in so regions
+/// get holes and ragged edges.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let seeds = (0..size * size / 30 + 1)
+        .map(|_| {
+            let plant = (b'A' + rng.below(26) as u8) as char;
+            (rng.below(size), rng.below(size), plant)
+        })
+        .collect::<Vec<_>>();
+    let mut garden = String::new();
+    for row in 0..size {
+        for col in 0..size {
+            let plant = if rng.chance(0.03) {
+                (b'A' + rng.below(26) as u8) as char
+            } else {
+                let nearest = seeds
+                    .iter()
+                    .min_by_key(|(r, c, _)| r.abs_diff(row).pow(2) + c.abs_diff(col).pow(2))
+                    .unwrap();
+                nearest.2
+            };
+            garden.push(plant);
+        }
+        garden.push('\n');
+    }
+    garden
+}
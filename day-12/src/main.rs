@@ -2,6 +2,8 @@ use itertools::Itertools;
 use queues::{IsQueue, Queue};
 use utils::{read_lines, time};
 
+mod generate;
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
 enum Direction {
     North,
@@ -22,7 +24,7 @@ impl Side {
     }
 }
 
-#[derive(Debug, Eq, Copy, Clone, Hash)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
 struct Plot {
     row: usize,
     col: usize,
@@ -42,7 +44,7 @@ impl Plot {
         if east.is_none() {
             s.push(Side::new(Direction::East, self.col));
         }
-        if self.col as i32 - 1 >= 0 {
+        if self.col > 0 {
             let west = region
                 .iter()
                 .find(|p| p.plant == self.plant && p.col == self.col - 1 && p.row == self.row);
@@ -58,7 +60,7 @@ impl Plot {
         if south.is_none() {
             s.push(Side::new(Direction::South, self.row));
         }
-        if self.row as i32 - 1 >= 0 {
+        if self.row > 0 {
             let north = region
                 .iter()
                 .find(|p| p.plant == self.plant && p.col == self.col && p.row == self.row - 1);
@@ -83,7 +85,7 @@ impl Plot {
         {
             n.push(r);
         }
-        if self.col as i32 - 1 >= 0 {
+        if self.col > 0 {
             if let Some(&r) = region
                 .iter()
                 .find(|p| p.plant == self.plant && p.col == self.col - 1 && p.row == self.row)
@@ -97,7 +99,7 @@ impl Plot {
         {
             n.push(r);
         }
-        if self.row as i32 - 1 >= 0 {
+        if self.row > 0 {
             if let Some(&r) = region
                 .iter()
                 .find(|p| p.plant == self.plant && p.col == self.col && p.row == self.row - 1)
@@ -109,12 +111,6 @@ impl Plot {
     }
 }
 
-impl PartialEq for Plot {
-    fn eq(&self, other: &Self) -> bool {
-        self.row == other.row && self.col == other.col && self.plant == other.plant
-    }
-}
-
 fn build_regions(lines: Vec<String>) -> Vec<Vec<Plot>> {
     let plots: Vec<Plot> = lines
         .iter()
@@ -131,23 +127,23 @@ fn build_regions(lines: Vec<String>) -> Vec<Vec<Plot>> {
     let mut result: Vec<Vec<Plot>> = Vec::new();
     let mut visited: Vec<&Plot> = Vec::new();
     for plot in &plots {
-        if visited.iter().any(|&x| x == plot) {
+        if visited.contains(&plot) {
             continue;
         }
         let mut region: Vec<&Plot> = Vec::new();
         let mut queue: Queue<&Plot> = Queue::new();
-        queue.add(&plot).expect("uh oh");
+        queue.add(plot).expect("uh oh");
         while queue.size() > 0 {
             let p = queue.remove().unwrap();
-            if visited.iter().any(|&x| x == p) {
+            if visited.contains(&p) {
                 continue;
             }
             visited.push(p);
             region.push(p);
             let neighbors = p.valid_orthogonal_neighbors(&plots.iter().collect_vec());
             for neighbor in neighbors.into_iter() {
-                if !visited.iter().any(|&x| x == neighbor) {
-                    queue.add(&neighbor).expect("uh oh");
+                if !visited.contains(&neighbor) {
+                    queue.add(neighbor).expect("uh oh");
                 }
             }
         }
@@ -186,10 +182,10 @@ fn price_part2(lines: Vec<String>) -> usize {
         for (_, side) in sides {
             let mut s: Vec<Vec<&Plot>> = Vec::new();
             for (_, p) in side {
-                if let Some(possible_side) = s
-                    .iter()
-                    .position(|x| x.iter().any(|y| y.valid_orthogonal_neighbors(&vec![p]).len() != 0))
-                {
+                if let Some(possible_side) = s.iter().position(|x| {
+                    x.iter()
+                        .any(|y| !y.valid_orthogonal_neighbors(&vec![p]).is_empty())
+                }) {
                     let z = s.get_mut(possible_side).unwrap();
                     z.push(p);
                 } else {
@@ -204,6 +200,10 @@ fn price_part2(lines: Vec<String>) -> usize {
 }
 
 fn main() {
+    // `day-12 generate <seed> [size]` prints a random `size` square garden
+    if utils::generate::command(140, generate::input) {
+        return;
+    }
     let (part1, time1) = time(|| price_part1(read_lines("src/input.txt")));
     println!("Part 1: {} (took {} seconds)", part1, time1.as_secs_f64());
     let (part2, time2) = time(|| price_part2(read_lines("src/input.txt")));
@@ -213,17 +213,34 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use utils::read_lines;
+    use utils::generate::Rng;
+    use utils::{aoc_test, read_lines};
+
+    aoc_test!(
+        test_sample,
+        "sample-map.txt",
+        |f| price_part1(read_lines(f)) => 1930,
+        |f| price_part2(read_lines(f)) => 1206,
+    );
+    aoc_test!(
+        test_input,
+        optional "input.txt",
+        |f| price_part1(read_lines(f)),
+        |f| price_part2(read_lines(f)),
+    );
 
     #[test]
-    fn test_part_1() {
-        let price = price_part1(read_lines("src/sample-map.txt"));
-        assert_eq!(price, 1930);
-    }
-
-    #[test]
-    fn test_part_2() {
-        let price = price_part2(read_lines("src/sample-map.txt"));
-        assert_eq!(price, 1206);
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 20);
+            let lines = input.lines().map(String::from).collect::<Vec<_>>();
+            // A region can't have more sides than fences
+            let (fences, sides) = (price_part1(lines.clone()), price_part2(lines));
+            assert!(sides > 0 && sides < fences);
+        }
+        // One big field: 400 plots with 80 fences along 4 sides
+        let field = vec!["A".repeat(20); 20];
+        assert_eq!(price_part1(field.clone()), 400 * 80);
+        assert_eq!(price_part2(field), 400 * 4);
     }
 }
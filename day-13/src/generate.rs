@@ -0,0 +1,22 @@
+use utils::generate::Rng;
+
+/// `size` claw machines. Most have their prize placed by pressing each button up to 100
+/// times, so part 1 can win them; the rest have the prize dropped somewhere at random.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let mut machines = Vec::new();
+    for _ in 0..size {
+        let (ax, ay) = (rng.range(10..100), rng.range(10..100));
+        let (bx, by) = (rng.range(10..100), rng.range(10..100));
+        let (px, py) = if rng.chance(0.7) {
+            let (a, b) = (rng.range(0..101), rng.range(0..101));
+            (ax * a + bx * b, ay * a + by * b)
+        } else {
+            (rng.range(1000..20000), rng.range(1000..20000))
+        };
+        machines.push(format!(
+            "Button A: X+{}, Y+{}\nButton B: X+{}, Y+{}\nPrize: X={}, Y={}\n",
+            ax, ay, bx, by, px, py
+        ));
+    }
+    machines.join("\n")
+}
@@ -1,8 +1,10 @@
-use regex::Regex;
-use utils::{read_file, time};
-use z3::{Config, Context, SatResult, Solver};
-use z3::ast::{Ast, Int};
+use utils::math::{gcd, mod_inverse, mul_mod, solve_integer};
+use utils::parse::{read_input, sections, AocError};
+use utils::{scan, time};
 
+mod generate;
+
+#[derive(Debug, Clone)]
 struct Prize {
     x1: usize,
     y1: usize,
@@ -12,20 +14,11 @@ struct Prize {
     z2: usize,
 }
 
-impl Prize {
-    fn new(x1: &str, y1: &str, x2: &str, y2: &str, z1: &str, z2: &str) -> Self {
-        Prize {
-            x1: x1.parse::<usize>().unwrap(),
-            y1: y1.parse::<usize>().unwrap(),
-            x2: x2.parse::<usize>().unwrap(),
-            y2: y2.parse::<usize>().unwrap(),
-            z1: z1.parse::<usize>().unwrap(),
-            z2: z2.parse::<usize>().unwrap(),
-        }
-    }
-}
-
 fn main() {
+    // `day-13 generate <seed> [size]` prints `size` random claw machines
+    if utils::generate::command(320, generate::input) {
+        return;
+    }
     let (part1, time1) = time(|| min_tokens("src/input.txt"));
     println!("Part 1: {} (took {} seconds)", part1, time1.as_secs_f64());
     let (part2, time2) = time(|| min_tokens_2("src/input.txt"));
@@ -33,16 +26,30 @@ fn main() {
 }
 
 fn parse_input(filename: &str) -> Vec<Prize> {
-    let re = Regex::new(
-        r"Button A: X\+(\d+), Y\+(\d+)\nButton B: X\+(\d+), Y\+(\d+)\nPrize: X=(\d+), Y=(\d+)",
-    )
-    .unwrap();
-    let file = read_file(filename);
-    let mut prizes: Vec<Prize> = Vec::new();
-    for (_, [x1, y1, x2, y2, z1, z2]) in re.captures_iter(&file).map(|c| c.extract()) {
-        prizes.push(Prize::new(x1, y1, x2, y2, z1, z2));
-    }
-    prizes
+    parse(&read_input(filename)).unwrap_or_else(|e| panic!("{}: {}", filename, e))
+}
+
+/// One claw machine per section
+fn parse(input: &str) -> Result<Vec<Prize>, AocError> {
+    sections(input)
+        .iter()
+        .map(|section| {
+            let (x1, y1, x2, y2, z1, z2) = scan!(
+                section.text,
+                "Button A: X+{}, Y+{}\nButton B: X+{}, Y+{}\nPrize: X={}, Y={}"
+                    => usize, usize, usize, usize, usize, usize
+            )
+            .map_err(|e| e.below(section.line - 1))?;
+            Ok(Prize {
+                x1,
+                y1,
+                x2,
+                y2,
+                z1,
+                z2,
+            })
+        })
+        .collect()
 }
 
 fn solve_prize(prize: &Prize) -> Option<(usize, usize)> {
@@ -70,46 +77,60 @@ fn solve_prize(prize: &Prize) -> Option<(usize, usize)> {
     }
 }
 
-fn solve_prize_z3(prize: &Prize) -> Option<u64> {
-    let config = Config::new();
-    let context = Context::new(&config);
-    let solver = Solver::new(&context);
-
-    let x1 = Int::from_u64(&context, prize.x1 as u64);
-    let x2 = Int::from_u64(&context, prize.x2 as u64);
-    let y1 = Int::from_u64(&context, prize.y1 as u64);
-    let y2 = Int::from_u64(&context, prize.y2 as u64);
-    let z1 = Int::from_u64(&context, prize.z1 as u64);
-    let z2 = Int::from_u64(&context, prize.z2 as u64);
-    let a = Int::new_const(&context, "a_presses");
-    let b = Int::new_const(&context, "b_presses");
-    let a_cost = Int::from_u64(&context, 3);
-    let b_cost = Int::from_u64(&context, 1);
-
-    solver.assert(&(x1 * &a + x2 * &b)._eq(&z1));
-    solver.assert(&(y1 * &a + y2 * &b)._eq(&z2));
-
-    let cost = Int::new_const(&context, "cost");
-    solver.assert(&cost._eq(&(a * a_cost + b * b_cost)));
-
-    match solver.check() {
-        SatResult::Sat => Some(solver
-            .get_model()
-            .unwrap()
-            .eval(&cost, true)
-            .unwrap()
-            .as_u64()
-            .unwrap()),
-        _ => None
+/// The fewest tokens that win the prize, solved exactly rather than by trying presses
+fn solve_prize_exact(prize: &Prize) -> Option<u64> {
+    let [x1, y1, x2, y2, z1, z2] =
+        [prize.x1, prize.y1, prize.x2, prize.y2, prize.z1, prize.z2].map(|n| n as i64);
+    let (a, b) = if x1 * y2 != x2 * y1 {
+        // Only one way to land on it, which might still need a fraction of a press
+        match solve_integer(&[vec![x1, x2], vec![y1, y2]], &[z1, z2])?[..] {
+            [a, b] => (a, b),
+            _ => unreachable!(),
+        }
+    } else {
+        // Both buttons push the same way, so there can be more than one way to win. Work
+        // along whichever axis they move on, then check the other one agrees.
+        let (a, b) = if (x1, x2) != (0, 0) {
+            cheapest_presses(x1, x2, z1)?
+        } else {
+            cheapest_presses(y1, y2, z2)?
+        };
+        (a * x1 + b * x2 == z1 && a * y1 + b * y2 == z2).then_some((a, b))?
+    };
+    (a >= 0 && b >= 0).then(|| (a * 3 + b) as u64)
+}
+
+/// The cheapest `(a, b)` presses with `a * p + b * q == r`, where A costs 3 and B costs 1
+fn cheapest_presses(p: i64, q: i64, r: i64) -> Option<(i64, i64)> {
+    match (p, q) {
+        (0, 0) => (r == 0).then_some((0, 0)),
+        (p, 0) => (r % p == 0).then_some((r / p, 0)),
+        (0, q) => (r % q == 0).then_some((0, r / q)),
+        _ => {
+            let g = gcd(p, q);
+            if r % g != 0 {
+                return None;
+            }
+            let (p, q, r) = (p / g, q / g, r / g);
+            // Every answer is some (a + q * t, b - p * t), so the cost only goes one way in t:
+            // as few A presses as possible unless trading q Bs for p As saves tokens
+            if 3 * q >= p {
+                let a = mul_mod(r, mod_inverse(p, q)?, q);
+                let b = (r - a * p) / q;
+                (b >= 0).then_some((a, b))
+            } else {
+                let b = mul_mod(r, mod_inverse(q, p)?, p);
+                let a = (r - b * q) / p;
+                (a >= 0).then_some((a, b))
+            }
+        }
     }
 }
 
 fn min_tokens(filename: &str) -> usize {
     parse_input(filename)
         .iter()
-        .map(|p| solve_prize(p))
-        .filter(|p| p.is_some())
-        .map(|p| p.unwrap())
+        .filter_map(solve_prize)
         .map(|p| p.0 * 3 + p.1)
         .sum::<usize>()
 }
@@ -120,21 +141,126 @@ fn min_tokens_2(filename: &str) -> u64 {
         p.z1 += 10000000000000;
         p.z2 += 10000000000000;
     });
-    prizes.iter().map(|p| solve_prize_z3(p))
-        .filter(|p| p.is_some())
-        .map(|p| p.unwrap())
-        .sum::<u64>()
+    prizes.iter().filter_map(solve_prize_exact).sum::<u64>()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::differential::{check, shrink_int};
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(test_part_1, "test-input.txt", min_tokens => 480);
+    aoc_test!(test_part_2, "test-input.txt", min_tokens_2 => 875318608908);
+    aoc_test!(test_input, optional "input.txt", min_tokens, min_tokens_2);
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 30);
+            let prizes = with_input(&input, parse_input);
+            assert_eq!(prizes.len(), 30);
+            // Any prize part 1 can win costs at most 400 tokens
+            let won = prizes.iter().filter_map(solve_prize).count();
+            let tokens = with_input(&input, min_tokens);
+            assert!(won > 0);
+            assert!(tokens <= won * 400);
+        }
+    }
 
     #[test]
-    fn test_part_1() {
-        let tokens = min_tokens("src/test-input.txt");
-        let tokens2 = min_tokens_2("src/test-input.txt");
-        assert_eq!(tokens, 480);
-        assert_eq!(tokens2, 875318608908);
+    fn test_malformed() {
+        let input = "Button A: X+94, Y+34\nButton B: X+22, Y+67\nPrize: X=8400, Y=5400\n\n\
+                     Button A: X+26, Y+66\nButton B: X+67 Y+21\nPrize: X=12748, Y=12176\n";
+        let error = parse(input).unwrap_err();
+        assert_eq!(error.to_string(), "line 6, column 15: expected `, Y+`");
+    }
+
+    #[test]
+    fn test_collinear_far_away() {
+        let far = 10000000000000;
+        let prize = |a: usize, b: usize, z: usize| Prize {
+            x1: a,
+            y1: a,
+            x2: b,
+            y2: b,
+            z1: z,
+            z2: z,
+        };
+        // B gets there for a token a step, A takes 3 tokens for 2
+        assert_eq!(solve_prize_exact(&prize(2, 1, far)), Some(far as u64));
+        // A takes 3 tokens for 4 steps, with one B to make up the odd step
+        assert_eq!(
+            solve_prize_exact(&prize(4, 1, far + 1)),
+            Some(far as u64 / 4 * 3 + 1)
+        );
+        assert_eq!(solve_prize_exact(&prize(4, 6, far + 1)), None);
+        // Off the line they both push along
+        let mut off = prize(4, 6, far);
+        off.z2 += 2;
+        assert_eq!(solve_prize_exact(&off), None);
+    }
+
+    #[test]
+    fn test_exact_against_brute_force() {
+        // Prizes within 100 of the claw never need more than the 100 presses the brute
+        // force tries
+        let valid = |p: &Prize| {
+            [p.x1, p.y1, p.x2, p.y2].iter().all(|&b| b > 0) && p.z1 <= 100 && p.z2 <= 100
+        };
+        let generate = |rng: &mut Rng| loop {
+            let mut prize = Prize {
+                x1: rng.below(10) + 1,
+                y1: rng.below(10) + 1,
+                x2: rng.below(10) + 1,
+                y2: rng.below(10) + 1,
+                z1: rng.below(101),
+                z2: rng.below(101),
+            };
+            // Buttons that push the same way, where there can be more than one way to win
+            if rng.chance(0.3) {
+                let (m, n) = (rng.below(3) + 1, rng.below(3) + 1);
+                (prize.x1, prize.y1) = (prize.x1 * m, prize.y1 * m);
+                (prize.x2, prize.y2) = (prize.x1 / m * n, prize.y1 / m * n);
+            }
+            if rng.chance(0.7) {
+                let (a, b) = (rng.below(6), rng.below(6));
+                prize.z1 = prize.x1 * a + prize.x2 * b;
+                prize.z2 = prize.y1 * a + prize.y2 * b;
+            }
+            if valid(&prize) {
+                break prize;
+            }
+        };
+        let shrink = |p: &Prize| {
+            let fields = [p.x1, p.y1, p.x2, p.y2, p.z1, p.z2];
+            (0..fields.len())
+                .flat_map(|i| {
+                    shrink_int(fields[i] as i64).into_iter().map(move |s| {
+                        let mut smaller = fields;
+                        smaller[i] = s as usize;
+                        smaller
+                    })
+                })
+                .map(|[x1, y1, x2, y2, z1, z2]| Prize {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    z1,
+                    z2,
+                })
+                .filter(|p| valid(p))
+                .collect()
+        };
+        check(
+            200,
+            generate,
+            shrink,
+            |p| solve_prize(p).map(|(a, b)| (a * 3 + b) as u64),
+            solve_prize_exact,
+        );
     }
 }
@@ -0,0 +1,42 @@
+use crate::{GRID_SIZE_X, GRID_SIZE_Y};
+use std::collections::HashSet;
+use utils::generate::Rng;
+
+/// `size` robots on the full size grid that all land on different tiles after some number
+/// of seconds, about half of them drawn into a tree. Returns the input and that number of
+/// seconds, which is the latest the easter egg can turn up.
+pub fn robots(rng: &mut Rng, size: usize) -> (String, usize) {
+    let size = size.min((GRID_SIZE_X * GRID_SIZE_Y) as usize);
+    let seconds = rng.below((GRID_SIZE_X * GRID_SIZE_Y) as usize) as i32;
+    let mut tree: Vec<(i32, i32)> = Vec::new();
+    let height = ((size / 2) as f64).sqrt() as i32;
+    for row in 0..height.min(GRID_SIZE_Y) {
+        for col in -row..=row {
+            tree.push((GRID_SIZE_X / 2 + col, GRID_SIZE_Y / 4 + row));
+        }
+    }
+    let mut taken = tree.iter().copied().collect::<HashSet<_>>();
+    while taken.len() < size {
+        let spot = (
+            rng.range(0..GRID_SIZE_X as i64) as i32,
+            rng.range(0..GRID_SIZE_Y as i64) as i32,
+        );
+        if taken.insert(spot) {
+            tree.push(spot);
+        }
+    }
+
+    let mut input = String::new();
+    for (x, y) in tree {
+        let (vx, vy) = (rng.range(-100..101) as i32, rng.range(-100..101) as i32);
+        // Run the clock backwards to find where it starts
+        let start_x = (x - vx * seconds).rem_euclid(GRID_SIZE_X);
+        let start_y = (y - vy * seconds).rem_euclid(GRID_SIZE_Y);
+        input.push_str(&format!("p={},{} v={},{}\n", start_x, start_y, vx, vy));
+    }
+    (input, seconds as usize)
+}
+
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    robots(rng, size).0
+}
@@ -1,56 +1,114 @@
 use crate::robot::Robot;
 use itertools::Itertools;
-use regex::Regex;
-use utils::{read_file, time};
+use std::path::Path;
+use utils::parse::read_input;
+use utils::render::{self, Image};
+use utils::viz::{Cell, Color, Frame, Grid, Recorder};
+use utils::{scan, time};
 
+mod generate;
 mod point;
 mod robot;
 
+const GRID_SIZE_X: i32 = 101;
+const GRID_SIZE_Y: i32 = 103;
+
 fn main() {
-    let grid_size_x = 101;
-    let grid_size_y = 103;
-    let robots = parse_robots("src/input.txt", grid_size_x, grid_size_y);
+    // `day-14 generate <seed> [size]` prints `size` random robots with a tree hidden in them
+    if utils::generate::command(500, generate::input) {
+        return;
+    }
+    let robots = parse_robots("src/input.txt", GRID_SIZE_X, GRID_SIZE_Y);
+    // `day-14 show [dir]` plays the robots up to the tree, or writes the frames to `dir`
+    let args = std::env::args().collect_vec();
+    if args.get(1).map(|a| a.as_str()) == Some("show") {
+        let (seconds, tree) = find_easter_egg(&robots);
+        let mut recorder = Recorder::new(1000);
+        let mut r = robots.clone();
+        for second in 0..seconds {
+            recorder.record(&Lobby::new(&r), &format!("{} seconds", second));
+            r.iter_mut().for_each(|robot| robot.tick());
+        }
+        let frames = recorder.finish(&Lobby::new(&tree), &format!("{} seconds", seconds));
+        utils::viz::present(&frames, args.get(2).map(|a| a.as_str()), 30.0).unwrap();
+        return;
+    }
+    // `day-14 render <file>` saves the tree as a PNG, or the robots finding it if `file`
+    // ends in .gif
+    if let (Some("render"), Some(path)) = (args.get(1).map(|a| a.as_str()), args.get(2)) {
+        let (seconds, tree) = find_easter_egg(&robots);
+        if path.ends_with(".gif") {
+            let mut recorder = Recorder::new(200);
+            let mut r = robots.clone();
+            for second in 0..seconds {
+                recorder.record(&Lobby::new(&r), &second.to_string());
+                r.iter_mut().for_each(|robot| robot.tick());
+            }
+            let frames = recorder.finish(&Lobby::new(&tree), &seconds.to_string());
+            let images = frames.iter().map(|f| Image::from_frame(f, 2)).collect_vec();
+            render::write_gif(&images, 5, Path::new(path)).unwrap();
+        } else {
+            Image::render(&Lobby::new(&tree), 4, render::cell_color)
+                .write_png(Path::new(path))
+                .unwrap();
+        }
+        return;
+    }
     let (part1, time1) = time(|| safety_score(&robots, 100));
     println!("Part 1: {} (took {} seconds)", part1, time1.as_secs_f64());
-    let (part2, time2) = time(|| find_easter_egg(&robots));
+    let ((part2, tree), time2) = time(|| find_easter_egg(&robots));
+    print!("{}", Frame::capture(&Lobby::new(&tree), "").to_ansi());
     println!("Part 2: {} (took {} seconds)", part2, time2.as_secs_f64());
 }
 
 fn parse_robots(filename: &str, grid_size_x: i32, grid_size_y: i32) -> Vec<Robot> {
-    let re = Regex::new(r"p=(\d+),(\d+) v=(-*\d+),(-*\d+)").unwrap();
-    let file = read_file(filename);
-    let mut robots: Vec<Robot> = Vec::new();
-    for (_, [pos_x, pos_y, vel_x, vel_y]) in re.captures_iter(&file).map(|c| c.extract()) {
-        robots.push(Robot::new(
-            pos_x.parse().unwrap(),
-            pos_y.parse().unwrap(),
-            vel_x.parse().unwrap(),
-            vel_y.parse().unwrap(),
-            grid_size_x,
-            grid_size_y,
-        ));
+    let file = read_input(filename);
+    file.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            scan!(line, "p={},{} v={},{}" => i32, i32, i32, i32)
+                .map(|(x, y, vx, vy)| Robot::new(x, y, vx, vy, grid_size_x, grid_size_y))
+                .unwrap_or_else(|e| panic!("{}: {}", filename, e.below(i)))
+        })
+        .collect()
+}
+
+/// How many robots are on each tile, counted up front since frames look at every tile
+struct Lobby {
+    counts: Vec<Vec<usize>>,
+}
+
+impl Lobby {
+    fn new(robots: &[Robot]) -> Lobby {
+        let (width, height) = robots.first().map_or((0, 0), |r| {
+            (r.position().grid_size_x, r.position().grid_size_y)
+        });
+        let mut counts = vec![vec![0; width as usize]; height as usize];
+        for robot in robots {
+            counts[robot.position().y as usize][robot.position().x as usize] += 1;
+        }
+        Lobby { counts }
     }
-    robots
 }
 
-fn print_map(robots: &Vec<Robot>) {
-    let grouped_robots = robots
-        .iter()
-        .into_group_map_by(|&r| (r.position().x, r.position().y));
-    for y in 0..robots[0].position().grid_size_y {
-        for x in 0..robots[0].position().grid_size_x {
-            let bot_count = grouped_robots.get(&(x, y)).unwrap_or(&vec![]).len();
-            if bot_count > 0 {
-                print!("{}", bot_count);
-            } else {
-                print!(".");
-            }
+impl Grid for Lobby {
+    fn size(&self) -> (usize, usize) {
+        (
+            self.counts.len(),
+            self.counts.first().map_or(0, |r| r.len()),
+        )
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Cell {
+        match self.counts[row][col] {
+            0 => '.'.into(),
+            n => Cell::new(char::from_digit(n.min(9) as u32, 10).unwrap(), Color::Green),
         }
-        println!();
     }
 }
 
-fn safety_score(robots: &Vec<Robot>, seconds: i32) -> usize {
+fn safety_score(robots: &[Robot], seconds: i32) -> usize {
     let mut r = robots.iter().cloned().collect_vec();
     for _ in 0..seconds {
         r.iter_mut().for_each(|robot| robot.tick());
@@ -59,37 +117,78 @@ fn safety_score(robots: &Vec<Robot>, seconds: i32) -> usize {
         .filter(|rb| rb.quadrant().is_some())
         .into_group_map_by(|rb| rb.quadrant())
         .iter()
-        .map(|rb| rb.1.iter().count())
+        .map(|rb| rb.1.len())
         .product()
 }
 
-fn find_easter_egg(robots: &Vec<Robot>) -> usize {
+/// Ticks until no two robots share a tile, which is when they draw the tree, and where
+/// they all are at that point
+fn find_easter_egg(robots: &[Robot]) -> (usize, Vec<Robot>) {
     let mut ticks = 0;
     let mut r = robots.iter().cloned().collect_vec();
     while r
         .iter()
         .into_group_map_by(|&r| (r.position().x, r.position().y))
         .iter()
-        .any(|rb| rb.1.len() > 1) {
+        .any(|rb| rb.1.len() > 1)
+    {
         r.iter_mut().for_each(|robot| robot.tick());
         ticks += 1;
     }
-    print_map(&r);
-    ticks
+    (ticks, r)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
 
     #[test]
-    fn test_part_1() {
-        let grid_size_x = 11;
-        let grid_size_y = 7;
-        let robots = parse_robots("src/test-input.txt", grid_size_x, grid_size_y);
-        print_map(&robots);
-        println!();
-        let score = safety_score(&robots, 100);
-        assert_eq!(score, 12);
+    fn test_lobby() {
+        let robots = parse_robots("src/test-input.txt", 11, 7);
+        // The starting positions drawn in the puzzle
+        assert_eq!(
+            Frame::capture(&Lobby::new(&robots), "").to_plain(),
+            "\
+1.12.......
+...........
+...........
+......11.11
+1.1........
+.........1.
+.......1...
+"
+        );
+    }
+
+    // The sample robots never spread out into a tree, so there's only part 1 to check
+    aoc_test!(
+        test_sample,
+        "test-input.txt",
+        |f| safety_score(&parse_robots(f, 11, 7), 100) => 12,
+    );
+    aoc_test!(
+        test_input,
+        optional "input.txt",
+        |f| safety_score(&parse_robots(f, GRID_SIZE_X, GRID_SIZE_Y), 100),
+        |f| find_easter_egg(&parse_robots(f, GRID_SIZE_X, GRID_SIZE_Y)).0,
+    );
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..5 {
+            let (input, seconds) = generate::robots(&mut Rng::new(seed), 200);
+            let robots = with_input(&input, |f| parse_robots(f, GRID_SIZE_X, GRID_SIZE_Y));
+            assert_eq!(robots.len(), 200);
+            let (found, spread) = find_easter_egg(&robots);
+            assert!(found <= seconds);
+            assert!(spread.iter().map(|r| r.position()).all_unique());
+            // So every robot is drawn as a lone 1
+            let drawn = Frame::capture(&Lobby::new(&spread), "").to_plain();
+            assert_eq!(drawn.matches('1').count(), 200);
+            assert_eq!(drawn.lines().count(), GRID_SIZE_Y as usize);
+        }
     }
 }
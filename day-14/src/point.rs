@@ -8,7 +8,12 @@ pub struct Point {
 
 impl Point {
     pub fn new(x: i32, y: i32, grid_size_x: i32, grid_size_y: i32) -> Point {
-        Point { x, y, grid_size_x, grid_size_y }
+        Point {
+            x,
+            y,
+            grid_size_x,
+            grid_size_y,
+        }
     }
 
     pub fn add(&mut self, delta_x: i32, delta_y: i32) {
@@ -27,4 +32,4 @@ impl Point {
             self.y += self.grid_size_y;
         }
     }
-}
\ No newline at end of file
+}
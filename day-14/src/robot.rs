@@ -33,16 +33,24 @@ impl Robot {
 
     /// Returns quadrant of the map clockwise from the top left
     pub fn quadrant(&self) -> Option<i32> {
-        if self.position.x < self.position.grid_size_x / 2 && self.position.y < self.position.grid_size_y / 2 {
+        if self.position.x < self.position.grid_size_x / 2
+            && self.position.y < self.position.grid_size_y / 2
+        {
             return Some(1);
         }
-        if self.position.x > self.position.grid_size_x / 2 && self.position.y < self.position.grid_size_y / 2 {
+        if self.position.x > self.position.grid_size_x / 2
+            && self.position.y < self.position.grid_size_y / 2
+        {
             return Some(2);
         }
-        if self.position.x > self.position.grid_size_x / 2 && self.position.y > self.position.grid_size_y / 2 {
+        if self.position.x > self.position.grid_size_x / 2
+            && self.position.y > self.position.grid_size_y / 2
+        {
             return Some(3);
         }
-        if self.position.x < self.position.grid_size_x / 2 && self.position.y > self.position.grid_size_y / 2 {
+        if self.position.x < self.position.grid_size_x / 2
+            && self.position.y > self.position.grid_size_y / 2
+        {
             return Some(4);
         }
         None
@@ -0,0 +1,33 @@
+use utils::generate::Rng;
+
+/// A `size` by `size` warehouse laid out like the real input: the map, a blank line, and
+/// then `size * size / 2` moves in lines of 1000. Inside the outer wall about one tile in
+/// ten is a wall and one in four is a box.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(3);
+    let mut map = vec![vec!['#'; size]; size];
+    for row in &mut map[1..size - 1] {
+        for tile in &mut row[1..size - 1] {
+            *tile = match rng.below(20) {
+                0 | 1 => '#',
+                2..=6 => 'O',
+                _ => '.',
+            };
+        }
+    }
+    map[rng.range(1..size as i64 - 1) as usize][rng.range(1..size as i64 - 1) as usize] = '@';
+
+    let mut input = map
+        .iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect::<String>();
+    input.push('\n');
+    let moves = (0..size * size / 2)
+        .map(|_| *rng.pick(&['^', '>', 'v', '<']))
+        .collect::<Vec<_>>();
+    for line in moves.chunks(1000) {
+        input.extend(line);
+        input.push('\n');
+    }
+    input
+}
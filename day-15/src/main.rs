@@ -1,138 +1,229 @@
+mod generate;
 mod object;
+mod warehouse;
 
-use crate::object::{AddMove, Direction, Object, ObjectType};
-use itertools::Itertools;
-use std::collections::HashMap;
-use utils::{read_file, read_lines, time};
+use crate::object::Direction;
+use crate::warehouse::Warehouse;
+use utils::parse::{read_input, sections, AocError};
+use utils::time;
+use utils::viz::Recorder;
 
 fn main() {
-    let (part1, time1) = time(|| run_sim("src/map.txt", "src/moves.txt", false));
+    // `day-15 generate <seed> [size]` prints a random `size` square warehouse and its moves
+    if utils::generate::command(50, generate::input) {
+        return;
+    }
+    // `day-15 show [scale] [dir]` plays the robot's moves, or writes the frames to `dir`
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(|a| a.as_str()) == Some("show") {
+        let scale = args.get(2).map_or(1, |s| s.parse().unwrap());
+        let mut recorder = Recorder::new(1000);
+        let (warehouse, moves) = simulate("src/input.txt", scale, |w, step| {
+            recorder.record(w, &format!("move {}", step))
+        });
+        let caption = format!("{} moves, GPS {}", moves, warehouse.gps_score());
+        let frames = recorder.finish(&warehouse, &caption);
+        utils::viz::present(&frames, args.get(3).map(|a| a.as_str()), 30.0).unwrap();
+        return;
+    }
+    let (part1, time1) = time(|| run_sim("src/input.txt", 1));
     println!("Part 1: {} (took {} seconds)", part1, time1.as_secs_f64());
-    let (part2, time2) = time(|| run_sim("src/map.txt", "src/moves.txt", true));
+    let (part2, time2) = time(|| run_sim("src/input.txt", 2));
     println!("Part 2: {} (took {} seconds)", part2, time2.as_secs_f64());
 }
 
-fn parse_map(filename: &str, wide: bool) -> (HashMap<(i32, i32), Object>, (i32, i32), i32, i32) {
-    let mut file = read_lines(filename);
-    let mut map: HashMap<(i32, i32), Object> = HashMap::new();
-    let mut robot: (i32, i32) = (-1, -1);
-
-    if !wide {
-        for (row, line) in file.iter().enumerate() {
-            for (col, c) in line.chars().enumerate() {
-                if c == '#' || c == 'O' {
-                    let o = Object::new(c);
-                    map.insert((row as i32, col as i32), o);
-                } else if c == '@' {
-                    robot = (row as i32, col as i32);
+/// Parses the warehouse map, widening every tile by `scale` cells. Boxes (`O`) become a
+/// single box `scale` cells wide, and already-widened boxes (`[`..`]`) are kept as one box
+/// spanning the brackets, which have to pair up within a line.
+fn parse_map(map: &str, scale: i32) -> Result<Warehouse, AocError> {
+    let rows = map.lines().count() as i32;
+    let cols = map.lines().map(|l| l.len()).max().unwrap_or(0) as i32 * scale;
+    let mut warehouse = Warehouse::new(rows, cols, (-1, -1));
+    let mut line_start = 0;
+    for (row, line) in map.lines().enumerate() {
+        let row = row as i32;
+        // Where the open `[` is in the map, and the column it starts the box at
+        let mut box_start: Option<(usize, i32)> = None;
+        for (i, c) in line.char_indices() {
+            let col = i as i32 * scale;
+            match (c, box_start) {
+                ('#', _) => (col..col + scale).for_each(|c| warehouse.add_wall((row, c))),
+                ('O', _) => {
+                    warehouse.add_box((row, col), scale, 1);
                 }
-            }
-        }
-    } else {
-        file = file
-            .iter()
-            .map(|l| {
-                l.replace("#", "##")
-                    .replace(".", "..")
-                    .replace("@", "@.")
-                    .replace("O", "[]")
-            })
-            .collect_vec();
-        for (row, line) in file.iter().enumerate() {
-            for (col, c) in line.chars().enumerate() {
-                match c {
-                    '[' | '#' | ']' => {
-                        map.insert((row as i32, col as i32), Object::new(c));
-                    }
-                    '@' => robot = (row as i32, col as i32),
-                    _ => (),
+                ('[', None) => box_start = Some((line_start + i, col)),
+                (']', Some((_, start))) => {
+                    warehouse.add_box((row, start), col + scale - start, 1);
+                    box_start = None;
                 }
+                ('[', Some((open, _))) => return Err(AocError::at(map, open, "unmatched `[`")),
+                (']', None) => return Err(AocError::at(map, line_start + i, "unmatched `]`")),
+                ('@', _) => warehouse.robot = (row, col),
+                _ => (),
             }
         }
+        if let Some((open, _)) = box_start {
+            return Err(AocError::at(map, open, "unmatched `[`"));
+        }
+        line_start += line.len() + 1;
     }
-    (map, robot, file.len() as i32, file[0].len() as i32)
+    Ok(warehouse)
 }
 
-fn parse_moves(filename: &str) -> Vec<Direction> {
-    read_file(filename)
-        .chars()
-        .filter_map(|c| match c {
-            '^' => Some(Direction::North),
-            '>' => Some(Direction::East),
-            'v' => Some(Direction::South),
-            '<' => Some(Direction::West),
-            _ => None,
+/// The moves can be split over as many lines as they like
+fn parse_moves(moves: &str) -> Result<Vec<Direction>, AocError> {
+    moves
+        .char_indices()
+        .filter(|(_, c)| !c.is_whitespace())
+        .map(|(i, c)| match c {
+            '^' => Ok(Direction::North),
+            '>' => Ok(Direction::East),
+            'v' => Ok(Direction::South),
+            '<' => Ok(Direction::West),
+            _ => Err(AocError::at(moves, i, format!("`{}` isn't a move", c))),
         })
         .collect()
 }
 
-fn run_sim(map_file: &str, moves_file: &str, wide: bool) -> i32 {
-    let (mut map, mut robot, rows, cols) = parse_map(map_file, wide);
-    let moves = parse_moves(moves_file);
-    for m in moves {
-        let mov = m.to_move();
-        let next_position = (robot.0 + mov.0, robot.1 + mov.1);
-        if let Some(next) = map.get(&next_position) {
-            // Something is in the next position, let's see if we can push it
-            let (new_map, push_result) = next.try_push(m, next_position, map.clone(), false);
-            if push_result {
-                // We pushed it
-                map = new_map;
-                robot = next_position;
-            }
-        } else {
-            // Next position is empty, we can move and the map doesn't change
-            robot = next_position;
-        }
-    }
-    print_map(&map, rows, cols, robot);
-    // Finally calculate the score
-    let mut score = 0;
-    for row in 0..rows {
-        for col in 0..cols {
-            if let Some(o) = map.get(&(row, col)) {
-                if o.kind == ObjectType::Box || o.kind == ObjectType::BoxLeft {
-                    score += 100 * row + col;
-                }
-            }
-        }
-    }
-    score
+/// The map, a blank line, then the moves. A map on its own just has no moves.
+fn parse(input: &str, scale: i32) -> Result<(Warehouse, Vec<Direction>), AocError> {
+    let (map, moves) = match sections(input)[..] {
+        [map] => (map, None),
+        [map, moves] => (map, Some(moves)),
+        _ => return Err(AocError::at(input, 0, "expected a map and then the moves")),
+    };
+    let warehouse = parse_map(map.text, scale).map_err(|e| e.below(map.line - 1))?;
+    let moves = match moves {
+        Some(moves) => parse_moves(moves.text).map_err(|e| e.below(moves.line - 1))?,
+        None => Vec::new(),
+    };
+    Ok((warehouse, moves))
 }
 
-fn print_map(map: &HashMap<(i32, i32), Object>, rows: i32, cols: i32, robot: (i32, i32)) {
-    for row in 0..rows {
-        for col in 0..cols {
-            if row == robot.0 && col == robot.1 {
-                print!("@");
-            } else if let Some(o) = map.get(&(row, col)) {
-                match o.kind {
-                    ObjectType::Wall => print!("#"),
-                    ObjectType::Box => print!("O"),
-                    ObjectType::BoxLeft => print!("["),
-                    ObjectType::BoxRight => print!("]"),
-                }
-            } else {
-                print!(".");
-            }
-        }
-        println!();
+fn run_sim(filename: &str, scale: i32) -> i32 {
+    simulate(filename, scale, |_, _| ()).0.gps_score()
+}
+
+/// Runs every move, calling `on_step` with the warehouse and how many moves have been
+/// made before each one. Returns the final warehouse and the number of moves.
+fn simulate(
+    filename: &str,
+    scale: i32,
+    mut on_step: impl FnMut(&Warehouse, usize),
+) -> (Warehouse, usize) {
+    let (mut warehouse, moves) =
+        parse(&read_input(filename), scale).unwrap_or_else(|e| panic!("{}: {}", filename, e));
+    for (step, &m) in moves.iter().enumerate() {
+        on_step(&warehouse, step);
+        warehouse.try_move(m);
     }
+    (warehouse, moves.len())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(
+        test_sample,
+        "sample.txt",
+        |f| run_sim(f, 1) => 10092,
+        |f| run_sim(f, 2) => 9021,
+        |f| run_sim(f, 3) => 8512,
+    );
+    aoc_test!(
+        test_input,
+        optional "input.txt",
+        |f| run_sim(f, 1),
+        |f| run_sim(f, 2),
+    );
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 20);
+            let (map, moves) = input.split_once("\n\n").unwrap();
+            assert_eq!(moves.chars().filter(|c| !c.is_whitespace()).count(), 200);
+            // Before anything moves the score is just where the boxes started
+            let start = map
+                .lines()
+                .enumerate()
+                .flat_map(|(r, line)| line.match_indices('O').map(move |(c, _)| 100 * r + c))
+                .sum::<usize>();
+            with_input(map, |map| assert_eq!(run_sim(map, 1), start as i32));
+            with_input(&input, |input| {
+                assert!(run_sim(input, 1) > 0);
+                assert!(run_sim(input, 2) > 0);
+            });
+        }
+    }
+
+    #[test]
+    fn test_frames() {
+        let mut recorder = Recorder::new(1000);
+        let (warehouse, moves) = simulate("src/sample.txt", 1, |w, step| {
+            recorder.record(w, &step.to_string())
+        });
+        let frames = recorder.finish(&warehouse, "done");
+        assert_eq!(moves, 700);
+        assert_eq!(frames.len(), 701);
+        let dir = std::env::temp_dir().join(format!("aoc-day-15-{}", std::process::id()));
+        utils::viz::write_frames(&frames, &dir).unwrap();
+        let read = |i: usize| std::fs::read_to_string(dir.join(format!("frame-{:05}.txt", i)));
+        assert!(read(0).unwrap().starts_with("0\n##########\n#..O..O.O#\n"));
+        // The end of the larger example from the puzzle
+        assert_eq!(
+            read(700).unwrap(),
+            "done
+##########
+#.O.O.OOO#
+#........#
+#OO......#
+#OO@.....#
+#O#.....O#
+#O.....OO#
+#O.....OO#
+#OO....OO#
+##########
+"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
     #[test]
-    fn test_part_1() {
-        let score = run_sim("src/sample-map.txt", "src/sample-moves.txt", false);
-        assert_eq!(score, 10092);
+    fn test_malformed() {
+        let error = parse("####\n#@.#\n####\n\n<>\n^x", 1).err().unwrap();
+        assert_eq!(error.to_string(), "line 6, column 2: `x` isn't a move");
+        let error = parse("#@#\n\n<\n\n>", 1).err().unwrap();
+        assert_eq!(error.message, "expected a map and then the moves");
+        let error = parse("######\n#@[].#\n#.[..#\n######", 1).err().unwrap();
+        assert_eq!(error.to_string(), "line 3, column 3: unmatched `[`");
+        let error = parse("######\n#@[[]#\n######", 1).err().unwrap();
+        assert_eq!(error.to_string(), "line 2, column 3: unmatched `[`");
+        let error = parse("\n######\n#@.]]#\n######\n\n<", 1).err().unwrap();
+        assert_eq!(error.to_string(), "line 3, column 4: unmatched `]`");
     }
 
     #[test]
-    fn test_part_2() {
-        let score = run_sim("src/sample-map.txt", "src/sample-moves.txt", true);
-        assert_eq!(score, 9021);
+    fn test_tall_boxes() {
+        // A 2x2 box sitting on top of two offset 2-wide boxes. Pushing the bottom-left
+        // box up should carry the big box with it, and the big box should drag nothing else.
+        let mut warehouse = Warehouse::new(8, 8, (6, 2));
+        (0..8).for_each(|c| warehouse.add_wall((0, c)));
+        let big = warehouse.add_box((2, 2), 2, 2);
+        let small = warehouse.add_box((4, 1), 2, 1);
+        let other = warehouse.add_box((5, 4), 2, 1);
+        assert!(warehouse.try_move(Direction::North));
+        assert!(warehouse.try_move(Direction::North));
+        assert_eq!(warehouse.boxes[big].position, (1, 2));
+        assert_eq!(warehouse.boxes[small].position, (3, 1));
+        assert_eq!(warehouse.boxes[other].position, (5, 4));
+        // The big box is against the wall now, so nothing else moves
+        assert!(!warehouse.try_move(Direction::North));
+        assert_eq!(warehouse.robot, (4, 2));
+        assert_eq!(warehouse.gps_score(), 102 + 301 + 504);
     }
 }
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub enum Direction {
     North,
@@ -9,7 +7,7 @@ pub enum Direction {
 }
 
 impl Direction {
-    pub fn to_move(&self) -> (i32, i32) {
+    pub fn to_move(self) -> (i32, i32) {
         match self {
             Direction::North => (-1, 0),
             Direction::South => (1, 0),
@@ -30,92 +28,45 @@ impl AddMove for (i32, i32) {
     }
 }
 
+/// What occupies a single cell of the warehouse
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
-pub enum ObjectType {
+pub enum Object {
     Wall,
-    BoxLeft,
-    BoxRight,
-    Box,
+    /// Part of the box at this index in the warehouse's box list
+    Box(usize),
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct Object {
-    pub(crate) kind: ObjectType,
+/// A rigid box covering `width` x `height` cells, anchored at its top-left cell.
+/// Every cell of the box moves together, so pushing any part of it pushes the whole thing.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub struct BoxBody {
+    pub position: (i32, i32),
+    pub width: i32,
+    pub height: i32,
 }
 
-impl Object {
-    pub fn new(kind: char) -> Object {
-        Object {
-            kind: match kind {
-                '#' => ObjectType::Wall,
-                'O' => ObjectType::Box,
-                '[' => ObjectType::BoxLeft,
-                ']' => ObjectType::BoxRight,
-                _ => panic!(),
-            },
+impl BoxBody {
+    pub fn new(position: (i32, i32), width: i32, height: i32) -> BoxBody {
+        assert!(
+            width > 0 && height > 0,
+            "boxes must cover at least one cell"
+        );
+        BoxBody {
+            position,
+            width,
+            height,
         }
     }
 
-    pub fn try_push(
-        &self,
-        direction: Direction,
-        position: (i32, i32),
-        map: HashMap<(i32, i32), Object>,
-        from_pair: bool
-    ) -> (HashMap<(i32, i32), Object>, bool) {
-        let mut map = map.clone();
-        if self.kind == ObjectType::Wall {
-            (map, false)
-        } else {
-            if !from_pair && (self.kind == ObjectType::BoxLeft || self.kind == ObjectType::BoxRight) &&
-                (direction == Direction::North || direction == Direction::South) {
-                // We're a left or right box so we have to move in tandem with our pair.
-                // This means we just have to only move if our pair can move too.
-                // `from_pair` indicates that we're coming from a pair so we shouldn't
-                // add the one we came from to the check. We only care about N/S moves here
-                // since E/W will be handled correctly by the normal logic
-                let p = match self.kind {
-                    ObjectType::BoxLeft => position.add(Direction::East),
-                    ObjectType::BoxRight => position.add(Direction::West),
-                    _ => unreachable!(),
-                };
-                if let Some(pair) = map.get(&p) {
-                    let (nm, push_result) = pair.try_push(direction, p, map.clone(), true);
-                    if !push_result {
-                        // Pair wasn't able to move, so the map doesn't change and we don't move
-                        return (map, false);
-                    }
-                    map = nm;
-                    // Pair was able to move, continue as normal
-                }
-            }
-            // Directions are (row, col)
-            let (row, col) = position.add(direction);
-            if let Some(next) = map.get(&(row, col)) {
-                if next.kind == ObjectType::Wall {
-                    // Next square is a wall, no moving here
-                    (map, false)
-                } else {
-                    // Next square is another box. Need to see if we can push the next box too
-                    let (mut new_map, push_result) =
-                        next.try_push(direction, (row, col), map.clone(), false);
-                    if push_result {
-                        // The next square over moved! We can move too.
-                        let o = new_map.remove(&(position.0, position.1)).unwrap();
-                        new_map.insert((row, col), o);
-                        (new_map, true)
-                    } else {
-                        // Next square didn't move, so we didn't move either
-                        (map, false)
-                    }
-                }
-            } else {
-                // Next square is empty, we can move!
-                let mut new_map = map.clone();
-                let o = new_map.remove(&(position.0, position.1)).unwrap();
-                new_map.insert((row, col), o);
-                (new_map, true)
-            }
-        }
+    /// All the (row, col) cells covered by this box
+    pub fn cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (0..self.height).flat_map(move |r| {
+            (0..self.width).map(move |c| (self.position.0 + r, self.position.1 + c))
+        })
+    }
+
+    /// GPS coordinate of the box, measured from its top-left cell
+    pub fn gps(&self) -> i32 {
+        100 * self.position.0 + self.position.1
     }
 }
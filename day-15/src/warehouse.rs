@@ -0,0 +1,125 @@
+use crate::object::{AddMove, BoxBody, Direction, Object};
+use std::collections::{HashMap, HashSet, VecDeque};
+use utils::viz::{Cell, Color, Grid};
+
+pub struct Warehouse {
+    map: HashMap<(i32, i32), Object>,
+    pub boxes: Vec<BoxBody>,
+    pub robot: (i32, i32),
+    pub rows: i32,
+    pub cols: i32,
+}
+
+impl Warehouse {
+    pub fn new(rows: i32, cols: i32, robot: (i32, i32)) -> Warehouse {
+        Warehouse {
+            map: HashMap::new(),
+            boxes: Vec::new(),
+            robot,
+            rows,
+            cols,
+        }
+    }
+
+    pub fn add_wall(&mut self, position: (i32, i32)) {
+        self.map.insert(position, Object::Wall);
+    }
+
+    /// Places a box with its top-left cell at `position`. Panics if the box would
+    /// overlap anything already in the warehouse.
+    pub fn add_box(&mut self, position: (i32, i32), width: i32, height: i32) -> usize {
+        let id = self.boxes.len();
+        let b = BoxBody::new(position, width, height);
+        for cell in b.cells() {
+            if self.map.insert(cell, Object::Box(id)).is_some() {
+                panic!(
+                    "box at {:?} overlaps an existing object at {:?}",
+                    position, cell
+                );
+            }
+        }
+        self.boxes.push(b);
+        id
+    }
+
+    /// Tries to move the robot one step, pushing every box in the way. Boxes are
+    /// collected breadth-first: each box we touch adds the cells in front of every
+    /// one of its own cells, so a push fans out through any boxes it overlaps.
+    /// If anything in the chain hits a wall nothing moves.
+    ///
+    /// Returns whether the robot moved.
+    pub fn try_move(&mut self, direction: Direction) -> bool {
+        let next_position = self.robot.add(direction);
+        let mut pushed: Vec<usize> = Vec::new();
+        let mut seen: HashSet<usize> = HashSet::new();
+        let mut to_check = VecDeque::from([next_position]);
+        while let Some(cell) = to_check.pop_front() {
+            match self.map.get(&cell) {
+                None => continue,
+                Some(Object::Wall) => return false,
+                Some(&Object::Box(id)) => {
+                    if !seen.insert(id) {
+                        continue;
+                    }
+                    pushed.push(id);
+                    for c in self.boxes[id].cells() {
+                        let next = c.add(direction);
+                        // Don't bother checking cells that belong to the box being pushed
+                        if self.map.get(&next) != Some(&Object::Box(id)) {
+                            to_check.push_back(next);
+                        }
+                    }
+                }
+            }
+        }
+        // Everything in the chain can move, so pick it all up and put it back down
+        // one step over. Clearing first means boxes don't trample each other.
+        for &id in &pushed {
+            for cell in self.boxes[id].cells() {
+                self.map.remove(&cell);
+            }
+        }
+        for &id in &pushed {
+            self.boxes[id].position = self.boxes[id].position.add(direction);
+            for cell in self.boxes[id].cells() {
+                self.map.insert(cell, Object::Box(id));
+            }
+        }
+        self.robot = next_position;
+        true
+    }
+
+    pub fn gps_score(&self) -> i32 {
+        self.boxes.iter().map(|b| b.gps()).sum()
+    }
+}
+
+impl Grid for Warehouse {
+    fn size(&self) -> (usize, usize) {
+        (self.rows as usize, self.cols as usize)
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Cell {
+        let (row, col) = (row as i32, col as i32);
+        if (row, col) == self.robot {
+            return Cell::new('@', Color::Red);
+        }
+        match self.map.get(&(row, col)) {
+            Some(Object::Wall) => Cell::new('#', Color::Gray),
+            Some(&Object::Box(id)) => {
+                let b = &self.boxes[id];
+                let glyph = if b.width == 1 {
+                    'O'
+                } else if col == b.position.1 {
+                    '['
+                } else if col == b.position.1 + b.width - 1 {
+                    ']'
+                } else {
+                    '='
+                };
+                Cell::new(glyph, Color::Yellow)
+            }
+            None => '.'.into(),
+        }
+    }
+}
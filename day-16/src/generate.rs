@@ -0,0 +1,21 @@
+use utils::generate::{maze, Rng};
+
+/// A `size` by `size` maze (rounded up to odd) with exactly one way through it, from `S`
+/// in the bottom left corner to `E` in the top right
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let maze = maze(rng, size, size);
+    let (rows, cols) = (maze.len(), maze[0].len());
+    let mut input = String::new();
+    for (r, row) in maze.iter().enumerate() {
+        for (c, &open) in row.iter().enumerate() {
+            input.push(match (r, c) {
+                _ if !open => '#',
+                (r, 1) if r == rows - 2 => 'S',
+                (1, c) if c == cols - 2 => 'E',
+                _ => '.',
+            });
+        }
+        input.push('\n');
+    }
+    input
+}
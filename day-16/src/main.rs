@@ -2,13 +2,72 @@ use array2d::Array2D;
 use itertools::Itertools;
 use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::hash::Hash;
-use utils::{read_lines, time};
+use std::path::Path;
+use utils::render::{self, Image};
+use utils::{read_lines, time, viz};
+
+mod generate;
 
 fn main() {
+    // `day-16 generate <seed> [size]` prints a random `size` square maze
+    if utils::generate::command(141, generate::input) {
+        return;
+    }
     let (map, start, finish) = parse_map("src/input.txt");
-    let (part1, time1) = time(|| cheapest_path(map.clone(), start, finish));
+    // `day-16 render <file.png>` draws the maze with the best paths through it
+    let args = std::env::args().collect::<Vec<_>>();
+    if let (Some("render"), Some(path)) = (args.get(1).map(|a| a.as_str()), args.get(2)) {
+        let (_, tiles) = cheapest_path(&map, start, finish, &CostModel::default()).unwrap();
+        let mut image = Image::render(&map, 4, render::cell_color);
+        let on_path = tiles.iter().map(|p| (p.0 as usize, p.1 as usize));
+        image.overlay(on_path, [240, 200, 50]);
+        image.overlay(
+            [start, finish].map(|p| (p.0 as usize, p.1 as usize)),
+            [230, 60, 50],
+        );
+        image.write_png(Path::new(path)).unwrap();
+        return;
+    }
+    let (part1, time1) =
+        time(|| cheapest_path(&map, start, finish, &CostModel::default()).unwrap());
     println!("Part 1: {} (took {} seconds)", part1.0, time1.as_secs_f64());
-    println!("Part 2: {} (computed at same time as 1)", part1.1);
+    println!("{}", render_best_paths(&map, &part1.1));
+    println!("Part 2: {} (computed at same time as 1)", part1.1.len());
+}
+
+/// What it costs the reindeer to get around the maze
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CostModel {
+    /// Moving one tile forward
+    step: i32,
+    /// Turning 90 degrees left or right
+    turn: i32,
+    /// Turning all the way around
+    u_turn: i32,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            step: 1,
+            turn: 1000,
+            u_turn: 2000,
+        }
+    }
+}
+
+impl CostModel {
+    /// Cost of stepping in direction `to` while facing `from`
+    fn cost(&self, from: Direction, to: Direction) -> i32 {
+        self.step
+            + if from == to {
+                0
+            } else if from.opposite() == to {
+                self.u_turn
+            } else {
+                self.turn
+            }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -19,6 +78,18 @@ enum Cell {
     Finish,
 }
 
+impl From<Cell> for viz::Cell {
+    fn from(cell: Cell) -> Self {
+        match cell {
+            Cell::Wall => '#',
+            Cell::Empty => '.',
+            Cell::Start => 'S',
+            Cell::Finish => 'E',
+        }
+        .into()
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Direction {
     North,
@@ -27,12 +98,27 @@ enum Direction {
     West,
 }
 
+impl Direction {
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Move(Direction, Point, i32, Vec<Point>);
+struct Move(Direction, Point, i32);
 
 impl Ord for Move {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.2.cmp(&self.2).then_with(|| self.1.cmp(&other.1))
+        other
+            .2
+            .cmp(&self.2)
+            .then_with(|| self.1.cmp(&other.1))
+            .then_with(|| (self.0 as u8).cmp(&(other.0 as u8)))
     }
 }
 
@@ -108,61 +194,160 @@ fn parse_map(filename: &str) -> (Array2D<Cell>, Point, Point) {
     )
 }
 
-fn cheapest_path(map: Array2D<Cell>, start: Point, finish: Point) -> (i32, i32) {
-    let mut distances: HashMap<(Point, Direction), i32> = HashMap::new();
+type State = (Point, Direction);
+
+/// Dijkstra over (position, facing) states. Rather than carrying the path along with every
+/// move we remember every predecessor that reached a state at its best cost, then walk those
+/// back from each optimal end state to find all the tiles on any best path.
+///
+/// Returns the best cost and the set of tiles on any best path, or `None` if the finish
+/// can't be reached.
+fn cheapest_path(
+    map: &Array2D<Cell>,
+    start: Point,
+    finish: Point,
+    costs: &CostModel,
+) -> Option<(i32, HashSet<Point>)> {
+    let mut distances: HashMap<State, i32> = HashMap::new();
+    let mut predecessors: HashMap<State, Vec<State>> = HashMap::new();
     let mut heap = BinaryHeap::new();
-    heap.push(Move(Direction::East, start, 0, vec![start]));
-    let mut tiles_in_best_paths = HashSet::<Point>::new();
-    let mut best = i32::MAX;
-
-    while let Some(Move {
-        0: direction,
-        1: point,
-        2: cost,
-        3: path,
-    }) = heap.pop()
-    {
-        if let Some(&c) = distances.get(&(point, direction)) {
-            if cost > c {
-                continue;
-            } else {
-                distances.insert((point, direction), cost);
-            }
-        } else {
-            distances.insert((point, direction), cost);
+    distances.insert((start, Direction::East), 0);
+    heap.push(Move(Direction::East, start, 0));
+
+    while let Some(Move(direction, point, cost)) = heap.pop() {
+        if cost > distances[&(point, direction)] {
+            // Stale heap entry, we've already found a cheaper way here
+            continue;
         }
-        if point == finish && cost <= best {
-            best = cost;
-            for tile in path {
-                tiles_in_best_paths.insert(tile);
-            }
+        if point == finish {
             continue;
         }
-
-        for edge in &point.orthogonal_neighbors(&map) {
-            let d = point.direction(*edge);
-            let mut new_cost = 1;
-            if d != direction {
-                new_cost += 1000;
+        for edge in point.orthogonal_neighbors(map) {
+            let d = point.direction(edge);
+            let next_cost = cost + costs.cost(direction, d);
+            let best = distances.entry((edge, d)).or_insert(i32::MAX);
+            if next_cost < *best {
+                *best = next_cost;
+                predecessors.insert((edge, d), vec![(point, direction)]);
+                heap.push(Move(d, edge, next_cost));
+            } else if next_cost == *best {
+                // Another equally good way in, which matters for counting tiles
+                predecessors
+                    .entry((edge, d))
+                    .or_default()
+                    .push((point, direction));
             }
-            let mut new_path = path.clone();
-            new_path.push(*edge);
-            let next = Move(d, *edge, cost + new_cost, new_path);
-            heap.push(next);
         }
     }
-    (best, tiles_in_best_paths.len() as i32)
+
+    let best = distances
+        .iter()
+        .filter(|((p, _), _)| *p == finish)
+        .map(|(_, &c)| c)
+        .min()?;
+    let mut to_visit = distances
+        .iter()
+        .filter(|(&(p, _), &c)| p == finish && c == best)
+        .map(|(&s, _)| s)
+        .collect_vec();
+    let mut visited = HashSet::<State>::new();
+    while let Some(state) = to_visit.pop() {
+        if !visited.insert(state) {
+            continue;
+        }
+        if let Some(prev) = predecessors.get(&state) {
+            to_visit.extend(prev.iter().copied());
+        }
+    }
+    Some((best, visited.into_iter().map(|(p, _)| p).collect()))
+}
+
+/// Draws the maze with every tile on a best path marked as `O`
+fn render_best_paths(map: &Array2D<Cell>, tiles: &HashSet<Point>) -> String {
+    map.rows_iter()
+        .enumerate()
+        .map(|(row, cells)| {
+            cells
+                .enumerate()
+                .map(|(col, cell)| {
+                    if tiles.contains(&Point(row as i32, col as i32)) {
+                        'O'
+                    } else {
+                        viz::Cell::from(*cell).glyph
+                    }
+                })
+                .collect::<String>()
+        })
+        .join("\n")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    fn part_1(filename: &str) -> i32 {
+        let (map, start, finish) = parse_map(filename);
+        cheapest_path(&map, start, finish, &CostModel::default())
+            .unwrap()
+            .0
+    }
+
+    fn part_2(filename: &str) -> usize {
+        let (map, start, finish) = parse_map(filename);
+        cheapest_path(&map, start, finish, &CostModel::default())
+            .unwrap()
+            .1
+            .len()
+    }
+
+    aoc_test!(test_sample, "test-input.txt", part_1 => 11048, part_2 => 64);
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
+
+    #[test]
+    fn test_generated() {
+        let free_turns = CostModel {
+            step: 1,
+            turn: 0,
+            u_turn: 0,
+        };
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 41);
+            let (map, start, finish) = with_input(&input, parse_map);
+            let (cost, tiles) = cheapest_path(&map, start, finish, &CostModel::default()).unwrap();
+            let (steps, _) = cheapest_path(&map, start, finish, &free_turns).unwrap();
+            // There's only one way through, so the best tiles are just that path, and the
+            // reindeer turns at least once to get from heading east to the top
+            assert_eq!(tiles.len() as i32, steps + 1);
+            assert!(cost > steps + 1000);
+            assert_eq!((cost - steps) % 1000, 0);
+        }
+    }
+
+    #[test]
+    fn test_small_sample() {
+        let (map, start, finish) = parse_map("src/sample.txt");
+        let (cost, tiles) = cheapest_path(&map, start, finish, &CostModel::default()).unwrap();
+        assert_eq!(cost, 7036);
+        assert_eq!(tiles.len(), 45);
+        let rendered = render_best_paths(&map, &tiles);
+        assert_eq!(rendered.matches('O').count(), 45);
+        assert_eq!(rendered.lines().nth(1).unwrap(), "#.......#....O#");
+        assert_eq!(rendered.lines().nth(13).unwrap(), "#O..#.....#OOO#");
+    }
 
     #[test]
-    fn test() {
-        let (map, start, finish) = parse_map("src/test-input.txt");
-        let result = cheapest_path(map, start, finish);
-        assert_eq!(result.0, 11048);
-        assert_eq!(result.1, 64);
+    fn test_custom_costs() {
+        // With free turns this is just the shortest walk through the maze
+        let (map, start, finish) = parse_map("src/sample.txt");
+        let costs = CostModel {
+            step: 1,
+            turn: 0,
+            u_turn: 0,
+        };
+        let (cost, _) = cheapest_path(&map, start, finish, &costs).unwrap();
+        assert_eq!(cost, 28);
     }
 }
@@ -0,0 +1,213 @@
+use crate::instruction::Instruction;
+use itertools::Itertools;
+use num_traits::ToPrimitive;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+
+/// How an instruction interprets its operand
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum OperandKind {
+    /// 0-3 are literals, 4-6 are registers A-C, 7 is reserved
+    Combo,
+    Literal,
+    /// A literal operand that's an address to jump to
+    Jump,
+    /// The operand is read but does nothing
+    Ignored,
+}
+
+impl Instruction {
+    pub fn operand_kind(&self) -> OperandKind {
+        match self {
+            Instruction::adv
+            | Instruction::bst
+            | Instruction::out
+            | Instruction::bdv
+            | Instruction::cdv => OperandKind::Combo,
+            Instruction::bxl => OperandKind::Literal,
+            Instruction::jnz => OperandKind::Jump,
+            Instruction::bxc => OperandKind::Ignored,
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Instruction> {
+        match mnemonic {
+            "adv" => Some(Instruction::adv),
+            "bxl" => Some(Instruction::bxl),
+            "bst" => Some(Instruction::bst),
+            "jnz" => Some(Instruction::jnz),
+            "bxc" => Some(Instruction::bxc),
+            "out" => Some(Instruction::out),
+            "bdv" => Some(Instruction::bdv),
+            "cdv" => Some(Instruction::cdv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum AsmError {
+    UnknownMnemonic { line: usize, mnemonic: String },
+    BadOperand { line: usize, operand: String },
+    MissingOperand { line: usize },
+    UndefinedLabel { line: usize, label: String },
+    DuplicateLabel { line: usize, label: String },
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsmError::UnknownMnemonic { line, mnemonic } => {
+                write!(f, "line {}: unknown mnemonic `{}`", line, mnemonic)
+            }
+            AsmError::BadOperand { line, operand } => {
+                write!(f, "line {}: bad operand `{}`", line, operand)
+            }
+            AsmError::MissingOperand { line } => write!(f, "line {}: missing operand", line),
+            AsmError::UndefinedLabel { line, label } => {
+                write!(f, "line {}: undefined label `{}`", line, label)
+            }
+            AsmError::DuplicateLabel { line, label } => {
+                write!(f, "line {}: label `{}` is already defined", line, label)
+            }
+        }
+    }
+}
+
+/// Flattens a program back into the comma separated numbers the puzzle uses
+pub fn encode(instructions: &[(Instruction, u64)]) -> Vec<u64> {
+    instructions
+        .iter()
+        .flat_map(|(i, op)| [i.to_u64().unwrap(), *op])
+        .collect_vec()
+}
+
+fn label_for(address: u64) -> String {
+    format!("L{}", address)
+}
+
+fn format_combo(operand: u64) -> String {
+    match operand {
+        4 => "A".to_string(),
+        5 => "B".to_string(),
+        6 => "C".to_string(),
+        // 0-3 are literals, and 7 is reserved so we just show it as-is
+        _ => operand.to_string(),
+    }
+}
+
+/// Turns a program into one mnemonic per line. Combo operands are shown as the register
+/// or literal they decode to, and every even `jnz` target gets a label. Odd targets land in
+/// the middle of an instruction so they're left as raw numbers.
+pub fn disassemble(instructions: &[(Instruction, u64)]) -> String {
+    let targets: BTreeSet<u64> = instructions
+        .iter()
+        .filter(|(i, op)| i.operand_kind() == OperandKind::Jump && op % 2 == 0)
+        .map(|(_, op)| *op)
+        .collect();
+    let mut lines: Vec<String> = Vec::new();
+    for (idx, (inst, operand)) in instructions.iter().enumerate() {
+        let address = idx as u64 * 2;
+        if targets.contains(&address) {
+            lines.push(format!("{}:", label_for(address)));
+        }
+        let operand = match inst.operand_kind() {
+            OperandKind::Combo => format_combo(*operand),
+            OperandKind::Literal => operand.to_string(),
+            OperandKind::Jump if operand % 2 == 0 => label_for(*operand),
+            OperandKind::Jump => operand.to_string(),
+            OperandKind::Ignored if *operand == 0 => String::new(),
+            OperandKind::Ignored => operand.to_string(),
+        };
+        lines.push(format!("    {:?} {}", inst, operand).trim_end().to_string());
+    }
+    // Jumps past the end of the program halt it, label them so they survive a round trip
+    for &t in targets.range(instructions.len() as u64 * 2..) {
+        lines.push(format!("{}:", label_for(t)));
+    }
+    lines.join("\n")
+}
+
+fn parse_operand(kind: OperandKind, operand: &str, line: usize) -> Result<u64, AsmError> {
+    let bad = || AsmError::BadOperand {
+        line,
+        operand: operand.to_string(),
+    };
+    let value = match (kind, operand) {
+        (OperandKind::Combo, "A") => 4,
+        (OperandKind::Combo, "B") => 5,
+        (OperandKind::Combo, "C") => 6,
+        _ => operand.parse::<u64>().map_err(|_| bad())?,
+    };
+    if value > 7 {
+        return Err(bad());
+    }
+    Ok(value)
+}
+
+/// Assembles the text produced by `disassemble` (or written by hand) back into a program.
+/// Labels are `name:` on their own line, `;` starts a comment, and `jnz` takes either a
+/// label or a literal address. `bxc` may leave its operand off, in which case it's 0.
+pub fn assemble(source: &str) -> Result<Vec<(Instruction, u64)>, AsmError> {
+    // First pass finds where every label points, second pass emits instructions
+    let mut labels: HashMap<&str, u64> = HashMap::new();
+    let mut statements: Vec<(usize, &str)> = Vec::new();
+    for (idx, line) in source.lines().enumerate() {
+        let line_no = idx + 1;
+        let code = line.split(';').next().unwrap().trim();
+        if code.is_empty() {
+            continue;
+        }
+        if let Some(label) = code.strip_suffix(':') {
+            let address = statements.len() as u64 * 2;
+            if labels.insert(label.trim(), address).is_some() {
+                return Err(AsmError::DuplicateLabel {
+                    line: line_no,
+                    label: label.trim().to_string(),
+                });
+            }
+        } else {
+            statements.push((line_no, code));
+        }
+    }
+
+    let mut program: Vec<(Instruction, u64)> = Vec::new();
+    for (line, code) in statements {
+        let mut parts = code.split_whitespace();
+        let mnemonic = parts.next().unwrap();
+        let inst = Instruction::from_mnemonic(mnemonic).ok_or(AsmError::UnknownMnemonic {
+            line,
+            mnemonic: mnemonic.to_string(),
+        })?;
+        let operand = parts.next();
+        if let Some(extra) = parts.next() {
+            return Err(AsmError::BadOperand {
+                line,
+                operand: extra.to_string(),
+            });
+        }
+        let value = match (inst.operand_kind(), operand) {
+            (OperandKind::Ignored, None) => 0,
+            (_, None) => return Err(AsmError::MissingOperand { line }),
+            (OperandKind::Jump, Some(target))
+                if !target.starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                let address = *labels.get(target).ok_or(AsmError::UndefinedLabel {
+                    line,
+                    label: target.to_string(),
+                })?;
+                // Operands are 3 bits, so we can only jump to the first four instructions
+                if address > 7 {
+                    return Err(AsmError::BadOperand {
+                        line,
+                        operand: target.to_string(),
+                    });
+                }
+                address
+            }
+            (kind, Some(op)) => parse_operand(kind, op, line)?,
+        };
+        program.push((inst, value));
+    }
+    Ok(program)
+}
@@ -0,0 +1,332 @@
+use crate::asm::disassemble;
+use crate::instruction::{Execute, Executor, Fault, Instruction};
+use itertools::Itertools;
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Register {
+    A,
+    B,
+    C,
+}
+
+impl Register {
+    fn read(&self, exec: &Executor) -> u64 {
+        match self {
+            Register::A => exec.register_a,
+            Register::B => exec.register_b,
+            Register::C => exec.register_c,
+        }
+    }
+
+    fn parse(name: &str) -> Option<Register> {
+        match name {
+            "A" | "a" => Some(Register::A),
+            "B" | "b" => Some(Register::B),
+            "C" | "c" => Some(Register::C),
+            _ => None,
+        }
+    }
+}
+
+/// Why the debugger handed control back
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum StopReason {
+    /// Executed a single instruction and nothing interesting happened
+    Stepped,
+    /// The instruction pointer ran off the end of the program
+    Halted,
+    /// About to execute the instruction at this instruction pointer
+    Breakpoint(u64),
+    /// A watched register changed value
+    Watchpoint {
+        register: Register,
+        old: u64,
+        new: u64,
+    },
+    /// Used up the whole cycle budget without halting
+    CycleLimit,
+    /// The instruction at the current instruction pointer can't run
+    Fault(Fault),
+}
+
+/// One executed instruction. The instruction pointer is where the instruction was fetched
+/// from, the registers are what they held after it ran.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct TraceEntry {
+    pub cycle: u64,
+    pub instruction_pointer: u64,
+    pub instruction: Instruction,
+    pub operand: u64,
+    pub register_a: u64,
+    pub register_b: u64,
+    pub register_c: u64,
+    pub output: Option<u64>,
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>6}  ip={:<3} {:?} {}  A={} B={} C={}",
+            self.cycle,
+            self.instruction_pointer,
+            self.instruction,
+            self.operand,
+            self.register_a,
+            self.register_b,
+            self.register_c
+        )?;
+        if let Some(out) = self.output {
+            write!(f, "  out={}", out)?;
+        }
+        Ok(())
+    }
+}
+
+/// How many trace entries the debugger keeps by default, so a long run doesn't hold on to
+/// one for every cycle
+pub const TRACE_LIMIT: usize = 10_000;
+
+pub struct Debugger {
+    exec: Executor,
+    program: Vec<(Instruction, u64)>,
+    breakpoints: HashSet<u64>,
+    watchpoints: Vec<Register>,
+    max_cycles: Option<u64>,
+    cycles: u64,
+    /// The most recent entries, oldest first
+    trace: VecDeque<TraceEntry>,
+    trace_limit: usize,
+    output: Vec<u64>,
+    /// The breakpoint `run` last stopped at, if nothing has run since
+    stopped_at: Option<u64>,
+}
+
+impl Debugger {
+    pub fn new(exec: Executor, program: Vec<(Instruction, u64)>) -> Debugger {
+        Debugger {
+            exec,
+            program,
+            breakpoints: HashSet::new(),
+            watchpoints: Vec::new(),
+            max_cycles: None,
+            cycles: 0,
+            trace: VecDeque::new(),
+            trace_limit: TRACE_LIMIT,
+            output: Vec::new(),
+            stopped_at: None,
+        }
+    }
+
+    /// Breakpoints are on instruction pointers, i.e. instruction indexes and not the
+    /// doubled addresses `jnz` uses
+    pub fn add_breakpoint(&mut self, instruction_pointer: u64) {
+        self.breakpoints.insert(instruction_pointer);
+    }
+
+    pub fn remove_breakpoint(&mut self, instruction_pointer: u64) {
+        self.breakpoints.remove(&instruction_pointer);
+    }
+
+    pub fn watch(&mut self, register: Register) {
+        if !self.watchpoints.contains(&register) {
+            self.watchpoints.push(register);
+        }
+    }
+
+    pub fn unwatch(&mut self, register: Register) {
+        self.watchpoints.retain(|&r| r != register);
+    }
+
+    /// Stop with `StopReason::CycleLimit` once this many instructions have run in total
+    pub fn set_max_cycles(&mut self, max_cycles: Option<u64>) {
+        self.max_cycles = max_cycles;
+    }
+
+    /// Keep only the last `limit` executed instructions in the trace
+    pub fn set_trace_limit(&mut self, limit: usize) {
+        self.trace_limit = limit;
+        while self.trace.len() > limit {
+            self.trace.pop_front();
+        }
+    }
+
+    pub fn executor(&self) -> &Executor {
+        &self.exec
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// The last instructions executed, up to the trace limit
+    pub fn trace(&self) -> &VecDeque<TraceEntry> {
+        &self.trace
+    }
+
+    pub fn output(&self) -> &[u64] {
+        &self.output
+    }
+
+    pub fn halted(&self) -> bool {
+        self.exec.instruction_pointer as usize >= self.program.len()
+    }
+
+    /// Executes exactly one instruction
+    pub fn step(&mut self) -> StopReason {
+        if self.halted() {
+            return StopReason::Halted;
+        }
+        if self.max_cycles.is_some_and(|max| self.cycles >= max) {
+            return StopReason::CycleLimit;
+        }
+        let before = self.exec;
+        let (inst, op) = self.program[self.exec.instruction_pointer as usize];
+        let (exec, out) = match inst.execute(op, self.exec) {
+            Ok(result) => result,
+            Err(fault) => return StopReason::Fault(fault),
+        };
+        self.exec = exec;
+        self.cycles += 1;
+        self.stopped_at = None;
+        if let Some(out) = out {
+            self.output.push(out);
+        }
+        self.trace.push_back(TraceEntry {
+            cycle: self.cycles,
+            instruction_pointer: before.instruction_pointer,
+            instruction: inst,
+            operand: op,
+            register_a: exec.register_a,
+            register_b: exec.register_b,
+            register_c: exec.register_c,
+            output: out,
+        });
+        if self.trace.len() > self.trace_limit {
+            self.trace.pop_front();
+        }
+        for &register in &self.watchpoints {
+            let (old, new) = (register.read(&before), register.read(&self.exec));
+            if old != new {
+                return StopReason::Watchpoint { register, old, new };
+            }
+        }
+        if self.halted() {
+            StopReason::Halted
+        } else {
+            StopReason::Stepped
+        }
+    }
+
+    /// Runs until the program halts, a breakpoint or watchpoint is hit, or the cycle budget
+    /// runs out. A breakpoint on the instruction we're sitting on fires straight away, unless
+    /// it's the one we just stopped at, so that continuing from a breakpoint makes progress.
+    pub fn run(&mut self) -> StopReason {
+        loop {
+            let ip = self.exec.instruction_pointer;
+            if self.breakpoints.contains(&ip) && self.stopped_at != Some(ip) {
+                self.stopped_at = Some(ip);
+                return StopReason::Breakpoint(ip);
+            }
+            match self.step() {
+                StopReason::Stepped => continue,
+                reason => return reason,
+            }
+        }
+    }
+
+    fn registers(&self) -> String {
+        let exec = self.executor();
+        format!(
+            "ip={} A={} B={} C={} cycles={}",
+            exec.instruction_pointer,
+            exec.register_a,
+            exec.register_b,
+            exec.register_c,
+            self.cycles()
+        )
+    }
+
+    /// A tiny command loop over any reader/writer pair, so it works on stdin or in tests.
+    ///
+    /// - `s [n]` / `step [n]`: step one (or n) instructions
+    /// - `c` / `continue`: run to the next stop
+    /// - `b <ip>` / `d <ip>`: add or delete a breakpoint
+    /// - `w <reg>` / `u <reg>`: watch or unwatch a register
+    /// - `limit <n>` / `limit off`: set the cycle budget
+    /// - `keep <n>`: keep only the last n trace entries
+    /// - `regs`, `out`, `trace [n]`, `list`
+    /// - `q` / `quit`
+    pub fn repl<R: BufRead, W: Write>(&mut self, input: R, mut out: W) -> std::io::Result<()> {
+        write!(out, "(dbg) ")?;
+        out.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            let parts = line.split_whitespace().collect_vec();
+            let arg = parts.get(1).copied();
+            match parts.first().copied() {
+                None => (),
+                Some("q" | "quit") => break,
+                Some("s" | "step") => {
+                    let count = arg.and_then(|a| a.parse::<u64>().ok()).unwrap_or(1);
+                    for _ in 0..count {
+                        let reason = self.step();
+                        writeln!(
+                            out,
+                            "{}",
+                            self.trace.back().map(|t| t.to_string()).unwrap_or_default()
+                        )?;
+                        if reason != StopReason::Stepped {
+                            writeln!(out, "{:?}", reason)?;
+                            break;
+                        }
+                    }
+                }
+                Some("c" | "continue") => {
+                    let reason = self.run();
+                    writeln!(out, "{:?}", reason)?;
+                    writeln!(out, "{}", self.registers())?;
+                }
+                Some(cmd @ ("b" | "d")) => match arg.and_then(|a| a.parse::<u64>().ok()) {
+                    Some(ip) if cmd == "b" => self.add_breakpoint(ip),
+                    Some(ip) => self.remove_breakpoint(ip),
+                    None => writeln!(out, "usage: {} <ip>", cmd)?,
+                },
+                Some(cmd @ ("w" | "u")) => match arg.and_then(Register::parse) {
+                    Some(r) if cmd == "w" => self.watch(r),
+                    Some(r) => self.unwatch(r),
+                    None => writeln!(out, "usage: {} <A|B|C>", cmd)?,
+                },
+                Some("limit") => match arg {
+                    Some("off") => self.set_max_cycles(None),
+                    Some(n) if n.parse::<u64>().is_ok() => self.set_max_cycles(n.parse().ok()),
+                    _ => writeln!(out, "usage: limit <n|off>")?,
+                },
+                Some("keep") => match arg.and_then(|a| a.parse::<usize>().ok()) {
+                    Some(n) => self.set_trace_limit(n),
+                    None => writeln!(out, "usage: keep <n>")?,
+                },
+                Some("regs") => writeln!(out, "{}", self.registers())?,
+                Some("out") => writeln!(out, "{}", self.output().iter().join(","))?,
+                Some("trace") => {
+                    let trace = self.trace();
+                    let count = arg
+                        .and_then(|a| a.parse::<usize>().ok())
+                        .unwrap_or(trace.len());
+                    for t in trace.range(trace.len().saturating_sub(count)..) {
+                        writeln!(out, "{}", t)?;
+                    }
+                }
+                Some("list") => writeln!(out, "{}", disassemble(&self.program))?,
+                Some(other) => writeln!(out, "unknown command `{}`", other)?,
+            }
+            write!(out, "(dbg) ")?;
+            out.flush()?;
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+use utils::generate::Rng;
+
+/// A program shaped like the real ones, which chew through register A three bits at a time,
+/// printing one value per chunk until it runs out. Register A is `size` bits long (at most
+/// 63), so the program prints about `size / 3` values.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let bits = size.clamp(1, 63);
+    let a = rng.next_u64() >> (64 - bits) | 1 << (bits - 1);
+    // bst A, bxl x, cdv B, then bxl y, bxc and adv 3 in any order, then out B and jnz 0
+    let mut middle = vec![[1, rng.range(0..8)], [4, rng.range(0..8)], [0, 3]];
+    rng.shuffle(&mut middle);
+    let mut program = vec![2, 4, 1, rng.range(0..8), 7, 5];
+    program.extend(middle.iter().flatten());
+    program.extend([5, 5, 3, 0]);
+    let program = program.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    format!(
+        "Register A: {}\nRegister B: 0\nRegister C: 0\n\nProgram: {}\n",
+        a,
+        program.join(",")
+    )
+}
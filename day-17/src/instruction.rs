@@ -1,5 +1,9 @@
+use crate::asm::OperandKind;
+use num_traits::FromPrimitive;
+use std::fmt::Display;
+
 #[allow(non_camel_case_types)]
-#[derive(FromPrimitive, Debug, ToPrimitive, Clone, Copy)]
+#[derive(FromPrimitive, Debug, ToPrimitive, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
     adv = 0,
     bxl = 1,
@@ -11,51 +15,216 @@ pub enum Instruction {
     cdv = 7,
 }
 
+/// Everything that can go wrong running a program. Each fault records the instruction
+/// pointer of the instruction that caused it.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Fault {
+    /// Combo operand 7 is reserved and doesn't mean anything
+    ReservedOperand { instruction_pointer: u64 },
+    /// Dividing by 2^n where n doesn't fit in a u64
+    ShiftOverflow {
+        instruction_pointer: u64,
+        amount: u64,
+    },
+    /// `jnz` to an odd address, which is the middle of an instruction
+    MisalignedJump {
+        instruction_pointer: u64,
+        target: u64,
+    },
+    /// Opcodes are 3 bits
+    OpcodeOutOfRange {
+        instruction_pointer: u64,
+        opcode: u64,
+    },
+    /// Operands are 3 bits too
+    OperandOutOfRange {
+        instruction_pointer: u64,
+        operand: u64,
+    },
+    /// The program ends with an opcode and no operand
+    MissingOperand { instruction_pointer: u64 },
+    /// Still going after this many instructions, so probably never going to halt
+    CycleLimit {
+        instruction_pointer: u64,
+        cycles: u64,
+    },
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fault::ReservedOperand {
+                instruction_pointer,
+            } => {
+                write!(f, "ip {}: reserved combo operand 7", instruction_pointer)
+            }
+            Fault::ShiftOverflow {
+                instruction_pointer,
+                amount,
+            } => write!(
+                f,
+                "ip {}: can't divide by 2^{}",
+                instruction_pointer, amount
+            ),
+            Fault::MisalignedJump {
+                instruction_pointer,
+                target,
+            } => write!(
+                f,
+                "ip {}: jump to odd address {}",
+                instruction_pointer, target
+            ),
+            Fault::OpcodeOutOfRange {
+                instruction_pointer,
+                opcode,
+            } => write!(
+                f,
+                "ip {}: opcode {} out of range",
+                instruction_pointer, opcode
+            ),
+            Fault::OperandOutOfRange {
+                instruction_pointer,
+                operand,
+            } => write!(
+                f,
+                "ip {}: operand {} out of range",
+                instruction_pointer, operand
+            ),
+            Fault::MissingOperand {
+                instruction_pointer,
+            } => write!(f, "ip {}: missing operand", instruction_pointer),
+            Fault::CycleLimit {
+                instruction_pointer,
+                cycles,
+            } => write!(
+                f,
+                "ip {}: didn't halt within {} cycles",
+                instruction_pointer, cycles
+            ),
+        }
+    }
+}
+
+impl Instruction {
+    /// Decodes raw program numbers into instructions, checking that every opcode and
+    /// operand fits in 3 bits
+    pub fn decode_program(numbers: &[u64]) -> Result<Vec<(Instruction, u64)>, Fault> {
+        numbers
+            .chunks(2)
+            .enumerate()
+            .map(|(ip, chunk)| {
+                let instruction_pointer = ip as u64;
+                let inst = Instruction::from_u64(chunk[0]).ok_or(Fault::OpcodeOutOfRange {
+                    instruction_pointer,
+                    opcode: chunk[0],
+                })?;
+                match chunk.get(1) {
+                    None => Err(Fault::MissingOperand {
+                        instruction_pointer,
+                    }),
+                    Some(&operand) if operand > 7 => Err(Fault::OperandOutOfRange {
+                        instruction_pointer,
+                        operand,
+                    }),
+                    Some(&operand) => Ok((inst, operand)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Checks a program for faults that can be spotted without running it: reserved and
+/// out of range operands, and jumps into the middle of an instruction. Shift overflows
+/// depend on register values, so those only show up at runtime.
+pub fn validate(program: &[(Instruction, u64)]) -> Vec<Fault> {
+    let mut faults = Vec::new();
+    for (ip, &(inst, operand)) in program.iter().enumerate() {
+        let instruction_pointer = ip as u64;
+        if operand > 7 {
+            faults.push(Fault::OperandOutOfRange {
+                instruction_pointer,
+                operand,
+            });
+        } else if inst.operand_kind() == OperandKind::Combo && operand == 7 {
+            faults.push(Fault::ReservedOperand {
+                instruction_pointer,
+            });
+        } else if inst == Instruction::jnz && operand % 2 == 1 {
+            faults.push(Fault::MisalignedJump {
+                instruction_pointer,
+                target: operand,
+            });
+        }
+    }
+    faults
+}
+
 pub trait Execute {
-    fn execute(&self, operand: u64, exec: Executor) -> (Executor, Option<u64>);
+    fn execute(&self, operand: u64, exec: Executor) -> Result<(Executor, Option<u64>), Fault>;
 }
 
 impl Execute for Instruction {
-    fn execute(&self, operand: u64, exec: Executor) -> (Executor, Option<u64>) {
+    fn execute(&self, operand: u64, exec: Executor) -> Result<(Executor, Option<u64>), Fault> {
         let mut exec = exec;
         let mut output: Option<u64> = None;
-        let handle_combo = || -> u64 {
+        let instruction_pointer = exec.instruction_pointer;
+        let handle_combo = || -> Result<u64, Fault> {
             // Combo operands 0 through 3 represent literal values 0 through 3.
             // Combo operand 4 represents the value of register A.
             // Combo operand 5 represents the value of register B.
             // Combo operand 6 represents the value of register C.
             // Combo operand 7 is reserved and will not appear in valid programs.
             match operand {
-                0 => 0,
-                1 => 1,
-                2 => 2,
-                3 => 3,
-                4 => exec.register_a,
-                5 => exec.register_b,
-                6 => exec.register_c,
-                _ => unreachable!("No other combo operands are valid!"),
+                0..=3 => Ok(operand),
+                4 => Ok(exec.register_a),
+                5 => Ok(exec.register_b),
+                6 => Ok(exec.register_c),
+                7 => Err(Fault::ReservedOperand {
+                    instruction_pointer,
+                }),
+                _ => Err(Fault::OperandOutOfRange {
+                    instruction_pointer,
+                    operand,
+                }),
             }
         };
+        // A / 2^n is just a shift, as long as n fits
+        let divide = || -> Result<u64, Fault> {
+            let amount = handle_combo()?;
+            u32::try_from(amount)
+                .ok()
+                .and_then(|n| exec.register_a.checked_shr(n))
+                .ok_or(Fault::ShiftOverflow {
+                    instruction_pointer,
+                    amount,
+                })
+        };
 
         match self {
             Instruction::adv => {
                 // Divide A / 2^(combo operand), truncate to int and store in A, increment inst pointer by 1
-                exec.register_a = exec.register_a / 2u64.pow(handle_combo() as u32);
+                exec.register_a = divide()?;
                 exec.instruction_pointer += 1;
             }
             Instruction::bxl => {
                 // Bitwise XOR of B and literal operand, store in B, increment inst pointer by 1
-                exec.register_b = exec.register_b ^ operand;
+                exec.register_b ^= operand;
                 exec.instruction_pointer += 1;
             }
             Instruction::bst => {
                 // Combo operand % 8, store in B, increment inst pointer by 1
-                exec.register_b = handle_combo() % 8;
+                exec.register_b = handle_combo()? % 8;
                 exec.instruction_pointer += 1;
             }
             Instruction::jnz => {
                 // If A == 0 nop ; if A != 0 jmp inst pointer to literal operand
                 if exec.register_a != 0 {
+                    if operand % 2 == 1 {
+                        return Err(Fault::MisalignedJump {
+                            instruction_pointer,
+                            target: operand,
+                        });
+                    }
                     exec.instruction_pointer = operand / 2;
                 } else {
                     exec.instruction_pointer += 1;
@@ -63,26 +232,26 @@ impl Execute for Instruction {
             }
             Instruction::bxc => {
                 // Bitwise XOR of B and C, store in B (ignore operand), increment inst pointer by 1
-                exec.register_b = exec.register_b ^ exec.register_c;
+                exec.register_b ^= exec.register_c;
                 exec.instruction_pointer += 1;
             }
             Instruction::out => {
                 // Combo operand % 8, output to terminal comma separated, increment inst pointer by 1
-                output = Some(handle_combo() % 8);
+                output = Some(handle_combo()? % 8);
                 exec.instruction_pointer += 1;
             }
             Instruction::bdv => {
                 // Divide A / 2^(combo operand), truncate to int and store in B, increment inst pointer by 1
-                exec.register_b = exec.register_a / 2u64.pow(handle_combo() as u32);
+                exec.register_b = divide()?;
                 exec.instruction_pointer += 1;
             }
             Instruction::cdv => {
                 // Divide A / 2^(combo operand), truncate to int and store in C, increment inst pointer by 1
-                exec.register_c = exec.register_a / 2u64.pow(handle_combo() as u32);
+                exec.register_c = divide()?;
                 exec.instruction_pointer += 1;
             }
         }
-        (exec, output)
+        Ok((exec, output))
     }
 }
 
@@ -1,114 +1,535 @@
 #[macro_use]
 extern crate num_derive;
 
-use crate::instruction::{Execute, Executor, Instruction};
+use crate::asm::{assemble, disassemble, encode};
+use crate::debugger::Debugger;
+use crate::instruction::{validate, Execute, Executor, Fault, Instruction};
+use crate::solver::find_quine;
 use itertools::Itertools;
-use num_traits::{FromPrimitive, ToPrimitive};
-use regex::Regex;
-use std::collections::HashSet;
-use utils::{read_file, time};
+use std::fmt::Display;
+use utils::parse::{ints, read_input, sections, AocError};
+use utils::{read_file, scan, time};
 
+mod asm;
+mod debugger;
+mod generate;
 mod instruction;
+mod solver;
 
 fn main() {
+    // `day-17 generate <seed> [size]` prints a random program with a `size` bit register A
+    if utils::generate::command(48, generate::input) {
+        return;
+    }
+    // `day-17 disasm <program>`, `day-17 asm <source>`, `day-17 check <program>` and
+    // `day-17 debug <program>` for poking at programs by hand
+    let args = std::env::args().collect_vec();
+    let (cmd, path) = (args.get(1).map(|a| a.as_str()), args.get(2));
+    match (cmd, path) {
+        (Some(cmd @ ("disasm" | "check" | "debug" | "asm")), None) => {
+            eprintln!("usage: day-17 {} <file>", cmd);
+            return;
+        }
+        (Some(cmd @ ("disasm" | "check" | "debug")), Some(path)) => {
+            let (exec, instructions) = match parse_program(path) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("{}: {}", path, e);
+                    return;
+                }
+            };
+            match cmd {
+                "disasm" => println!("{}", disassemble(&instructions)),
+                "check" => validate(&instructions)
+                    .iter()
+                    .for_each(|fault| println!("{}", fault)),
+                _ => {
+                    let mut debugger = Debugger::new(exec, instructions);
+                    debugger
+                        .repl(std::io::stdin().lock(), std::io::stdout())
+                        .unwrap();
+                }
+            }
+            return;
+        }
+        (Some("asm"), Some(path)) => {
+            match assemble(&read_file(path)) {
+                Ok(program) => println!("Program: {}", encode(&program).iter().join(",")),
+                Err(e) => eprintln!("{}", e),
+            }
+            return;
+        }
+        _ => (),
+    }
     let (output1, time1) = time(|| run("src/input.txt"));
-    println!("Part 1: {} (took {} µs)", output1, time1.as_micros());
+    match output1 {
+        Ok(output) => println!("Part 1: {} (took {} µs)", output, time1.as_micros()),
+        Err(e) => println!("Part 1 failed: {}", e),
+    }
     let (output2, time2) = time(|| run2("src/input.txt"));
     println!("Part 2: {} (took {} µs)", output2, time2.as_micros());
 }
 
-fn run(filename: &str) -> String {
-    let (exec, instructions) = parse_program(filename);
-    _run(exec, instructions).into_iter().join(",")
+fn run(filename: &str) -> Result<String, ProgramError> {
+    let (exec, instructions) = parse_program(filename)?;
+    Ok(_run(exec, instructions)?.into_iter().join(","))
 }
 
 fn run2(filename: &str) -> u64 {
-    let (exec, instructions) = parse_program(filename);
-    let decoded_inst = instructions
-        .iter()
-        .flat_map(|i| vec![i.0.to_u64().unwrap(), i.1])
-        .collect_vec();
-    let mut results: HashSet<u64> = HashSet::new();
-    results.insert(0);
-    for (idx, _) in decoded_inst.iter().enumerate().rev() {
-        let mut new_results: HashSet<u64> = HashSet::new();
-        for r in results {
-            // If you decompile the program it becomes apparent that the last 3 bits are what
-            // drives the output value, so we can programmatically check all combinations
-            // of the last 3 bits and then shift over the valid answers by 3. If we do this in
-            // sequence and ensure every time that our output continues to be OK, we will
-            // eventually find a valid value.
-            for x in 0..=7 {
-                let mut e = exec.clone();
-                let test_val = (r << 3) + x;
-                e.register_a = test_val;
-                let result = _run(e, instructions.clone());
-                if result == decoded_inst[idx..decoded_inst.len()] {
-                    new_results.insert(test_val);
-                }
-            }
-        }
-        results = new_results;
-    }
-    *results.iter().min().unwrap()
+    let (exec, instructions) = parse_program(filename).unwrap();
+    find_quine(&instructions, &exec).unwrap()
+}
+
+/// How many instructions `_run` will execute before deciding the program loops forever.
+/// Real inputs halt after a few hundred.
+const MAX_CYCLES: u64 = 10_000_000;
+
+fn _run(exec: Executor, instructions: Vec<(Instruction, u64)>) -> Result<Vec<u64>, Fault> {
+    run_for(exec, instructions, MAX_CYCLES)
 }
 
-fn _run(mut exec: Executor, instructions: Vec<(Instruction, u64)>) -> Vec<u64> {
+/// Runs the program to the end, or faults once it has executed `max_cycles` instructions
+fn run_for(
+    mut exec: Executor,
+    instructions: Vec<(Instruction, u64)>,
+    max_cycles: u64,
+) -> Result<Vec<u64>, Fault> {
     let mut output: Vec<u64> = Vec::new();
+    let mut cycles = 0;
     while (exec.instruction_pointer as usize) < instructions.len() {
+        if cycles == max_cycles {
+            return Err(Fault::CycleLimit {
+                instruction_pointer: exec.instruction_pointer,
+                cycles,
+            });
+        }
+        cycles += 1;
         let (inst, op) = &instructions[exec.instruction_pointer as usize];
-        let result = inst.execute(*op, exec);
+        let result = inst.execute(*op, exec)?;
         exec = result.0;
         if let Some(out) = result.1 {
             output.push(out);
         }
     }
-    output
+    Ok(output)
+}
+
+/// Why a program file couldn't be loaded or run
+#[derive(Debug, Eq, PartialEq, Clone)]
+enum ProgramError {
+    /// The file isn't laid out like a puzzle input
+    Parse(AocError),
+    /// The numbers don't decode, or the program faulted while running
+    Fault(Fault),
+}
+
+impl Display for ProgramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgramError::Parse(e) => write!(f, "{}", e),
+            ProgramError::Fault(fault) => write!(f, "{}", fault),
+        }
+    }
+}
+
+impl From<AocError> for ProgramError {
+    fn from(e: AocError) -> Self {
+        ProgramError::Parse(e)
+    }
+}
+
+impl From<Fault> for ProgramError {
+    fn from(fault: Fault) -> Self {
+        ProgramError::Fault(fault)
+    }
 }
 
-fn parse_program(filename: &str) -> (Executor, Vec<(Instruction, u64)>) {
-    let file_contents = read_file(filename);
-    let registers_regex =
-        Regex::new(r"Register A: (\d+)\nRegister B: (\d+)\nRegister C: (\d+)").unwrap();
-    let program_regex = Regex::new(r"(\d),(\d)").unwrap();
-
-    let (_, [register_a, register_b, register_c]) = registers_regex
-        .captures_iter(&file_contents)
-        .map(|c| c.extract())
-        .collect_vec()[0];
-    let ops: Vec<(Instruction, u64)> = program_regex
-        .captures_iter(&file_contents)
-        .map(|c| c.extract())
-        .map(|(_, [op, val])| {
-            (
-                Instruction::from_u64(op.parse::<u64>().unwrap()).unwrap(),
-                val.parse::<u64>().unwrap(),
-            )
-        })
-        .collect_vec();
-    (
-        Executor::new(
+fn parse_program(filename: &str) -> Result<(Executor, Vec<(Instruction, u64)>), ProgramError> {
+    let ((a, b, c), numbers) = parse(&read_input(filename))?;
+    Ok((
+        Executor::new(0, a, b, c),
+        Instruction::decode_program(&numbers)?,
+    ))
+}
+
+/// A, B and C, before the program starts
+type Registers = (u64, u64, u64);
+
+/// The starting registers, then the program as its raw numbers
+fn parse(input: &str) -> Result<(Registers, Vec<u64>), AocError> {
+    let [registers, program] = sections(input)[..] else {
+        return Err(AocError::at(
+            input,
             0,
-            register_a.parse::<u64>().unwrap(),
-            register_b.parse::<u64>().unwrap(),
-            register_c.parse::<u64>().unwrap(),
-        ),
-        ops,
+            "expected the registers and the program separated by a blank line",
+        ));
+    };
+    let registers = scan!(
+        registers.text,
+        "Register A: {}\nRegister B: {}\nRegister C: {}" => u64, u64, u64
     )
+    .map_err(|e| e.below(registers.line - 1))?;
+    let below = |e: AocError| e.below(program.line - 1);
+    scan!(program.text, "Program: {}" => String).map_err(below)?;
+    Ok((registers, ints(program.text).map_err(below)?))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::run;
+    use crate::asm::{assemble, disassemble, encode, AsmError};
+    use crate::debugger::{Debugger, Register, StopReason};
+    use crate::instruction::{validate, Execute, Executor, Fault, Instruction};
+    use crate::solver::{find_quine, solve, SolveError};
+    use crate::{_run, parse, parse_program, run, run2, run_for, ProgramError};
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(
+        test_part_1,
+        "test-program.txt",
+        run => Ok("4,6,3,5,6,3,5,2,1,0".to_string()),
+    );
+    aoc_test!(test_part_2, "test-quine.txt", run2 => 117440);
+    aoc_test!(test_input, optional "input.txt", |f| run(f).unwrap(), run2);
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..2 {
+            let input = crate::generate::input(&mut Rng::new(seed), 24);
+            let (exec, program) = with_input(&input, parse_program).unwrap();
+            assert_eq!(validate(&program), vec![]);
+            let output = _run(exec, program.clone()).unwrap();
+            assert_eq!(output.len(), 8);
+            // Register A makes this output, so the solver has to find it or something smaller
+            let a = solve(&program, &exec, &output).unwrap();
+            assert!(a <= exec.register_a);
+            let mut e = exec;
+            e.register_a = a;
+            assert_eq!(_run(e, program).unwrap(), output);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let registers = "Register A: 729\nRegister B: 0\nRegister C: 0\n\n";
+        assert_eq!(
+            parse(&format!("{}Program: 0,1,5,4,3,0\n", registers)),
+            Ok(((729, 0, 0), vec![0, 1, 5, 4, 3, 0]))
+        );
+        let error = parse("Register A: 729\nRegister B: x\nRegister C: 0\n\nProgram: 0\n");
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "line 2, column 13: can't read `x` as a u64"
+        );
+        let error = parse(&format!("{}Program: 0,99999999999999999999", registers));
+        assert_eq!(error.map_err(|e| (e.line, e.column)), Err((5, 12)));
+        assert_eq!(parse(registers).unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn test_parse_program_errors() {
+        let registers = "Register A: 729\nRegister B: 0\nRegister C: 0\n\n";
+        let input = "Register A: 729\nRegister B: x\nRegister C: 0\n\nProgram: 0,1\n";
+        let error = with_input(input, parse_program);
+        assert!(matches!(error, Err(ProgramError::Parse(e)) if e.line == 2));
+        let error = with_input(&format!("{}Program: 0,1,5\n", registers), parse_program);
+        assert_eq!(
+            error.err(),
+            Some(ProgramError::Fault(Fault::MissingOperand {
+                instruction_pointer: 1
+            }))
+        );
+        let error = with_input(&format!("{}Program: 0,1,9,4\n", registers), run);
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "ip 1: opcode 9 out of range"
+        );
+    }
+
+    #[test]
+    fn test_quine_general() {
+        // Shaped like a real input: the output depends on A shifted by a register amount
+        let numbers = [2, 4, 1, 5, 7, 5, 1, 6, 4, 3, 5, 5, 0, 3, 3, 0];
+        let program = Instruction::decode_program(&numbers).unwrap();
+        let exec = Executor::new(0, 0, 0, 0);
+        let a = find_quine(&program, &exec).unwrap();
+        // Same answer the old shift-by-3 search gets for this shape of program
+        assert_eq!(a, 107416732707226);
+        let mut e = exec;
+        e.register_a = a;
+        assert_eq!(_run(e, program.clone()).unwrap(), numbers);
+
+        // A program that consumes A two bits at a time, which the old 3 bit search
+        // couldn't handle
+        let program = assemble("top:\n bst A\n bxl 3\n out B\n adv 2\n jnz top").unwrap();
+        let target = [0, 7, 6, 0, 1];
+        let a = solve(&program, &exec, &target).unwrap();
+        let mut e = exec;
+        e.register_a = a;
+        assert_eq!(_run(e, program.clone()).unwrap(), target);
+        // Brute force agrees that it's the smallest
+        let first = (0..4096u64)
+            .find(|&candidate| {
+                let mut e = exec;
+                e.register_a = candidate;
+                _run(e, program.clone()) == Ok(target.to_vec())
+            })
+            .unwrap();
+        assert_eq!(a, first);
+    }
+
+    #[test]
+    fn test_quine_unsatisfiable() {
+        // Always prints 0, so it can never print itself
+        let program = assemble("out 0").unwrap();
+        let exec = Executor::new(0, 0, 0, 0);
+        assert_eq!(find_quine(&program, &exec), Err(SolveError::Unsatisfiable));
+        // Prints one value per 3 bits, so can't print a 1 after the last non-zero chunk
+        let program = assemble("top:\n out A\n adv 3\n jnz top").unwrap();
+        assert_eq!(
+            solve(&program, &exec, &[5, 0]),
+            Err(SolveError::Unsatisfiable)
+        );
+        assert_eq!(solve(&program, &exec, &[5, 1]), Ok(13));
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let (_, instructions) = parse_program("src/test-program.txt").unwrap();
+        assert_eq!(
+            disassemble(&instructions),
+            "L0:\n    adv 1\n    out A\n    jnz L0"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // The sample, the part 2 sample, and a typical real input shape
+        for program in [
+            vec![0, 1, 5, 4, 3, 0],
+            vec![0, 3, 5, 4, 3, 0],
+            vec![2, 4, 1, 5, 7, 5, 1, 6, 4, 3, 5, 5, 0, 3, 3, 0],
+            vec![4, 0, 4, 7, 3, 3, 5, 7],
+        ] {
+            let instructions = Instruction::decode_program(&program).unwrap();
+            let text = disassemble(&instructions);
+            assert_eq!(encode(&assemble(&text).unwrap()), program, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_assemble_by_hand() {
+        let source = "
+            ; count A down to zero, printing each step
+            loop:
+                bst A
+                out B   ; low 3 bits
+                adv 1
+                jnz loop
+        ";
+        let program = assemble(source).unwrap();
+        assert_eq!(encode(&program), vec![2, 4, 5, 5, 0, 1, 3, 0]);
+        assert_eq!(
+            assemble("jnz nowhere"),
+            Err(AsmError::UndefinedLabel {
+                line: 1,
+                label: "nowhere".to_string()
+            })
+        );
+        assert_eq!(
+            assemble("bxl 8"),
+            Err(AsmError::BadOperand {
+                line: 1,
+                operand: "8".to_string()
+            })
+        );
+        assert!(matches!(
+            assemble("mul A"),
+            Err(AsmError::UnknownMnemonic { .. })
+        ));
+    }
+
+    #[test]
+    fn test_debugger() {
+        let (exec, instructions) = parse_program("src/test-program.txt").unwrap();
+        let mut debugger = Debugger::new(exec, instructions);
+        debugger.add_breakpoint(1);
+        assert_eq!(debugger.run(), StopReason::Breakpoint(1));
+        assert_eq!(debugger.executor().register_a, 364);
+        assert_eq!(debugger.step(), StopReason::Stepped);
+        assert_eq!(debugger.output(), &[4]);
+        debugger.remove_breakpoint(1);
+        debugger.watch(Register::A);
+        assert_eq!(
+            debugger.run(),
+            StopReason::Watchpoint {
+                register: Register::A,
+                old: 364,
+                new: 182
+            }
+        );
+        debugger.unwatch(Register::A);
+        assert_eq!(debugger.run(), StopReason::Halted);
+        assert_eq!(debugger.output(), &[4, 6, 3, 5, 6, 3, 5, 2, 1, 0]);
+        assert_eq!(debugger.cycles(), 30);
+        assert_eq!(
+            debugger.trace()[1].to_string(),
+            "     2  ip=1   out 4  A=364 B=0 C=0  out=4"
+        );
+    }
+
+    #[test]
+    fn test_debugger_cycle_limit() {
+        // bxl 1 ; jnz 0 never touches A, so this loops forever
+        let program = assemble("top:\n bxl 1\n jnz top").unwrap();
+        let mut debugger = Debugger::new(Executor::new(0, 1, 0, 0), program);
+        debugger.set_max_cycles(Some(1000));
+        assert_eq!(debugger.run(), StopReason::CycleLimit);
+        assert_eq!(debugger.cycles(), 1000);
+        assert_eq!(debugger.trace().len(), 1000);
+    }
+
+    #[test]
+    fn test_debugger_start_breakpoint() {
+        let (exec, instructions) = parse_program("src/test-program.txt").unwrap();
+        let mut debugger = Debugger::new(exec, instructions);
+        // Stops before running anything, then goes once round the loop to stop there again
+        debugger.add_breakpoint(0);
+        assert_eq!(debugger.run(), StopReason::Breakpoint(0));
+        assert_eq!(debugger.cycles(), 0);
+        assert_eq!(debugger.run(), StopReason::Breakpoint(0));
+        assert_eq!(debugger.cycles(), 3);
+        // Stepping off it and back on counts as arriving there again
+        debugger.remove_breakpoint(0);
+        debugger.add_breakpoint(1);
+        assert_eq!(debugger.step(), StopReason::Stepped);
+        assert_eq!(debugger.run(), StopReason::Breakpoint(1));
+        assert_eq!(debugger.cycles(), 4);
+    }
+
+    #[test]
+    fn test_debugger_trace_limit() {
+        let program = assemble("top:\n bxl 1\n jnz top").unwrap();
+        let mut debugger = Debugger::new(Executor::new(0, 1, 0, 0), program);
+        debugger.set_max_cycles(Some(1000));
+        debugger.set_trace_limit(10);
+        assert_eq!(debugger.run(), StopReason::CycleLimit);
+        // Only the last 10 instructions are kept
+        let trace = debugger.trace();
+        assert_eq!(trace.len(), 10);
+        assert_eq!((trace[0].cycle, trace[9].cycle), (991, 1000));
+        debugger.set_trace_limit(3);
+        assert_eq!(debugger.trace()[0].cycle, 998);
+        debugger.set_trace_limit(0);
+        debugger.set_max_cycles(None);
+        assert_eq!(debugger.step(), StopReason::Stepped);
+        assert!(debugger.trace().is_empty());
+    }
+
+    #[test]
+    fn test_debugger_repl() {
+        let (exec, instructions) = parse_program("src/test-program.txt").unwrap();
+        let mut debugger = Debugger::new(exec, instructions);
+        let commands = "b 2\nc\nregs\ns 3\nout\nkeep 2\ntrace\nq\n";
+        let mut out: Vec<u8> = Vec::new();
+        debugger.repl(commands.as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Breakpoint(2)"));
+        assert!(out.contains("ip=2 A=364 B=0 C=0 cycles=2"));
+        assert!(out.contains("(dbg) 4,6\n"));
+        // Five instructions have run, and only the last two are still in the trace
+        assert!(out.ends_with(
+            "(dbg) (dbg)      4  ip=0   adv 1  A=182 B=0 C=0\n     \
+             5  ip=1   out 4  A=182 B=0 C=0  out=6\n(dbg) \n"
+        ));
+    }
+
+    #[test]
+    fn test_cycle_limit() {
+        // Jumps back to the start forever without touching A
+        let program = assemble("out A\njnz 0").unwrap();
+        let exec = Executor::new(0, 1, 0, 0);
+        assert_eq!(
+            run_for(exec, program.clone(), 100),
+            Err(Fault::CycleLimit {
+                instruction_pointer: 0,
+                cycles: 100
+            })
+        );
+        assert!(matches!(_run(exec, program), Err(Fault::CycleLimit { .. })));
+        // A program that halts within the budget exactly is fine
+        let program = assemble("out A\nout B").unwrap();
+        assert_eq!(run_for(exec, program, 2), Ok(vec![1, 0]));
+    }
 
     #[test]
-    fn test_part_1() {
-        let output = run("src/test-program.txt");
-        assert_eq!(output, "4,6,3,5,6,3,5,2,1,0");
+    fn test_faults() {
+        let exec = Executor::new(0, 1 << 40, 0, 0);
+        // Shifting by more than the register width
+        let program = assemble("bst 3\nbxl 7\nbxl 7\nadv A").unwrap();
+        assert_eq!(
+            _run(exec, program),
+            Err(Fault::ShiftOverflow {
+                instruction_pointer: 3,
+                amount: 1 << 40
+            })
+        );
+        assert_eq!(
+            Instruction::out.execute(7, exec).err(),
+            Some(Fault::ReservedOperand {
+                instruction_pointer: 0
+            })
+        );
+        assert_eq!(
+            Instruction::jnz.execute(3, exec).err(),
+            Some(Fault::MisalignedJump {
+                instruction_pointer: 0,
+                target: 3
+            })
+        );
+        // Odd jumps are fine when they aren't taken
+        assert!(Instruction::jnz
+            .execute(3, Executor::new(0, 0, 0, 0))
+            .is_ok());
+        assert_eq!(
+            Instruction::decode_program(&[0, 1, 8, 2]),
+            Err(Fault::OpcodeOutOfRange {
+                instruction_pointer: 1,
+                opcode: 8
+            })
+        );
+        assert_eq!(
+            Instruction::decode_program(&[0, 9]),
+            Err(Fault::OperandOutOfRange {
+                instruction_pointer: 0,
+                operand: 9
+            })
+        );
+        assert_eq!(
+            Instruction::decode_program(&[0, 1, 5]),
+            Err(Fault::MissingOperand {
+                instruction_pointer: 1
+            })
+        );
     }
 
     #[test]
-    fn test_part_2() {
-        assert_eq!(true, true);
+    fn test_validate() {
+        let (_, program) = parse_program("src/test-program.txt").unwrap();
+        assert_eq!(validate(&program), vec![]);
+        let program = Instruction::decode_program(&[2, 7, 1, 7, 3, 1, 4, 7]).unwrap();
+        assert_eq!(
+            validate(&program),
+            vec![
+                Fault::ReservedOperand {
+                    instruction_pointer: 0
+                },
+                Fault::MisalignedJump {
+                    instruction_pointer: 2,
+                    target: 1
+                },
+            ]
+        );
     }
 }
@@ -0,0 +1,359 @@
+use crate::asm::OperandKind;
+use crate::instruction::{Executor, Instruction};
+use std::fmt::Display;
+
+/// A single bit of a register, as an affine function of the bits of the initial value of
+/// register A: `constant ^ (xor of every bit of A set in mask)`.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+struct Bit {
+    mask: u64,
+    constant: bool,
+}
+
+impl Bit {
+    const ZERO: Bit = Bit {
+        mask: 0,
+        constant: false,
+    };
+
+    fn constant(value: bool) -> Bit {
+        Bit {
+            mask: 0,
+            constant: value,
+        }
+    }
+
+    fn xor(self, other: Bit) -> Bit {
+        Bit {
+            mask: self.mask ^ other.mask,
+            constant: self.constant ^ other.constant,
+        }
+    }
+}
+
+/// A 64 bit register where every bit is symbolic, least significant bit first
+type Word = [Bit; 64];
+
+fn concrete_word(value: u64) -> Word {
+    std::array::from_fn(|i| Bit::constant(value >> i & 1 == 1))
+}
+
+fn xor_words(a: &Word, b: &Word) -> Word {
+    std::array::from_fn(|i| a[i].xor(b[i]))
+}
+
+fn shift_right(word: &Word, amount: u64) -> Word {
+    std::array::from_fn(|i| word.get(i + amount as usize).copied().unwrap_or(Bit::ZERO))
+}
+
+fn low_bits(word: &Word) -> Word {
+    std::array::from_fn(|i| if i < 3 { word[i] } else { Bit::ZERO })
+}
+
+/// Linear equations over GF(2) on the bits of A. Each row is stored under its highest
+/// variable, so reducing an expression is one pass from the top bit down.
+#[derive(Debug, Clone)]
+struct System {
+    rows: [Option<Bit>; 64],
+}
+
+impl System {
+    fn new() -> System {
+        System { rows: [None; 64] }
+    }
+
+    fn reduce(&self, bit: Bit) -> Bit {
+        let mut bit = bit;
+        for var in (0..64).rev() {
+            if bit.mask >> var & 1 == 1 {
+                if let Some(row) = self.rows[var] {
+                    bit = bit.xor(row);
+                }
+            }
+        }
+        bit
+    }
+
+    /// Adds `bit == value`, returning false if that contradicts what we already know
+    fn assume(&mut self, bit: Bit, value: bool) -> bool {
+        let reduced = self.reduce(bit.xor(Bit::constant(value)));
+        if reduced.mask == 0 {
+            return !reduced.constant;
+        }
+        let pivot = 63 - reduced.mask.leading_zeros() as usize;
+        self.rows[pivot] = Some(reduced);
+        true
+    }
+
+    /// Either the concrete value of the word, or a free variable that it depends on
+    fn concrete(&self, word: &Word) -> Result<u64, usize> {
+        let mut value = 0;
+        for (i, &bit) in word.iter().enumerate() {
+            let reduced = self.reduce(bit);
+            if reduced.mask != 0 {
+                return Err(63 - reduced.mask.leading_zeros() as usize);
+            }
+            value |= (reduced.constant as u64) << i;
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    instruction_pointer: u64,
+    a: Word,
+    b: Word,
+    c: Word,
+    system: System,
+    /// Words that took a `jnz` and so must have at least one bit set
+    nonzero: Vec<Word>,
+    outputs: usize,
+    steps: usize,
+}
+
+impl State {
+    /// Splits into one state where `bit` is 1 and one where it's 0, dropping either if it
+    /// contradicts what we know. The 0 state comes last so it's popped first off a stack.
+    fn branch(&self, bit: Bit) -> Vec<State> {
+        [true, false]
+            .into_iter()
+            .filter_map(|value| {
+                let mut s = self.clone();
+                (s.system.assume(bit, value) && s.feasible()).then_some(s)
+            })
+            .collect()
+    }
+
+    /// Whether every `jnz` we took could still have seen a non-zero A
+    fn feasible(&self) -> bool {
+        self.nonzero.iter().all(|word| {
+            word.iter().any(|&bit| {
+                let reduced = self.system.reduce(bit);
+                reduced.mask != 0 || reduced.constant
+            })
+        })
+    }
+
+    fn combo(&self, operand: u64) -> Option<Word> {
+        match operand {
+            0..=3 => Some(concrete_word(operand)),
+            4 => Some(self.a),
+            5 => Some(self.b),
+            6 => Some(self.c),
+            _ => None,
+        }
+    }
+
+    /// The smallest value of A consistent with everything we've learned on this path.
+    /// Fixes the bits of A from the top down preferring 0, so the first complete assignment
+    /// found is the minimum.
+    fn minimal_a(&self) -> Option<u64> {
+        let mut stack = vec![self.clone()];
+        while let Some(state) = stack.pop() {
+            let undetermined = (0..64)
+                .rev()
+                .map(var)
+                .find(|&bit| state.system.reduce(bit).mask != 0);
+            match undetermined {
+                Some(bit) => stack.extend(state.branch(bit)),
+                None => return state.system.concrete(&initial_a()).ok(),
+            }
+        }
+        None
+    }
+}
+
+/// Bit `i` of the initial value of A
+fn var(i: usize) -> Bit {
+    Bit {
+        mask: 1 << i,
+        constant: false,
+    }
+}
+
+fn initial_a() -> Word {
+    std::array::from_fn(var)
+}
+
+enum Step {
+    Continue(State),
+    Branch(Vec<State>),
+    Halted(State),
+    Dead,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SolveError {
+    /// No value of A produces the target output
+    Unsatisfiable,
+    /// Some paths ran past the step limit without halting, so we can't be sure
+    StepLimit,
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Unsatisfiable => write!(f, "no value of register A gives that output"),
+            SolveError::StepLimit => write!(f, "gave up after hitting the step limit"),
+        }
+    }
+}
+
+/// The most instructions we'll follow down a single path before giving up on it
+const MAX_STEPS: usize = 100_000;
+
+/// Runs one instruction symbolically. Mirrors `Instruction::execute`, except that whenever
+/// a value has to be concrete (shift amounts and `jnz`) and isn't, we split the path on one
+/// of the unknown bits of A and try both.
+fn step(program: &[(Instruction, u64)], target: &[u64], mut state: State) -> Step {
+    let Some(&(inst, operand)) = program.get(state.instruction_pointer as usize) else {
+        return Step::Halted(state);
+    };
+    state.steps += 1;
+    let combo = if inst.operand_kind() == OperandKind::Combo {
+        match state.combo(operand) {
+            Some(word) => word,
+            None => return Step::Dead,
+        }
+    } else {
+        concrete_word(operand)
+    };
+    match inst {
+        Instruction::adv | Instruction::bdv | Instruction::cdv => {
+            let amount = match state.system.concrete(&combo) {
+                Ok(amount) => amount,
+                Err(v) => return Step::Branch(state.branch(var(v))),
+            };
+            if amount >= 64 {
+                // The real machine can't shift this far either
+                return Step::Dead;
+            }
+            let result = shift_right(&state.a, amount);
+            match inst {
+                Instruction::adv => state.a = result,
+                Instruction::bdv => state.b = result,
+                _ => state.c = result,
+            }
+            state.instruction_pointer += 1;
+        }
+        Instruction::bxl => {
+            state.b = xor_words(&state.b, &combo);
+            state.instruction_pointer += 1;
+        }
+        Instruction::bst => {
+            state.b = low_bits(&combo);
+            state.instruction_pointer += 1;
+        }
+        Instruction::jnz if operand % 2 == 1 => {
+            // Jumping here faults, so the only way through is for A to be zero
+            if !state.a.iter().all(|&bit| state.system.assume(bit, false)) || !state.feasible() {
+                return Step::Dead;
+            }
+            state.instruction_pointer += 1;
+        }
+        Instruction::jnz => {
+            let reduced = state.a.map(|bit| state.system.reduce(bit));
+            if reduced.iter().any(|b| b.mask == 0 && b.constant) {
+                state.instruction_pointer = operand / 2;
+            } else if reduced.iter().all(|b| b.mask == 0) {
+                state.instruction_pointer += 1;
+            } else {
+                // Either every bit of A is zero and we fall through, or we jump and
+                // remember that A has to have been non-zero
+                let mut zero = state.clone();
+                let consistent = state.a.iter().all(|&bit| zero.system.assume(bit, false));
+                zero.instruction_pointer += 1;
+                let mut jump = state;
+                jump.nonzero.push(jump.a);
+                jump.instruction_pointer = operand / 2;
+                return Step::Branch(
+                    [jump]
+                        .into_iter()
+                        .chain((consistent && zero.feasible()).then_some(zero))
+                        .collect(),
+                );
+            }
+        }
+        Instruction::bxc => {
+            state.b = xor_words(&state.b, &state.c);
+            state.instruction_pointer += 1;
+        }
+        Instruction::out => {
+            let Some(&expected) = target.get(state.outputs) else {
+                // Already printed everything we wanted
+                return Step::Dead;
+            };
+            let consistent = (0..3).all(|i| state.system.assume(combo[i], expected >> i & 1 == 1));
+            if !consistent || !state.feasible() {
+                return Step::Dead;
+            }
+            state.outputs += 1;
+            state.instruction_pointer += 1;
+        }
+    }
+    Step::Continue(state)
+}
+
+/// Finds the smallest initial value of register A that makes `program` print exactly
+/// `target`, with B and C starting as they are in `exec`. Every bit of A starts out unknown
+/// and we execute symbolically, branching on bits only when we have to.
+pub fn solve(
+    program: &[(Instruction, u64)],
+    exec: &Executor,
+    target: &[u64],
+) -> Result<u64, SolveError> {
+    let initial = State {
+        instruction_pointer: exec.instruction_pointer,
+        a: initial_a(),
+        b: concrete_word(exec.register_b),
+        c: concrete_word(exec.register_c),
+        system: System::new(),
+        nonzero: Vec::new(),
+        outputs: 0,
+        steps: 0,
+    };
+    let mut best: Option<u64> = None;
+    let mut gave_up = false;
+    let mut stack = vec![initial];
+    while let Some(mut state) = stack.pop() {
+        // Nothing down this path can beat what we've already found
+        match (state.minimal_a(), best) {
+            (None, _) => continue,
+            (Some(lowest), Some(b)) if lowest >= b => continue,
+            _ => (),
+        }
+        loop {
+            match step(program, target, state) {
+                Step::Continue(next) => state = next,
+                Step::Branch(children) => {
+                    stack.extend(children);
+                    break;
+                }
+                Step::Halted(done) => {
+                    if done.outputs == target.len() {
+                        if let Some(a) = done.minimal_a() {
+                            best = Some(best.map_or(a, |b| b.min(a)));
+                        }
+                    }
+                    break;
+                }
+                Step::Dead => break,
+            }
+            if state.steps >= MAX_STEPS {
+                gave_up = true;
+                break;
+            }
+        }
+    }
+    match best {
+        Some(a) => Ok(a),
+        None if gave_up => Err(SolveError::StepLimit),
+        None => Err(SolveError::Unsatisfiable),
+    }
+}
+
+/// The smallest A that makes the program print itself
+pub fn find_quine(program: &[(Instruction, u64)], exec: &Executor) -> Result<u64, SolveError> {
+    solve(program, exec, &crate::asm::encode(program))
+}
@@ -0,0 +1,18 @@
+use utils::generate::Rng;
+
+/// Falling bytes for a memory space with coordinates from 0 to `size`. Seven in ten tiles
+/// get a byte, in random order, which is plenty to cut the corners off from each other
+/// eventually. Nothing ever lands on the corners themselves.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let mut tiles = (0..=size)
+        .flat_map(|y| (0..=size).map(move |x| (x, y)))
+        .filter(|&tile| tile != (0, 0) && tile != (size, size))
+        .collect::<Vec<_>>();
+    rng.shuffle(&mut tiles);
+    tiles.truncate((size + 1) * (size + 1) * 7 / 10);
+    tiles
+        .iter()
+        .map(|(x, y)| format!("{},{}\n", x, y))
+        .collect()
+}
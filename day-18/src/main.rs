@@ -1,17 +1,39 @@
 use array2d::Array2D;
 use itertools::Itertools;
-use pathfinding::prelude::dijkstra;
+use pathfinding::prelude::bfs;
+use union_find::UnionFind;
 use utils::{read_lines, time, Point};
 
+mod generate;
+mod union_find;
+
 fn main() {
+    // `day-18 generate <seed> [size]` prints random bytes for coordinates up to `size`
+    if utils::generate::command(70, generate::input) {
+        return;
+    }
     let (part1, time1) = time(|| part_1("src/input.txt", 1024, 70));
     println!("Part 1: {} (took {} µs)", part1, time1.as_micros());
     let (part2, time2) = time(|| part_2("src/input.txt", 70));
+    let blockage = part2.unwrap();
     println!(
         "Part 2: {:?} (took {} milliseconds)",
-        part2.unwrap(),
+        blockage.byte,
         time2.as_millis()
     );
+    println!(
+        "Byte {} cut this {} step path: {:?}",
+        blockage.index,
+        blockage.path.len() - 1,
+        blockage.path
+    );
+    let bytes = parse("src/input.txt");
+    let (check, time3) = time(|| first_blocking_byte_bsearch(&bytes, 70));
+    println!(
+        "Binary search agrees: {} (took {} milliseconds)",
+        check == Some(blockage.index),
+        time3.as_millis()
+    );
 }
 
 fn parse(filename: &str) -> Vec<(usize, usize)> {
@@ -29,58 +51,179 @@ fn parse(filename: &str) -> Vec<(usize, usize)> {
 
 fn part_1(filename: &str, num_bytes: usize, coordinate_max: usize) -> usize {
     let bytes = parse(filename);
-    find_path(bytes, num_bytes, coordinate_max).unwrap()
+    find_path(&bytes, num_bytes, coordinate_max).unwrap()
+}
+
+/// The first byte that cuts the exit off, along with the path it cut
+#[derive(Debug)]
+struct Blockage {
+    /// Index of the byte in the input
+    index: usize,
+    byte: (usize, usize),
+    /// A shortest path from just before the byte fell, which has to run through it
+    path: Vec<(usize, usize)>,
 }
 
-fn part_2(filename: &str, coordinate_max: usize) -> Option<(usize, usize)> {
+fn part_2(filename: &str, coordinate_max: usize) -> Option<Blockage> {
     let bytes = parse(filename);
-    for x in 0..bytes.len() {
-        let result = find_path(bytes.clone(), x, coordinate_max);
-        if result.is_none() {
-            return Some(bytes[x - 1]);
+    let index = first_blocking_byte(&bytes, coordinate_max)?;
+    let path = shortest_path(&bytes[..index], coordinate_max)?
+        .into_iter()
+        .map(|p| (p.col as usize, p.row as usize))
+        .collect_vec();
+    Some(Blockage {
+        index,
+        byte: bytes[index],
+        path,
+    })
+}
+
+/// Runs the bytes backwards: drop them all, then pick them back up one at a time, joining
+/// each freed cell to its free neighbours. The first byte whose removal joins the start
+/// and exit is the one that cut them apart. One union-find pass, so near-linear.
+fn first_blocking_byte(bytes: &[(usize, usize)], coordinate_max: usize) -> Option<usize> {
+    let size = coordinate_max + 1;
+    let index = |row: usize, col: usize| row * size + col;
+    // Bytes can land on the same cell more than once, so count them
+    let mut blocked: Array2D<usize> = Array2D::filled_with(0, size, size);
+    for &(col, row) in bytes {
+        blocked[(row, col)] += 1;
+    }
+    let mut sets = UnionFind::new(size * size);
+    let free = |sets: &mut UnionFind, blocked: &Array2D<usize>, row: usize, col: usize| {
+        for (r, c) in [
+            (row + 1, col),
+            (row, col + 1),
+            (row.wrapping_sub(1), col),
+            (row, col.wrapping_sub(1)),
+        ] {
+            if blocked.get(r, c) == Some(&0) {
+                sets.union(index(row, col), index(r, c));
+            }
+        }
+    };
+    for row in 0..size {
+        for col in 0..size {
+            if blocked[(row, col)] == 0 {
+                free(&mut sets, &blocked, row, col);
+            }
+        }
+    }
+    let (start, exit) = (index(0, 0), index(coordinate_max, coordinate_max));
+    if sets.connected(start, exit) {
+        return None;
+    }
+    for (i, &(col, row)) in bytes.iter().enumerate().rev() {
+        blocked[(row, col)] -= 1;
+        if blocked[(row, col)] == 0 {
+            free(&mut sets, &blocked, row, col);
+            if sets.connected(start, exit) {
+                return Some(i);
+            }
         }
     }
     None
 }
 
-fn find_path(bytes: Vec<(usize, usize)>, num_bytes: usize, coordinate_max: usize) -> Option<usize> {
+/// Same answer as `first_blocking_byte`, by binary searching on how many bytes have
+/// fallen and running a full search at each step
+fn first_blocking_byte_bsearch(bytes: &[(usize, usize)], coordinate_max: usize) -> Option<usize> {
+    // Invariant: a path exists with `low` bytes down and doesn't with `high` bytes down
+    let (mut low, mut high) = (0, bytes.len() + 1);
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+        if shortest_path(&bytes[..mid], coordinate_max).is_some() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (high <= bytes.len()).then_some(high - 1)
+}
+
+fn find_path(bytes: &[(usize, usize)], num_bytes: usize, coordinate_max: usize) -> Option<usize> {
+    shortest_path(&bytes[..num_bytes], coordinate_max).map(|path| path.len() - 1)
+}
+
+/// BFS from the top left to the bottom right around every byte in `bytes`
+fn shortest_path(bytes: &[(usize, usize)], coordinate_max: usize) -> Option<Vec<Point>> {
     let mut grid: Array2D<bool> =
         Array2D::filled_with(false, coordinate_max + 1, coordinate_max + 1);
-    for (col, row) in bytes[0..num_bytes].into_iter() {
-        grid.set(*row, *col, true)
+    for &(col, row) in bytes {
+        grid.set(row, col, true)
             .expect("Uh oh we're outside the grid");
     }
-    if let Some(result) = dijkstra(
+    bfs(
         &Point::new(0, 0),
         |p| {
-            p.neighbors().into_iter().filter(|x| {
-                *grid
-                    .get(x.0.row as usize, x.0.col as usize)
-                    .unwrap_or(&true)
-                    == false
-            })
+            p.neighbors()
+                .into_iter()
+                .map(|(n, _)| n)
+                .filter(|x| !*grid.get(x.row as usize, x.col as usize).unwrap_or(&true))
         },
         |p| *p == Point::new(coordinate_max as i32, coordinate_max as i32),
-    ) {
-        Some(result.0.len() - 1)
-    } else {
-        None
-    }
+    )
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{part_1, part_2};
+    use crate::{first_blocking_byte, first_blocking_byte_bsearch, parse, part_1, part_2};
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(
+        test_sample,
+        "sample.txt",
+        |f| part_1(f, 12, 6) => 22,
+        |f| part_2(f, 6).map(|b| b.byte) => Some((6, 1)),
+    );
+    aoc_test!(
+        test_input,
+        optional "input.txt",
+        |f| part_1(f, 1024, 70),
+        |f| {
+            let (x, y) = part_2(f, 70).unwrap().byte;
+            format!("{},{}", x, y)
+        },
+    );
 
     #[test]
-    fn test_part_1() {
-        let result = part_1("src/sample.txt", 12, 6);
-        assert_eq!(result, 22);
+    fn test_part_2() {
+        let result = part_2("src/sample.txt", 6).unwrap();
+        assert_eq!(result.byte, (6, 1));
+        assert_eq!(result.index, 20);
+        assert_eq!(result.path.first(), Some(&(0, 0)));
+        assert_eq!(result.path.last(), Some(&(6, 6)));
+        assert!(result.path.contains(&(6, 1)));
     }
 
     #[test]
-    fn test_part_2() {
-        let result = part_2("src/sample.txt", 6);
-        assert_eq!(result, Some((6, 1)));
+    fn test_binary_search_agrees() {
+        let bytes = parse("src/sample.txt");
+        assert_eq!(first_blocking_byte_bsearch(&bytes, 6), Some(20));
+        // Not enough bytes to block anything yet
+        assert_eq!(first_blocking_byte(&bytes[..12], 6), None);
+        assert_eq!(first_blocking_byte_bsearch(&bytes[..12], 6), None);
+        // A wall straight across with a repeat in the middle
+        let wall = vec![(0, 2), (1, 2), (1, 2), (2, 2), (3, 2)];
+        assert_eq!(first_blocking_byte(&wall, 3), Some(4));
+        assert_eq!(first_blocking_byte_bsearch(&wall, 3), Some(4));
+    }
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = crate::generate::input(&mut Rng::new(seed), 20);
+            let bytes = with_input(&input, parse);
+            let blocked = first_blocking_byte(&bytes, 20).unwrap();
+            assert_eq!(first_blocking_byte_bsearch(&bytes, 20), Some(blocked));
+            // The corners are connected right up until the blocking byte lands
+            assert!(with_input(&input, |f| part_1(f, blocked, 20)) >= 40);
+            assert_eq!(
+                with_input(&input, |f| part_2(f, 20)).unwrap().index,
+                blocked
+            );
+        }
     }
 }
@@ -0,0 +1,35 @@
+use itertools::Itertools;
+use utils::generate::Rng;
+
+const COLORS: [char; 5] = ['w', 'u', 'b', 'r', 'g'];
+
+/// A line of towels and then `size` designs. Like the real thing, one colour has no towel
+/// of its own, so random designs usually can't be made. Seven in ten designs are built from
+/// the towels and always can.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let missing = *rng.pick(&COLORS);
+    let mut towels: Vec<String> = Vec::new();
+    while towels.len() < size + size / 8 + 4 {
+        let len = rng.below(8) + 1;
+        let towel = (0..len).map(|_| *rng.pick(&COLORS)).collect::<String>();
+        if towel != missing.to_string() && !towels.contains(&towel) {
+            towels.push(towel);
+        }
+    }
+    let mut input = format!("{}\n\n", towels.iter().join(", "));
+    for _ in 0..size {
+        let len = rng.below(20) + 40;
+        let mut design = String::new();
+        if rng.chance(0.7) {
+            while design.len() < len {
+                let towel = rng.pick(&towels);
+                design.push_str(towel);
+            }
+        } else {
+            design.extend((0..len).map(|_| *rng.pick(&COLORS)));
+        }
+        input.push_str(&design);
+        input.push('\n');
+    }
+    input
+}
@@ -1,9 +1,26 @@
 use itertools::Itertools;
-use std::collections::HashMap;
 use trie_rs::{Trie, TrieBuilder};
+use utils::parse::{normalize, sections};
 use utils::{read_file, time};
 
+mod generate;
+
 fn main() {
+    // `day-19 generate <seed> [size]` prints random towels and `size` designs
+    if utils::generate::command(400, generate::input) {
+        return;
+    }
+    // `day-19 show <design> [limit]` prints the ways to make a design from the real towels
+    let args = std::env::args().collect_vec();
+    if args.get(1).map(|a| a.as_str()) == Some("show") {
+        let (towels, _) = parse_input("src/input.txt");
+        let limit = args.get(3).map(|l| l.parse::<usize>().unwrap());
+        for arrangement in arrangements(&towels, args[2].as_bytes(), limit) {
+            let pieces = arrangement.iter().map(|t| String::from_utf8_lossy(t));
+            println!("{}", pieces.format(" "));
+        }
+        return;
+    }
     let (solution, time1) = time(|| solve("src/input.txt"));
     println!(
         "Part 1: {}\nPart 2: {}\nTook {} seconds to solve",
@@ -14,67 +31,150 @@ fn main() {
 }
 
 fn solve(filename: &str) -> (usize, usize) {
-    let (towels_available, towel_goals) = parse_input(filename);
-    let mut memo: HashMap<String, (bool, usize)> = HashMap::new();
-    let possible_towels = towel_goals
+    let (towels, designs) = parse_input(filename);
+    let counts = designs
         .iter()
-        .map(|t| can_build(&towels_available, t, &mut memo))
+        .map(|d| count_arrangements(&towels, d.as_bytes()))
         .collect_vec();
     (
-        possible_towels.iter().filter(|b| b.0).count(),
-        possible_towels.iter().map(|b| b.1).sum(),
+        counts.iter().filter(|&&c| c > 0).count(),
+        counts.iter().sum(),
     )
 }
 
-fn can_build(
-    available: &Trie<u8>,
-    towel: &String,
-    mut memo: &mut HashMap<String, (bool, usize)>,
-) -> (bool, usize) {
-    if towel.len() == 0 {
-        (true, 1)
-    } else if let Some(r) = memo.get(towel) {
-        *r
+/// The length of every towel that `rest` starts with, shortest first
+fn towel_lengths<'a>(towels: &'a Trie<u8>, rest: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    let mut search = towels.inc_search();
+    rest.iter()
+        .map_while(move |b| search.query(b))
+        .enumerate()
+        .filter(|(_, answer)| answer.is_match())
+        .map(|(i, _)| i + 1)
+}
+
+/// `ways[i]` is how many ways there are to make `design[i..]`, filled in from the back
+fn ways_from(towels: &Trie<u8>, design: &[u8]) -> Vec<usize> {
+    let mut ways = vec![0; design.len() + 1];
+    ways[design.len()] = 1;
+    for start in (0..design.len()).rev() {
+        ways[start] = towel_lengths(towels, &design[start..])
+            .map(|len| ways[start + len])
+            .sum();
+    }
+    ways
+}
+
+fn count_arrangements(towels: &Trie<u8>, design: &[u8]) -> usize {
+    ways_from(towels, design)[0]
+}
+
+/// Every way to make `design` out of towels, as the slices of the design each towel covers,
+/// stopping after `limit` of them if there's a limit. Dead ends are skipped using the same
+/// table `count_arrangements` builds, so each arrangement costs about its own length.
+fn arrangements<'a>(
+    towels: &'a Trie<u8>,
+    design: &'a [u8],
+    limit: Option<usize>,
+) -> impl Iterator<Item = Vec<&'a [u8]>> + 'a {
+    let ways = ways_from(towels, design);
+    let mut stack: Vec<(usize, Vec<&[u8]>)> = if ways[0] > 0 {
+        vec![(0, Vec::new())]
     } else {
-        let prefixes: Vec<String> = available.common_prefix_search(&towel).collect_vec();
-        let r = prefixes
-            .iter()
-            .map(|p| can_build(available, &towel[p.len()..].to_string(), &mut memo))
-            .filter(|b| b.0)
-            .collect_vec();
-        if r.len() > 0 {
-            let result = (true, r.iter().map(|x| x.1).sum());
-            memo.insert(towel.to_string(), result);
-            result
-        } else {
-            memo.insert(towel.to_string(), (false, 0));
-            (false, 0)
+        Vec::new()
+    };
+    std::iter::from_fn(move || {
+        while let Some((start, path)) = stack.pop() {
+            if start == design.len() {
+                return Some(path);
+            }
+            let lengths = towel_lengths(towels, &design[start..])
+                .filter(|len| ways[start + len] > 0)
+                .collect_vec();
+            // Reversed so the shortest towel comes off the stack first
+            for len in lengths.into_iter().rev() {
+                let mut next = path.clone();
+                next.push(&design[start..start + len]);
+                stack.push((start + len, next));
+            }
         }
-    }
+        None
+    })
+    .take(limit.unwrap_or(usize::MAX))
 }
 
 fn parse_input(filename: &str) -> (Trie<u8>, Vec<String>) {
-    let input = read_file(filename);
-    let raw = input.split("\n\n").collect_vec();
+    parse(&read_file(filename))
+}
+
+/// The first section lists the towels, every line in the sections after it is a design
+fn parse(input: &str) -> (Trie<u8>, Vec<String>) {
+    let input = normalize(input);
+    let sections = sections(&input);
     let mut builder = TrieBuilder::new();
-    raw[0]
-        .split(", ")
-        .map(|x| x.to_string())
-        .for_each(|x| builder.push(x));
+    sections
+        .first()
+        .map_or("", |s| s.text)
+        .split(',')
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .for_each(|t| builder.push(t));
+    let designs = sections.iter().skip(1).flat_map(|s| s.lines());
     (
         builder.build(),
-        raw[1].split("\n").map(|x| x.to_string()).collect_vec(),
+        designs.map(|d| d.trim().to_string()).collect_vec(),
     )
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::solve;
+    use crate::{arrangements, count_arrangements, parse, parse_input, solve};
+    use itertools::Itertools;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(test_both, "sample.txt", |f| solve(f).0 => 6, |f| solve(f).1 => 16);
+    aoc_test!(test_input, optional "input.txt", |f| solve(f).0, |f| solve(f).1);
+
+    #[test]
+    fn test_messy_input() {
+        // CRLF, a trailing newline and extra blank lines used to add an empty design
+        let (towels, designs) = parse("r, wr,b, g,br\r\n\r\n\r\nbrwrr\r\n\r\nbggr\r\n\r\n");
+        assert_eq!(designs, vec!["brwrr", "bggr"]);
+        assert_eq!(count_arrangements(&towels, b"brwrr"), 2);
+        assert_eq!(count_arrangements(&towels, b"bggr"), 1);
+    }
 
     #[test]
-    fn test_both() {
-        let r = solve("src/sample.txt");
-        assert_eq!(r.0, 6);
-        assert_eq!(r.1, 16);
+    fn test_arrangements() {
+        let (towels, designs) = parse_input("src/sample.txt");
+        let brwrr = arrangements(&towels, b"brwrr", None)
+            .map(|a| a.iter().map(|t| std::str::from_utf8(t).unwrap()).join(" "))
+            .collect_vec();
+        assert_eq!(brwrr, vec!["b r wr r", "br wr r"]);
+        assert_eq!(arrangements(&towels, b"brwrr", Some(1)).count(), 1);
+        assert_eq!(arrangements(&towels, b"bbrgwb", None).count(), 0);
+        for design in designs {
+            let all = arrangements(&towels, design.as_bytes(), None).collect_vec();
+            assert_eq!(all.len(), count_arrangements(&towels, design.as_bytes()));
+            assert!(all.iter().all(|a| a.concat() == design.as_bytes()));
+            assert_eq!(all.iter().unique().count(), all.len());
+        }
+    }
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = crate::generate::input(&mut Rng::new(seed), 50);
+            let (possible, ways) = with_input(&input, solve);
+            // Designs built from towels can always be made, often in many ways, random ones rarely
+            assert!((25..50).contains(&possible));
+            assert!(ways > possible);
+            let (towels, designs) = parse(&input);
+            for design in designs.iter().take(5) {
+                let ways = count_arrangements(&towels, design.as_bytes());
+                assert_eq!(arrangements(&towels, design.as_bytes(), None).count(), ways);
+            }
+        }
     }
 }
@@ -0,0 +1,24 @@
+use utils::generate::Rng;
+
+/// `size` reports of five to eight levels. A third are safe, a third are safe apart from
+/// one extra level the dampener can take out, and the rest have two extra levels.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..size {
+        let direction = if rng.chance(0.5) { 1 } else { -1 };
+        let mut level = rng.range(30..70);
+        let mut report = vec![level];
+        for _ in 1..rng.range(5..9) {
+            level += direction * rng.range(1..4);
+            report.push(level);
+        }
+        for _ in 0..rng.below(3) {
+            let at = rng.below(report.len() + 1);
+            report.insert(at, rng.range(1..100));
+        }
+        let report = report.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        input.push_str(&report.join(" "));
+        input.push('\n');
+    }
+    input
+}
@@ -1,7 +1,21 @@
 use utils::read_lines;
 
+mod generate;
+
 fn main() {
-    let reports: Vec<Vec<u32>> = read_lines("src/input.txt")
+    // `day-2 generate <seed> [size]` prints a random input with `size` reports
+    if utils::generate::command(1000, generate::input) {
+        return;
+    }
+    println!("Number of safe reports: {}", part_1("src/input.txt"));
+    println!(
+        "Number of safe reports with the Problem Dampener: {}",
+        part_2("src/input.txt")
+    );
+}
+
+fn parse_reports(filename: &str) -> Vec<Vec<u32>> {
+    read_lines(filename)
         .iter()
         .map(|report| {
             report
@@ -9,22 +23,21 @@ fn main() {
                 .map(|x| x.parse::<u32>().unwrap())
                 .collect()
         })
-        .collect();
-    let safe_reports = reports
+        .collect()
+}
+
+fn part_1(filename: &str) -> usize {
+    parse_reports(filename)
         .iter()
-        .map(|x| validate_report_safety(x, false))
-        .filter(|x| *x)
-        .count();
-    println!("Number of safe reports: {}", safe_reports);
-    let safe_reports_2 = reports
+        .filter(|x| validate_report_safety(x, false))
+        .count()
+}
+
+fn part_2(filename: &str) -> usize {
+    parse_reports(filename)
         .iter()
-        .map(|x| validate_report_safety(x, true))
-        .filter(|x| *x)
-        .count();
-    println!(
-        "Number of safe reports with the Problem Dampener: {}",
-        safe_reports_2
-    );
+        .filter(|x| validate_report_safety(x, true))
+        .count()
 }
 
 /// Validates a report's safety. Reports are determined safe if the following
@@ -35,7 +48,7 @@ fn main() {
 ///
 /// If the input `enable_dampener` is set to `true`, reports that have
 /// a **single** bad level will be allowed.
-fn validate_report_safety(report: &Vec<u32>, enable_dampener: bool) -> bool {
+fn validate_report_safety(report: &[u32], enable_dampener: bool) -> bool {
     let mut direction = 0;
     let mut problems = 0;
     for i in 1..report.len() {
@@ -80,24 +93,41 @@ fn validate_report_safety(report: &Vec<u32>, enable_dampener: bool) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(test_sample, "sample.txt", part_1 => 2, part_2 => 4);
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 300);
+            let (safe, dampened) = with_input(&input, |f| (part_1(f), part_2(f)));
+            // Around a third are clean and another third have one stray level
+            assert!(safe >= 50, "only {} safe", safe);
+            assert!(dampened > safe && dampened < 300);
+        }
+    }
 
     #[test]
     fn test_part_1() {
-        assert_eq!(validate_report_safety(&vec![7, 6, 4, 2, 1], false), true);
-        assert_eq!(validate_report_safety(&vec![1, 2, 7, 8, 9], false), false);
-        assert_eq!(validate_report_safety(&vec![9, 7, 6, 2, 1], false), false);
-        assert_eq!(validate_report_safety(&vec![1, 3, 2, 4, 5], false), false);
-        assert_eq!(validate_report_safety(&vec![8, 6, 4, 4, 1], false), false);
-        assert_eq!(validate_report_safety(&vec![1, 3, 6, 7, 9], false), true);
+        assert!(validate_report_safety(&[7, 6, 4, 2, 1], false));
+        assert!(!validate_report_safety(&[1, 2, 7, 8, 9], false));
+        assert!(!validate_report_safety(&[9, 7, 6, 2, 1], false));
+        assert!(!validate_report_safety(&[1, 3, 2, 4, 5], false));
+        assert!(!validate_report_safety(&[8, 6, 4, 4, 1], false));
+        assert!(validate_report_safety(&[1, 3, 6, 7, 9], false));
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(validate_report_safety(&vec![7, 6, 4, 2, 1], true), true);
-        assert_eq!(validate_report_safety(&vec![1, 2, 7, 8, 9], true), false);
-        assert_eq!(validate_report_safety(&vec![9, 7, 6, 2, 1], true), false);
-        assert_eq!(validate_report_safety(&vec![1, 3, 2, 4, 5], true), true);
-        assert_eq!(validate_report_safety(&vec![8, 6, 4, 4, 1], true), true);
-        assert_eq!(validate_report_safety(&vec![1, 3, 6, 7, 9], true), true);
+        assert!(validate_report_safety(&[7, 6, 4, 2, 1], true));
+        assert!(!validate_report_safety(&[1, 2, 7, 8, 9], true));
+        assert!(!validate_report_safety(&[9, 7, 6, 2, 1], true));
+        assert!(validate_report_safety(&[1, 3, 2, 4, 5], true));
+        assert!(validate_report_safety(&[8, 6, 4, 4, 1], true));
+        assert!(validate_report_safety(&[1, 3, 6, 7, 9], true));
     }
 }
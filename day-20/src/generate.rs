@@ -0,0 +1,36 @@
+use std::collections::VecDeque;
+use utils::generate::{maze, Rng};
+
+/// A `size` by `size` racetrack (rounded up to odd): a single winding track with no
+/// branches, from `S` in the bottom left corner to `E` in the top right. It's the route
+/// through a random maze with everything off that route walled back up.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let maze = maze(rng, size, size);
+    let (rows, cols) = (maze.len(), maze[0].len());
+    let (start, end) = ((rows - 2, 1), (1, cols - 2));
+
+    // Walk back from the end along the breadth first search tree to find the track
+    let mut came_from = vec![vec![None; cols]; rows];
+    let mut queue = VecDeque::from([start]);
+    came_from[start.0][start.1] = Some(start);
+    while let Some((r, c)) = queue.pop_front() {
+        for (nr, nc) in [(r - 1, c), (r + 1, c), (r, c - 1), (r, c + 1)] {
+            if maze[nr][nc] && came_from[nr][nc].is_none() {
+                came_from[nr][nc] = Some((r, c));
+                queue.push_back((nr, nc));
+            }
+        }
+    }
+    let mut track = vec![vec!['#'; cols]; rows];
+    let mut at = end;
+    while at != start {
+        track[at.0][at.1] = '.';
+        at = came_from[at.0][at.1].unwrap();
+    }
+    track[start.0][start.1] = 'S';
+    track[end.0][end.1] = 'E';
+    track
+        .iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
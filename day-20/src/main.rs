@@ -1,17 +1,62 @@
+use array2d::Array2D;
 use itertools::Itertools;
-use pathfinding::num_traits::abs;
-use pathfinding::prelude::dijkstra;
-use std::iter::repeat;
-use utils::{parse_map, time, Tile};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::Path;
+use utils::render::{self, Image};
+use utils::{parse_map, read_lines, time, Point, Tile};
+
+mod generate;
 
 fn main() {
+    // `day-20 generate <seed> [size]` prints a random `size` square racetrack
+    if utils::generate::command(141, generate::input) {
+        return;
+    }
+    // `day-20 render <file.png> [duration] [threshold]` draws the track with where the
+    // cheats saving at least `threshold` start and end
+    let args = std::env::args().collect_vec();
+    if let (Some("render"), Some(path)) = (args.get(1).map(|a| a.as_str()), args.get(2)) {
+        let duration = args.get(3).map_or(20, |d| d.parse().unwrap());
+        let threshold = args.get(4).map_or(100, |t| t.parse().unwrap());
+        render_cheats("src/input.txt", duration, threshold, Path::new(path));
+        return;
+    }
     let (part1, time1) = time(|| cheat("src/input.txt", 2, 100));
     println!("Part 1: {} (took {} secs)", part1, time1.as_secs_f64());
-    let (part2, time2) = time(|| cheat("src/input.txt", 20, 100));
-    println!("Part 2: {} (took {} secs)", part2, time2.as_secs_f64());
+    let (part2, time2) = time(|| cheat_histogram("src/input.txt", 20));
+    println!(
+        "{}",
+        describe_histogram(&part2.range(100..).map(|(&k, &v)| (k, v)).collect())
+    );
+    println!(
+        "Part 2: {} (took {} secs)",
+        part2.range(100..).map(|(_, v)| v).sum::<usize>(),
+        time2.as_secs_f64()
+    );
 }
 
 fn cheat(filename: &str, cheat_duration: i32, threshold: i32) -> i32 {
+    cheat_histogram(filename, cheat_duration)
+        .range(threshold..)
+        .map(|(_, &count)| count as i32)
+        .sum()
+}
+
+/// How many cheats save each number of picoseconds. Instead of following one shortest
+/// path we take BFS distances from both the start and the exit, so a cheat can begin or
+/// end anywhere on the track, not just on a single best route. A cheat from `p` to `q`
+/// then costs `from_start[p] + |p - q| + to_exit[q]`, and we only look at the `q`s in
+/// the diamond of radius `cheat_duration` around each `p`.
+fn cheat_histogram(filename: &str, cheat_duration: i32) -> BTreeMap<i32, usize> {
+    let mut histogram: BTreeMap<i32, usize> = BTreeMap::new();
+    for_each_cheat(filename, cheat_duration, |_, _, saved| {
+        *histogram.entry(saved).or_default() += 1;
+    });
+    histogram
+}
+
+/// Calls `visit` with the start, end and time saved of every cheat that saves any time
+fn for_each_cheat(filename: &str, cheat_duration: i32, mut visit: impl FnMut(&Point, &Point, i32)) {
     let grid = parse_map(filename, |c: char| match c {
         '#' => Tile::Wall,
         '.' => Tile::Path,
@@ -21,50 +66,249 @@ fn cheat(filename: &str, cheat_duration: i32, threshold: i32) -> i32 {
     });
     let (start, _) = grid.iter().find(|(_, v)| **v == Tile::Start).unwrap();
     let (end, _) = grid.iter().find(|(_, v)| **v == Tile::Exit).unwrap();
-    let (shortest_path, _) = dijkstra(
-        start,
-        |p| {
-            p.neighbors()
-                .into_iter()
-                .filter(|(x, _)| *grid.get(x).unwrap() != Tile::Wall)
-                .collect_vec()
-        },
-        |p| p == end,
-    )
-    .unwrap();
-    let possible_cheats = repeat(shortest_path.iter())
-        .take(2)
-        .multi_cartesian_product()
-        .map(|x| (x[0], x[1]))
-        .filter(|(x, y)| x != y && abs(x.row - y.row) + abs(x.col - y.col) <= cheat_duration)
+    let from_start = distances(&grid, start);
+    let to_exit = distances(&grid, end);
+    let fastest = from_start[end];
+
+    for (p, &before) in &from_start {
+        for dr in -cheat_duration..=cheat_duration {
+            let reach = cheat_duration - dr.abs();
+            for dc in -reach..=reach {
+                let q = Point::new(p.row + dr, p.col + dc);
+                if let Some(&after) = to_exit.get(&q) {
+                    let saved = fastest - (before + dr.abs() + dc.abs() + after);
+                    if saved > 0 {
+                        visit(p, &q, saved);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Where the cheats saving at least `threshold` start and end, as (starts, ends)
+fn cheat_endpoints(
+    filename: &str,
+    cheat_duration: i32,
+    threshold: i32,
+) -> (HashSet<Point>, HashSet<Point>) {
+    let (mut starts, mut ends) = (HashSet::new(), HashSet::new());
+    for_each_cheat(filename, cheat_duration, |p, q, saved| {
+        if saved >= threshold {
+            starts.insert(p.clone());
+            ends.insert(q.clone());
+        }
+    });
+    (starts, ends)
+}
+
+/// Saves the track as a PNG with cheat starts in cyan and ends in magenta, or both in
+/// yellow where a tile is each
+fn render_cheats(filename: &str, cheat_duration: i32, threshold: i32, path: &Path) {
+    let rows = read_lines(filename)
+        .iter()
+        .map(|l| l.chars().collect_vec())
         .collect_vec();
-    let mut passing_cheats = 0;
-    let shortest_path_map = shortest_path.iter().enumerate().into_group_map_by(|x| x.1);
-    for (cs, ce) in possible_cheats {
-        let (start_pos, _) = shortest_path_map.get(&cs).unwrap()[0];
-        let (end_pos, _) = shortest_path_map.get(&ce).unwrap()[0];
-        if end_pos as i32 - start_pos as i32 - (abs(cs.row - ce.row) + abs(cs.col - ce.col))
-            >= threshold
-        {
-            passing_cheats += 1;
+    let track = Array2D::from_rows(&rows).unwrap();
+    let (starts, ends) = cheat_endpoints(filename, cheat_duration, threshold);
+    let tile = |p: &Point| (p.row as usize, p.col as usize);
+    let mut image = Image::render(&track, 4, render::cell_color);
+    image.overlay(starts.difference(&ends).map(tile), [70, 210, 220]);
+    image.overlay(ends.difference(&starts).map(tile), [210, 80, 210]);
+    image.overlay(starts.intersection(&ends).map(tile), [240, 200, 50]);
+    image.write_png(path).unwrap();
+}
+
+/// BFS distance from `from` to every reachable non-wall tile
+fn distances(grid: &HashMap<Point, Tile>, from: &Point) -> HashMap<Point, i32> {
+    let mut distances = HashMap::from([(from.clone(), 0)]);
+    let mut queue = VecDeque::from([from.clone()]);
+    while let Some(p) = queue.pop_front() {
+        let d = distances[&p];
+        for (n, _) in p.neighbors() {
+            if grid.get(&n).is_some_and(|t| *t != Tile::Wall) && !distances.contains_key(&n) {
+                distances.insert(n.clone(), d + 1);
+                queue.push_back(n);
+            }
         }
     }
-    passing_cheats
+    distances
+}
+
+/// Lists the histogram the way the puzzle does
+fn describe_histogram(histogram: &BTreeMap<i32, usize>) -> String {
+    histogram
+        .iter()
+        .map(|(saved, count)| match count {
+            1 => format!("There is one cheat that saves {} picoseconds.", saved),
+            _ => format!(
+                "There are {} cheats that save {} picoseconds.",
+                count, saved
+            ),
+        })
+        .join("\n")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::differential::{check, shrink_int};
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(
+        test_sample,
+        "sample.txt",
+        |f| cheat(f, 2, 1) => 44,
+        |f| cheat(f, 20, 50) => 285,
+    );
+    aoc_test!(
+        test_input,
+        optional "input.txt",
+        |f| cheat(f, 2, 100),
+        |f| cheat(f, 20, 100),
+    );
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 31);
+            // A single track, so every tile on it has at most two neighbours on it
+            let track = input.lines().map(|l| l.as_bytes()).collect::<Vec<_>>();
+            for r in 1..track.len() - 1 {
+                for c in 1..track[0].len() - 1 {
+                    let around = [
+                        track[r - 1][c],
+                        track[r + 1][c],
+                        track[r][c - 1],
+                        track[r][c + 1],
+                    ];
+                    if track[r][c] != b'#' {
+                        assert!(around.iter().filter(|&&t| t != b'#').count() <= 2);
+                    }
+                }
+            }
+            let (short, long) =
+                with_input(&input, |f| (cheat_histogram(f, 2), cheat_histogram(f, 20)));
+            // The grid is a chessboard, so a shortcut always saves an even number of steps
+            assert!(short.keys().chain(long.keys()).all(|saved| saved % 2 == 0));
+            assert!(short.values().sum::<usize>() <= long.values().sum::<usize>());
+        }
+    }
+
+    /// Walks the track from `S` to `E` and tries every pair of tiles on it as a cheat. Only
+    /// right for a single track, since the time to the exit is just what's left of the walk.
+    fn naive_histogram(input: &str, cheat_duration: i32) -> BTreeMap<i32, usize> {
+        let grid = input.lines().map(|l| l.as_bytes()).collect_vec();
+        let find = |tile| {
+            (0..grid.len())
+                .flat_map(|r| (0..grid[r].len()).map(move |c| (r, c)))
+                .find(|&(r, c)| grid[r][c] == tile)
+                .unwrap()
+        };
+        let (mut at, end) = (find(b'S'), find(b'E'));
+        let mut track = vec![at];
+        while at != end {
+            let (r, c) = at;
+            at = [(r - 1, c), (r + 1, c), (r, c - 1), (r, c + 1)]
+                .into_iter()
+                .find(|&(nr, nc)| grid[nr][nc] != b'#' && !track.contains(&(nr, nc)))
+                .unwrap();
+            track.push(at);
+        }
+        let mut histogram = BTreeMap::new();
+        for (i, j) in (0..track.len()).tuple_combinations() {
+            let distance =
+                (track[i].0.abs_diff(track[j].0) + track[i].1.abs_diff(track[j].1)) as i32;
+            let saved = (j - i) as i32 - distance;
+            if distance <= cheat_duration && saved > 0 {
+                *histogram.entry(saved).or_default() += 1;
+            }
+        }
+        histogram
+    }
+
+    #[test]
+    fn test_against_naive() {
+        // Cases are (seed, size, cheat duration), so shrinking makes the track smaller
+        // rather than trying to cut bits out of it. Any smaller than 5 and the start and
+        // exit land on the same tile.
+        check(
+            100,
+            |rng| {
+                (
+                    rng.next_u64() % 1000,
+                    rng.below(20) + 5,
+                    rng.range(1..21) as i32,
+                )
+            },
+            |&(seed, size, duration)| {
+                let mut smaller = Vec::new();
+                if size > 5 {
+                    smaller.push((seed, size - 2, duration));
+                }
+                smaller.extend(
+                    shrink_int(duration as i64)
+                        .into_iter()
+                        .map(|d| (seed, size, d as i32)),
+                );
+                smaller.extend(
+                    shrink_int(seed as i64)
+                        .into_iter()
+                        .map(|s| (s as u64, size, duration)),
+                );
+                smaller
+            },
+            |&(seed, size, duration)| {
+                naive_histogram(&generate::input(&mut Rng::new(seed), size), duration)
+            },
+            |&(seed, size, duration)| {
+                let input = generate::input(&mut Rng::new(seed), size);
+                with_input(&input, |f| cheat_histogram(f, duration))
+            },
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let histogram = cheat_histogram("src/sample.txt", 2);
+        assert_eq!(
+            histogram,
+            BTreeMap::from([
+                (2, 14),
+                (4, 14),
+                (6, 2),
+                (8, 4),
+                (10, 2),
+                (12, 3),
+                (20, 1),
+                (36, 1),
+                (38, 1),
+                (40, 1),
+                (64, 1)
+            ])
+        );
+        let description = describe_histogram(&histogram);
+        assert!(description.starts_with("There are 14 cheats that save 2 picoseconds.\n"));
+        assert!(description.ends_with("There is one cheat that saves 64 picoseconds."));
+    }
 
     #[test]
-    fn test_part_1() {
-        let result = cheat("src/sample.txt", 2, 1);
-        assert_eq!(result, 44);
+    fn test_cheat_endpoints() {
+        // The 64 picosecond cheat is the only one saving that much, and it lands on the exit
+        let (starts, ends) = cheat_endpoints("src/sample.txt", 2, 64);
+        assert_eq!(starts, HashSet::from([Point::new(7, 7)]));
+        assert_eq!(ends, HashSet::from([Point::new(7, 5)]));
+        let (starts, ends) = cheat_endpoints("src/sample.txt", 2, 1);
+        assert!(starts.len() <= 44 && ends.len() <= 44);
     }
 
     #[test]
-    fn test_part_2() {
-        let result = cheat("src/sample.txt", 20, 50);
-        assert_eq!(result, 285);
+    fn test_branching_maze() {
+        // Two cheats tie for the best saving: one cuts through the wall from the shortest
+        // path, and the other starts on the dead end branch, off the shortest path entirely
+        let histogram = cheat_histogram("src/sample-branching.txt", 2);
+        assert_eq!(histogram, BTreeMap::from([(2, 1), (4, 2)]));
     }
 }
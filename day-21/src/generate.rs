@@ -0,0 +1,13 @@
+use utils::generate::Rng;
+
+/// `size` different door codes, three digits and then `A`
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let mut codes: Vec<String> = Vec::new();
+    while codes.len() < size.min(1000) {
+        let code = format!("{:03}A", rng.below(1000));
+        if !codes.contains(&code) {
+            codes.push(code);
+        }
+    }
+    codes.iter().map(|c| format!("{}\n", c)).collect()
+}
@@ -0,0 +1,118 @@
+use crate::button::{Button, ToButton};
+use std::collections::{HashMap, VecDeque};
+use utils::Point;
+
+pub const NUMERIC_LAYOUT: &str = "
++---+---+---+
+| 7 | 8 | 9 |
++---+---+---+
+| 4 | 5 | 6 |
++---+---+---+
+| 1 | 2 | 3 |
++---+---+---+
+    | 0 | A |
+    +---+---+
+";
+
+pub const DIRECTIONAL_LAYOUT: &str = "
+    +---+---+
+    | ^ | A |
++---+---+---+
+| < | v | > |
++---+---+---+
+";
+
+#[derive(Debug, Clone)]
+pub struct Keypad {
+    buttons: HashMap<Button, Point>,
+    positions: HashMap<Point, Button>,
+}
+
+impl Keypad {
+    /// Parses a keypad drawn the way the puzzle draws them, with `+---+` borders and the
+    /// label in the middle of each `|   |` cell. Borders are optional, so `"789\n456"` works
+    /// too. Anything blank is a gap the robot arm can't hover over.
+    pub fn parse(layout: &str) -> Keypad {
+        let mut buttons: HashMap<Button, Point> = HashMap::new();
+        let rows = layout
+            .lines()
+            .filter(|l| !l.trim().is_empty() && !l.trim().starts_with('+'));
+        for (row, line) in rows.enumerate() {
+            let boxed = line.contains('|');
+            for (i, c) in line.chars().enumerate() {
+                if c == ' ' || c == '|' || (boxed && i % 4 != 2) {
+                    continue;
+                }
+                let col = if boxed { i / 4 } else { i };
+                let position = Point::new(row as i32, col as i32);
+                if buttons.insert(c.to_button(), position).is_some() {
+                    panic!("Button {} appears twice in keypad", c);
+                }
+            }
+        }
+        let positions = buttons.iter().map(|(b, p)| (p.clone(), *b)).collect();
+        Keypad { buttons, positions }
+    }
+
+    pub fn position(&self, button: Button) -> &Point {
+        self.buttons
+            .get(&button)
+            .unwrap_or_else(|| panic!("No {} button on this keypad", button))
+    }
+
+    pub fn has(&self, button: Button) -> bool {
+        self.buttons.contains_key(&button)
+    }
+
+    pub fn button_at(&self, position: &Point) -> Option<Button> {
+        self.positions.get(position).copied()
+    }
+
+    /// Every shortest sequence of arrow presses that moves the arm from `from` to `to`
+    /// without crossing a gap. Usually that's the two L-shaped paths (or one, if the corner
+    /// is a gap), but gaps in odd places can force zig-zags or detours.
+    pub fn paths(&self, from: Button, to: Button) -> Vec<Vec<Button>> {
+        let (start, target) = (self.position(from), self.position(to));
+        // BFS backwards from the target so we can walk downhill from the start
+        let mut distance: HashMap<&Point, usize> = HashMap::from([(target, 0)]);
+        let mut queue = VecDeque::from([target]);
+        while let Some(p) = queue.pop_front() {
+            for (n, _) in p.neighbors() {
+                if let Some((n, _)) = self.positions.get_key_value(&n) {
+                    if !distance.contains_key(n) {
+                        distance.insert(n, distance[p] + 1);
+                        queue.push_back(n);
+                    }
+                }
+            }
+        }
+
+        let mut paths: Vec<Vec<Button>> = Vec::new();
+        if !distance.contains_key(start) {
+            return paths;
+        }
+        let mut stack: Vec<(&Point, Vec<Button>)> = vec![(start, Vec::new())];
+        while let Some((p, path)) = stack.pop() {
+            if p == target {
+                paths.push(path);
+                continue;
+            }
+            for (direction, (dr, dc)) in [
+                (Button::Up, (-1, 0)),
+                (Button::Down, (1, 0)),
+                (Button::Left, (0, -1)),
+                (Button::Right, (0, 1)),
+            ] {
+                let n = Point::new(p.row + dr, p.col + dc);
+                if let Some((&n, &d)) = distance.get_key_value(&n) {
+                    if d + 1 == distance[p] {
+                        let mut next = path.clone();
+                        next.push(direction);
+                        stack.push((n, next));
+                    }
+                }
+            }
+        }
+        paths
+    }
+}
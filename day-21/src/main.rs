@@ -1,108 +1,306 @@
 mod button;
+mod generate;
+mod keypad;
+mod simulator;
 
 use crate::button::{Button, ToButton};
+use crate::keypad::{Keypad, DIRECTIONAL_LAYOUT, NUMERIC_LAYOUT};
+use crate::simulator::verify;
 use itertools::Itertools;
-use memoize::memoize;
 use std::collections::HashMap;
-use std::iter::zip;
-use std::sync::LazyLock;
-use utils::{time, Point};
-
-static NUMERIC_PAD: LazyLock<HashMap<Button, Point>> = LazyLock::new(|| {
-    vec![
-        (Button::Seven, Point::new(0, 0)),
-        (Button::Eight, Point::new(0, 1)),
-        (Button::Nine, Point::new(0, 2)),
-        (Button::Four, Point::new(1, 0)),
-        (Button::Five, Point::new(1, 1)),
-        (Button::Six, Point::new(1, 2)),
-        (Button::One, Point::new(2, 0)),
-        (Button::Two, Point::new(2, 1)),
-        (Button::Three, Point::new(2, 2)),
-        (Button::Zero, Point::new(3, 1)),
-        (Button::Activate, Point::new(3, 2)),
-    ]
-    .into_iter()
-    .collect()
-});
-
-static D_PAD: LazyLock<HashMap<Button, Point>> = LazyLock::new(|| {
-    vec![
-        (Button::Up, Point::new(0, 1)),
-        (Button::Down, Point::new(1, 1)),
-        (Button::Left, Point::new(1, 0)),
-        (Button::Right, Point::new(1, 2)),
-        (Button::Activate, Point::new(0, 2)),
-    ]
-    .into_iter()
-    .collect()
-});
+use std::iter::once;
+use utils::{read_lines, time};
 
 fn main() {
-    let (part1, time1) = time(|| solve(4));
+    // `day-21 generate <seed> [size]` prints `size` random door codes
+    if utils::generate::command(5, generate::input) {
+        return;
+    }
+    let (part1, time1) = time(|| solve("src/input.txt", &robot_chain(2)));
     println!("Part 1: {} (took {} secs)", part1, time1.as_secs_f64());
-    let (part2, time2) = time(|| solve(27));
+    // Show (and double check) what we'd actually press for part 1
+    let chain = robot_chain(2);
+    let mut solver = Solver::new(&chain);
+    for code in read_lines("src/input.txt")
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+    {
+        let presses = solver.presses(code.trim()).collect_vec();
+        verify(&chain, presses.iter().copied(), code.trim()).unwrap();
+        println!("{}: {}", code.trim(), presses.iter().join(""));
+    }
+    let (part2, time2) = time(|| solve("src/input.txt", &robot_chain(25)));
     println!("Part 2: {} (took {} secs)", part2, time2.as_secs_f64());
 }
 
-fn solve(keypads: i32) -> u128 {
-    let mut r: u128 = 0;
-    for i in vec!["319A", "670A", "349A", "964A", "586A"] {
-        r += shortest(i.to_string(), keypads) * i[0..3].to_string().parse::<u128>().unwrap()
-    }
-    r
+/// The door's numeric keypad, a directional keypad for each robot in between, and the
+/// directional keypad we press ourselves
+fn robot_chain(robots: usize) -> Vec<Keypad> {
+    once(Keypad::parse(NUMERIC_LAYOUT))
+        .chain((0..=robots).map(|_| Keypad::parse(DIRECTIONAL_LAYOUT)))
+        .collect()
+}
+
+fn solve(filename: &str, chain: &[Keypad]) -> u128 {
+    let mut solver = Solver::new(chain);
+    read_lines(filename)
+        .iter()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(|code| {
+            let numeric = code
+                .chars()
+                .filter(|c| c.is_ascii_digit())
+                .collect::<String>()
+                .parse::<u128>()
+                .unwrap_or(0);
+            solver.shortest(code) * numeric
+        })
+        .sum()
 }
 
-#[memoize]
-fn shortest(moves: String, depth: i32) -> u128 {
-    if depth == 1 {
-        return moves.chars().count() as u128;
+/// Works out how many presses it takes on the last keypad of `chain` to type a code on the
+/// first one. Each keypad is operated by a robot whose arm is steered by the next keypad
+/// along, and every arm starts (and finishes each press) on `A`.
+struct Solver<'a> {
+    chain: &'a [Keypad],
+    /// (layer, from, to) -> presses on the last keypad to move layer's arm and press `to`,
+    /// and the arrows (plus the final `A`) on the next layer up that achieve it
+    cache: HashMap<(usize, Button, Button), (u128, Vec<Button>)>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(chain: &'a [Keypad]) -> Solver<'a> {
+        Solver {
+            chain,
+            cache: HashMap::new(),
+        }
+    }
+
+    fn shortest(&mut self, code: &str) -> u128 {
+        let buttons = code.chars().map(|c| c.to_button()).collect::<Vec<_>>();
+        self.sequence_cost(0, &buttons)
     }
-    let numeric = "1234567890".chars().any(|x| moves.contains(x));
-    let mut res = 0u128;
-    for (key1, key2) in zip(format!("A{}", moves).chars(), moves.chars()) {
-        let sp = shortest_path(key1.to_button(), key2.to_button(), numeric);
-        res += sp
+
+    /// Presses needed to type `buttons` on keypad `layer`, starting from `A`
+    fn sequence_cost(&mut self, layer: usize, buttons: &[Button]) -> u128 {
+        once(Button::Activate)
+            .chain(buttons.iter().copied())
+            .zip(buttons.iter().copied())
+            .map(|(from, to)| self.press_cost(layer, from, to))
+            .sum()
+    }
+
+    fn press_cost(&mut self, layer: usize, from: Button, to: Button) -> u128 {
+        if layer == self.chain.len() - 1 {
+            // That's us, we just press it
+            return 1;
+        }
+        if let Some((cost, _)) = self.cache.get(&(layer, from, to)) {
+            return *cost;
+        }
+        let (cost, path) = self.chain[layer]
+            .paths(from, to)
             .into_iter()
-            .map(|s| shortest(format!("{}A", s), depth - 1))
-            .min()
-            .unwrap()
+            .map(|mut path| {
+                path.push(Button::Activate);
+                (self.sequence_cost(layer + 1, &path), path)
+            })
+            .min_by_key(|(cost, _)| *cost)
+            .unwrap_or_else(|| panic!("Can't get from {} to {}", from, to));
+        self.cache.insert((layer, from, to), (cost, path));
+        cost
+    }
+
+    /// The arrows and `A` to press on `layer + 1` to get `layer`'s arm from `from` to `to`
+    /// and press it
+    fn best_path(&mut self, layer: usize, from: Button, to: Button) -> Vec<Button> {
+        self.press_cost(layer, from, to);
+        self.cache[&(layer, from, to)].1.clone()
+    }
+
+    /// Streams one optimal sequence of presses on the last keypad that types `code`. For
+    /// long robot chains the full sequence is far too big to hold, so it's expanded lazily
+    /// one layer at a time as the iterator is pulled.
+    fn presses(&mut self, code: &str) -> Presses<'_, 'a> {
+        let buttons = code.chars().map(|c| c.to_button()).collect();
+        Presses {
+            solver: self,
+            stack: vec![(0, buttons, 0, Button::Activate)],
+        }
+    }
+}
+
+struct Presses<'s, 'a> {
+    solver: &'s mut Solver<'a>,
+    /// (layer, buttons to press on that layer, how many we've done, where the arm is)
+    stack: Vec<(usize, Vec<Button>, usize, Button)>,
+}
+
+impl Iterator for Presses<'_, '_> {
+    type Item = Button;
+
+    fn next(&mut self) -> Option<Button> {
+        let last = self.solver.chain.len() - 1;
+        loop {
+            let (layer, buttons, done, arm) = self.stack.last_mut()?;
+            if *done == buttons.len() {
+                self.stack.pop();
+                continue;
+            }
+            let (layer, to, from) = (*layer, buttons[*done], *arm);
+            *done += 1;
+            *arm = to;
+            if layer == last {
+                return Some(to);
+            }
+            let path = self.solver.best_path(layer, from, to);
+            self.stack.push((layer + 1, path, 0, Button::Activate));
+        }
     }
-    res
 }
 
-#[memoize]
-fn shortest_path(a: Button, b: Button, numeric: bool) -> Vec<String> {
-    let keypad = if numeric { &NUMERIC_PAD } else { &D_PAD };
-    let pos1 = keypad.get(&a).unwrap().clone();
-    let pos2 = keypad.get(&b).unwrap().clone();
-    let (dr, dc) = (pos2.row - pos1.row, pos2.col - pos1.col);
-
-    let row_moves = if dr >= 0 {
-        "v".repeat(dr as usize)
-    } else {
-        "^".repeat(dr.abs() as usize)
-    };
-    let col_moves = if dc >= 0 {
-        ">".repeat(dc as usize)
-    } else {
-        "<".repeat(dc.abs() as usize)
-    };
-
-    if dr == 0 && dc == 0 {
-        vec!["".to_string()]
-    } else if dr == 0 {
-        vec![col_moves]
-    } else if dc == 0 {
-        vec![row_moves]
-    } else if !keypad.values().contains(&Point::new(pos1.row, pos2.col)) {
-        vec![format!("{}{}", row_moves, col_moves)]
-    } else if !keypad.values().contains(&Point::new(pos2.row, pos1.col)) {
-        vec![format!("{}{}", col_moves, row_moves)]
-    } else {
-        vec![
-            format!("{}{}", col_moves, row_moves),
-            format!("{}{}", row_moves, col_moves),
-        ]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::SimError;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(
+        test_sample,
+        "sample.txt",
+        |f| solve(f, &robot_chain(2)) => 126384,
+        |f| solve(f, &robot_chain(25)) => 154115708116294,
+    );
+    aoc_test!(
+        test_input,
+        optional "input.txt",
+        |f| solve(f, &robot_chain(2)),
+        |f| solve(f, &robot_chain(25)),
+    );
+
+    #[test]
+    fn test_sample_lengths() {
+        let chain = robot_chain(2);
+        let mut solver = Solver::new(&chain);
+        assert_eq!(solver.shortest("029A"), 68);
+        assert_eq!(solver.shortest("379A"), 64);
+        // Typing straight onto the numeric keypad is just the code
+        assert_eq!(Solver::new(&chain[..1]).shortest("029A"), 4);
+        assert_eq!(Solver::new(&chain[..2]).shortest("029A"), 12);
+        assert_eq!(Solver::new(&chain[..3]).shortest("029A"), 28);
+    }
+
+    #[test]
+    fn test_press_sequences() {
+        let chain = robot_chain(2);
+        let mut solver = Solver::new(&chain);
+        for code in ["029A", "980A", "179A", "456A", "379A"] {
+            let presses = solver.presses(code).collect_vec();
+            assert_eq!(presses.len() as u128, solver.shortest(code));
+            assert_eq!(verify(&chain, presses, code), Ok(()));
+        }
+        // Shorter chains give the intermediate sequences from the puzzle
+        let chain = robot_chain(0);
+        let presses = Solver::new(&chain).presses("029A").join("");
+        assert_eq!(presses.len(), 12);
+        assert_eq!(
+            verify(&chain, presses.chars().map(|c| c.to_button()), "029A"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_streaming_long_chains() {
+        // Far too long to collect, but we can still pull the start of it
+        let chain = robot_chain(25);
+        let mut solver = Solver::new(&chain);
+        assert!(solver.shortest("029A") > 1_000_000_000);
+        assert_eq!(solver.presses("029A").take(10_000).count(), 10_000);
+        // A chain small enough to replay end to end
+        let chain = robot_chain(6);
+        let mut solver = Solver::new(&chain);
+        let presses = solver.presses("379A").collect_vec();
+        assert_eq!(presses.len() as u128, solver.shortest("379A"));
+        assert_eq!(verify(&chain, presses, "379A"), Ok(()));
+    }
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 10);
+            // Every extra robot makes every code take more presses
+            let totals = (0..5)
+                .map(|robots| with_input(&input, |f| solve(f, &robot_chain(robots))))
+                .collect_vec();
+            assert!(totals.windows(2).all(|w| w[0] < w[1]));
+            let chain = robot_chain(2);
+            let mut solver = Solver::new(&chain);
+            for code in input.lines() {
+                let presses = solver.presses(code).collect_vec();
+                assert_eq!(verify(&chain, presses, code), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_layout() {
+        // The gap sits in the middle, so going from 2 to 0 has to detour around it
+        let layout = "
+            +---+---+---+
+            | 1 | 2 | 3 |
+            +---+---+---+
+            | 4 |   | 5 |
+            +---+---+---+
+            | A | 0 | 6 |
+            +---+---+---+
+        ";
+        let custom = Keypad::parse(
+            &layout
+                .lines()
+                .map(|l| l.trim())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        assert_eq!(custom.paths(Button::Two, Button::Zero).len(), 2);
+        assert!(custom
+            .paths(Button::Two, Button::Zero)
+            .iter()
+            .all(|p| p.len() == 4));
+        let chain = vec![custom, Keypad::parse(DIRECTIONAL_LAYOUT)];
+        assert_eq!(Solver::new(&chain).shortest("20A"), 11);
+        let presses = Solver::new(&chain).presses("20A").collect_vec();
+        assert_eq!(verify(&chain, presses, "20A"), Ok(()));
+        // The compact form without borders describes the same keypad
+        let compact = Keypad::parse("123\n4 5\nA06");
+        let chain = vec![compact, Keypad::parse(DIRECTIONAL_LAYOUT)];
+        assert_eq!(Solver::new(&chain).shortest("20A"), 11);
+    }
+
+    #[test]
+    fn test_simulator_errors() {
+        let chain = robot_chain(0);
+        let presses = |s: &str| s.chars().map(|c| c.to_button()).collect_vec();
+        // Straight left from A goes through 0 and then into the gap
+        assert_eq!(
+            verify(&chain, presses("<<A"), "1"),
+            Err(SimError::Gap { press: 1, layer: 0 })
+        );
+        assert_eq!(
+            verify(&chain, presses("<A"), "1"),
+            Err(SimError::WrongCode {
+                typed: "0".to_string()
+            })
+        );
+        assert_eq!(
+            verify(&chain, presses("7"), "7"),
+            Err(SimError::InvalidPress {
+                press: 0,
+                layer: 1,
+                button: Button::Seven
+            })
+        );
     }
 }
@@ -0,0 +1,90 @@
+use crate::button::Button;
+use crate::keypad::Keypad;
+use utils::Point;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum SimError {
+    /// Press number `press` moved the arm over `layer` into a gap
+    Gap { press: usize, layer: usize },
+    /// Press number `press` asked for a button that isn't on `layer`, or isn't something
+    /// the arm above it knows how to do
+    InvalidPress {
+        press: usize,
+        layer: usize,
+        button: Button,
+    },
+    /// Everything was legal but the door got the wrong code
+    WrongCode { typed: String },
+}
+
+/// Replays presses on the last keypad of `chain` through every robot, returning what gets
+/// typed on the first keypad. Every arm starts on `A`.
+pub fn simulate(
+    chain: &[Keypad],
+    presses: impl IntoIterator<Item = Button>,
+) -> Result<String, SimError> {
+    let last = chain.len() - 1;
+    let mut arms: Vec<Point> = chain[..last]
+        .iter()
+        .map(|k| k.position(Button::Activate).clone())
+        .collect();
+    let mut typed = String::new();
+    for (press, button) in presses.into_iter().enumerate() {
+        if !chain[last].has(button) {
+            return Err(SimError::InvalidPress {
+                press,
+                layer: last,
+                button,
+            });
+        }
+        // `button` was just pressed on `layer`, which steers the arm over `layer - 1`
+        let (mut button, mut layer) = (button, last);
+        while layer > 0 {
+            let arm = &mut arms[layer - 1];
+            let (dr, dc) = match button {
+                Button::Activate => {
+                    button = chain[layer - 1].button_at(arm).unwrap();
+                    layer -= 1;
+                    continue;
+                }
+                Button::Up => (-1, 0),
+                Button::Down => (1, 0),
+                Button::Left => (0, -1),
+                Button::Right => (0, 1),
+                _ => {
+                    return Err(SimError::InvalidPress {
+                        press,
+                        layer,
+                        button,
+                    })
+                }
+            };
+            *arm = Point::new(arm.row + dr, arm.col + dc);
+            if chain[layer - 1].button_at(arm).is_none() {
+                return Err(SimError::Gap {
+                    press,
+                    layer: layer - 1,
+                });
+            }
+            break;
+        }
+        if layer == 0 {
+            typed.push_str(&button.to_string());
+        }
+    }
+    Ok(typed)
+}
+
+/// Checks that `presses` types exactly `code` without any arm ever hovering over a gap
+pub fn verify(
+    chain: &[Keypad],
+    presses: impl IntoIterator<Item = Button>,
+    code: &str,
+) -> Result<(), SimError> {
+    let typed = simulate(chain, presses)?;
+    if typed == code {
+        Ok(())
+    } else {
+        Err(SimError::WrongCode { typed })
+    }
+}
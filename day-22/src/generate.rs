@@ -0,0 +1,8 @@
+use utils::generate::Rng;
+
+/// `size` buyers' starting secrets, anywhere a secret can be
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| format!("{}\n", rng.below(16777215) + 1))
+        .collect()
+}
@@ -1,17 +1,32 @@
-use itertools::{iterate, Itertools};
+use itertools::Itertools;
 use rayon::prelude::*;
-use std::collections::HashMap;
 use utils::{read_lines, time};
 
+mod generate;
+
 fn main() {
-    let (part1, time1) = time(|| part_1());
+    // `day-22 generate <seed> [size]` prints `size` random starting secrets
+    if utils::generate::command(2000, generate::input) {
+        return;
+    }
+    let (part1, time1) = time(|| part_1("src/input.txt"));
     println!("Part 1: {} (took {} secs)", part1, time1.as_secs_f64());
-    let (part2, time2) = time(|| part_2());
-    println!("Part 2: {} (took {} secs)", part2, time2.as_secs_f64());
+    let (part2, time2) = time(|| part_2("src/input.txt"));
+    println!(
+        "Part 2: {} (took {} secs)",
+        part2.total,
+        time2.as_secs_f64()
+    );
+    println!(
+        "Sell on {:?}, {} of {} buyers bite",
+        part2.changes,
+        part2.sale_prices.iter().flatten().count(),
+        part2.sale_prices.len()
+    );
 }
 
-fn part_1() -> i64 {
-    read_lines("src/input.txt")
+fn part_1(filename: &str) -> i64 {
+    read_lines(filename)
         .par_iter()
         .map(|l| l.parse::<i64>())
         .map(|n| nth_secret(n.unwrap(), 2000))
@@ -21,43 +36,97 @@ fn part_1() -> i64 {
 fn step(mut secret_num: i64) -> i64 {
     // Step 1
     secret_num = (secret_num * 64) ^ secret_num;
-    secret_num = secret_num % 16777216;
+    secret_num %= 16777216;
     // Step 2
     secret_num = (secret_num / 32) ^ secret_num;
-    secret_num = secret_num % 16777216;
+    secret_num %= 16777216;
     // Step 3
     secret_num = (secret_num * 2048) ^ secret_num;
     secret_num % 16777216
 }
 
-fn part_2() -> i64 {
-    // Reminds me of LINQ......
-    read_lines("src/input.txt")
-        .par_iter()
-        .map(|l| l.parse::<i64>())
-        .map(|p| {
-            let prices = iterate(p.unwrap(), |secret_num: &i64| step(*secret_num))
-                .take(2001)
-                .map(|n| n % 10)
-                .collect_vec();
-            prices
-                .into_iter()
-                .rev()
-                .tuple_windows()
-                .map(|(a, b, c, d, e)| ((d - e, c - d, b - c, a - b), a))
-                .collect::<HashMap<_, _>>()
+fn part_2(filename: &str) -> Market {
+    let seeds = read_lines(filename)
+        .iter()
+        .map(|l| l.parse::<i64>().unwrap())
+        .collect_vec();
+    best_sequence(&seeds)
+}
+
+/// Four price changes in a row, each in -9..=9, packed into a base 19 number
+const SEQUENCES: usize = 19 * 19 * 19 * 19;
+
+#[derive(Debug)]
+struct Market {
+    /// Bananas we get by selling on `changes`
+    total: i64,
+    changes: [i64; 4],
+    /// What each buyer sold for, or `None` if the sequence never came up for them
+    sale_prices: Vec<Option<i64>>,
+}
+
+fn decode_changes(index: usize) -> [i64; 4] {
+    let mut changes = [0; 4];
+    let mut index = index;
+    for change in changes.iter_mut().rev() {
+        *change = (index % 19) as i64 - 9;
+        index /= 19;
+    }
+    changes
+}
+
+/// Calls `f(index, price)` for every sequence of four changes a buyer sees, in order.
+/// The index rolls along with the prices so nothing gets allocated.
+fn for_each_sequence(seed: i64, mut f: impl FnMut(usize, i64)) {
+    let mut secret = seed;
+    let mut price = secret % 10;
+    let mut index = 0;
+    for n in 1..=2000 {
+        secret = step(secret);
+        let next = secret % 10;
+        index = (index * 19 + (next - price + 9) as usize) % SEQUENCES;
+        price = next;
+        if n >= 4 {
+            f(index, price);
+        }
+    }
+}
+
+fn best_sequence(seeds: &[i64]) -> Market {
+    let mut totals = vec![0i64; SEQUENCES];
+    // Which buyer last saw each sequence. Only the first time counts, and stamping with
+    // the buyer number means we never have to clear it between buyers.
+    let mut seen = vec![0usize; SEQUENCES];
+    for (buyer, &seed) in seeds.iter().enumerate() {
+        for_each_sequence(seed, |index, price| {
+            if seen[index] != buyer + 1 {
+                seen[index] = buyer + 1;
+                totals[index] += price;
+            }
+        });
+    }
+    let (best, &total) = totals
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, &total)| total)
+        .unwrap();
+    let sale_prices = seeds
+        .iter()
+        .map(|&seed| {
+            let mut sold = None;
+            for_each_sequence(seed, |index, price| {
+                if index == best && sold.is_none() {
+                    sold = Some(price);
+                }
+            });
+            sold
         })
-        .reduce(
-            || HashMap::new(),
-            |mut accumulator, val| {
-                val.into_iter()
-                    .for_each(|(k, v)| *accumulator.entry(k).or_insert(0) += v);
-                accumulator
-            },
-        )
-        .into_values()
-        .max()
-        .unwrap()
+        .collect();
+    Market {
+        total,
+        changes: decode_changes(best),
+        sale_prices,
+    }
 }
 
 fn nth_secret(mut secret_num: i64, n: usize) -> i64 {
@@ -69,7 +138,15 @@ fn nth_secret(mut secret_num: i64, n: usize) -> i64 {
 
 #[cfg(test)]
 mod tests {
-    use crate::nth_secret;
+    use crate::{best_sequence, nth_secret, part_1, part_2, step};
+    use itertools::Itertools;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(test_sample_1, "sample-1.txt", part_1 => 37327623);
+    aoc_test!(test_sample_2, "sample-2.txt", |f| part_2(f).total => 23);
+    aoc_test!(test_input, optional "input.txt", part_1, |f| part_2(f).total);
 
     #[test]
     fn test_part_1() {
@@ -78,4 +155,40 @@ mod tests {
         assert_eq!(nth_secret(100, 2000), 15273692);
         assert_eq!(nth_secret(2024, 2000), 8667524);
     }
+
+    #[test]
+    fn test_part_2() {
+        let market = best_sequence(&[1, 2, 3, 2024]);
+        assert_eq!(market.total, 23);
+        assert_eq!(market.changes, [-2, 1, -1, 3]);
+        assert_eq!(market.sale_prices, vec![Some(7), Some(7), None, Some(9)]);
+    }
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..3 {
+            let input = crate::generate::input(&mut Rng::new(seed), 50);
+            let market = with_input(&input, part_2);
+            assert_eq!(market.sale_prices.len(), 50);
+            assert_eq!(
+                market.total,
+                market.sale_prices.iter().flatten().sum::<i64>()
+            );
+            // Walk each buyer's prices the long way round and look for the changes
+            for (line, sold) in input.lines().zip(&market.sale_prices) {
+                let prices = (0..2000)
+                    .scan(line.parse::<i64>().unwrap(), |secret, _| {
+                        let price = *secret % 10;
+                        *secret = step(*secret);
+                        Some(price)
+                    })
+                    .chain([nth_secret(line.parse().unwrap(), 2000) % 10])
+                    .collect_vec();
+                let first = prices
+                    .windows(5)
+                    .find(|w| w.windows(2).map(|p| p[1] - p[0]).collect_vec() == market.changes);
+                assert_eq!(first.map(|w| w[4]), *sold);
+            }
+        }
+    }
 }
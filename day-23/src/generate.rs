@@ -0,0 +1,33 @@
+use itertools::Itertools;
+use utils::generate::Rng;
+
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    network(rng, size).0
+}
+
+/// A LAN of `size` computers (at most 676, there are only so many names) with sparse random
+/// links, plus a party of thirteen who are all linked to each other. Random links almost
+/// never make a group bigger than four, so the party is the answer to part 2 and is
+/// returned alongside the connection list.
+pub fn network(rng: &mut Rng, size: usize) -> (String, Vec<String>) {
+    let mut names = ('a'..='z')
+        .cartesian_product('a'..='z')
+        .map(|(a, b)| format!("{}{}", a, b))
+        .collect_vec();
+    rng.shuffle(&mut names);
+    names.truncate(size.clamp(2, names.len()));
+    let party = names.len().min(13);
+    let link_chance = 12.0 / names.len() as f64;
+    let mut links = Vec::new();
+    for (a, b) in (0..names.len()).tuple_combinations() {
+        if b < party || rng.chance(link_chance) {
+            links.push(if rng.chance(0.5) { (a, b) } else { (b, a) });
+        }
+    }
+    rng.shuffle(&mut links);
+    let connections = links
+        .iter()
+        .map(|&(a, b)| format!("{}-{}\n", names[a], names[b]))
+        .collect();
+    (connections, names[..party].to_vec())
+}
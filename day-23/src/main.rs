@@ -1,9 +1,29 @@
 use itertools::Itertools;
-use petgraph::graphmap::UnGraphMap;
-use std::collections::HashSet;
+use utils::export::GraphExport;
+use utils::graph::{Graph, Interner};
 use utils::{read_lines, time};
 
+mod generate;
+
 fn main() {
+    // `day-23 generate <seed> [size]` prints a random network of `size` computers
+    if utils::generate::command(520, generate::input) {
+        return;
+    }
+    // `day-23 export <file.dot|file.graphml> [input]` writes the network out for graph tools
+    let args = std::env::args().collect_vec();
+    if args.get(1).map(|a| a.as_str()) == Some("export") {
+        let input = args.get(3).map(|a| a.as_str()).unwrap_or("src/input.txt");
+        let (network, names) = Graph::from_edges(&load_network(input), false);
+        let export = export_network(&network, &names, &network.max_clique());
+        let written = if args[2].ends_with(".graphml") {
+            export.write_graphml(&args[2])
+        } else {
+            export.write_dot(&args[2])
+        };
+        written.unwrap();
+        return;
+    }
     let (part1, time1) = time(|| part_1("src/input.txt"));
     println!(
         "Part 1: {} (took {} milliseconds)",
@@ -15,58 +35,58 @@ fn main() {
 }
 
 fn part_1(filename: &str) -> usize {
-    let ed = load_network(filename);
-    let edges = ed
-        .iter()
-        .map(|s| (s.0.as_str(), s.1.as_str()))
-        .collect_vec();
-    let network = UnGraphMap::<_, ()>::from_edges(edges);
-    let mut groups: HashSet<Vec<String>> = HashSet::new();
-    for n1 in network.nodes() {
-        for n2 in network.neighbors(n1) {
-            for n3 in network.neighbors(n2) {
-                for n4 in network.neighbors(n3) {
-                    if n4 == n1 {
-                        // Interconnected node found
-                        groups.insert(
-                            vec![n1.to_string(), n2.to_string(), n3.to_string()]
-                                .into_iter()
-                                .sorted()
-                                .collect_vec(),
-                        );
-                    }
-                }
-            }
-        }
-    }
-    groups
-        .into_iter()
-        .filter(|n| n.iter().any(|x| x.starts_with("t")))
-        .count()
+    let (network, names) = Graph::from_edges(&load_network(filename), false);
+    network
+        .k_cliques(3, |clique| {
+            clique.iter().any(|&n| names.resolve(n).starts_with('t'))
+        })
+        .len()
 }
 
 fn part_2(filename: &str) -> String {
-    let ed = load_network(filename);
-    let edges = ed
-        .iter()
-        .map(|s| (s.0.as_str(), s.1.as_str()))
-        .collect_vec();
-    let network = UnGraphMap::<_, ()>::from_edges(edges);
-    let mut largest = vec![];
-    for node in network.nodes() {
-        let mut g = vec![node];
-        let neighbors = network.neighbors(node);
-        for neighbor in neighbors {
-            if g.iter().all(|n| network.neighbors(neighbor).contains(n)) {
-                g.push(neighbor);
-            }
+    let (network, names) = Graph::from_edges(&load_network(filename), false);
+    let clique = network.max_clique();
+    debug_assert!(network.is_clique(&clique));
+    clique
+        .into_iter()
+        .map(|n| names.resolve(n))
+        .sorted()
+        .join(",")
+}
+
+/// The LAN party as an exportable graph, with the members of `clique` filled in red and
+/// the edges between them drawn bold
+fn export_network(network: &Graph, names: &Interner, clique: &[usize]) -> GraphExport {
+    let mut export = GraphExport::new("lan", false);
+    for n in 0..network.len() {
+        let name = names.resolve(n);
+        if clique.contains(&n) {
+            export.add_node(
+                name,
+                &[
+                    ("label", name),
+                    ("clique", "true"),
+                    ("style", "filled"),
+                    ("fillcolor", "red"),
+                ],
+            );
+        } else {
+            export.add_node(name, &[("label", name), ("clique", "false")]);
         }
-        g.sort();
-        if g.len() > largest.len() {
-            largest = g;
+    }
+    for (a, b) in network.edges() {
+        let (from, to) = (names.resolve(a), names.resolve(b));
+        if clique.contains(&a) && clique.contains(&b) {
+            export.add_edge(
+                from,
+                to,
+                &[("clique", "true"), ("color", "red"), ("penwidth", "2")],
+            );
+        } else {
+            export.add_edge(from, to, &[("clique", "false")]);
         }
     }
-    largest.iter().join(",")
+    export
 }
 
 fn load_network(filename: &str) -> Vec<(String, String)> {
@@ -81,15 +101,135 @@ fn load_network(filename: &str) -> Vec<(String, String)> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{part_1, part_2};
+    use crate::{export_network, load_network, part_1, part_2};
+    use itertools::Itertools;
+    use utils::aoc_test;
+    use utils::differential::{check, shrink_vec};
+    use utils::generate::Rng;
+    use utils::graph::Graph;
+    use utils::test_support::with_input;
+
+    aoc_test!(test_sample, "sample.txt", part_1 => 7, part_2 => "co,de,ka,ta");
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
+
+    #[test]
+    fn test_k_cliques() {
+        let (network, names) = Graph::from_edges(&load_network("src/sample.txt"), false);
+        assert_eq!(network.k_cliques(3, |_| true).len(), 12);
+        let fours = network.k_cliques(4, |_| true);
+        assert_eq!(fours.len(), 1);
+        assert_eq!(
+            fours[0]
+                .iter()
+                .map(|&n| names.resolve(n))
+                .sorted()
+                .join(","),
+            "co,de,ka,ta"
+        );
+        assert!(network.k_cliques(5, |_| true).is_empty());
+    }
+
+    #[test]
+    fn test_export_network() {
+        let (network, names) = Graph::from_edges(&load_network("src/sample.txt"), false);
+        let export = export_network(&network, &names, &network.max_clique());
+        let dot = export.to_dot();
+        assert!(dot.starts_with("graph \"lan\" {"));
+        assert_eq!(dot.matches(" -- ").count(), 32);
+        assert_eq!(dot.matches("fillcolor=\"red\"").count(), 4);
+        // Every pair of the four clique members is an edge
+        assert_eq!(dot.matches("color=\"red\", penwidth").count(), 6);
+        assert!(dot.contains("\"co\" [label=\"co\", clique=\"true\""));
+        let graphml = export.to_graphml();
+        assert_eq!(graphml.matches("<node ").count(), 16);
+        assert_eq!(
+            graphml
+                .matches("<data key=\"node_clique\">true</data>")
+                .count(),
+            4
+        );
+    }
+
+    /// Every subset of the computers that's a clique
+    fn brute_force_cliques(edges: &[(String, String)]) -> Vec<Vec<usize>> {
+        let (network, _) = Graph::from_edges(edges, false);
+        (0..1u32 << network.len())
+            .map(|mask| {
+                (0..network.len())
+                    .filter(|i| mask >> i & 1 == 1)
+                    .collect_vec()
+            })
+            .filter(|nodes| network.is_clique(nodes))
+            .collect()
+    }
 
     #[test]
-    fn test_part_1() {
-        assert_eq!(part_1("src/sample.txt"), 7);
+    fn test_against_brute_force() {
+        // Small random graphs with about two thirds of the possible links
+        let generate = |rng: &mut Rng| {
+            let mut edges = Vec::new();
+            for (a, b) in (0..12).tuple_combinations::<(u64, u64)>() {
+                if rng.below(3) != 0 {
+                    edges.push((a.to_string(), b.to_string()));
+                }
+            }
+            edges
+        };
+        let shrink = |edges: &Vec<(String, String)>| {
+            let mut smaller = shrink_vec(edges);
+            smaller.retain(|e| !e.is_empty());
+            smaller
+        };
+        check(
+            50,
+            generate,
+            shrink,
+            |edges| brute_force_cliques(edges).iter().map(|c| c.len()).max(),
+            |edges| {
+                let (network, _) = Graph::from_edges(edges, false);
+                let clique = network.max_clique();
+                assert!(network.is_clique(&clique));
+                Some(clique.len())
+            },
+        );
+        check(
+            50,
+            generate,
+            shrink,
+            |edges| {
+                let cliques = brute_force_cliques(edges);
+                cliques.iter().filter(|c| c.len() == 3).count()
+            },
+            |edges| {
+                Graph::from_edges(edges, false)
+                    .0
+                    .k_cliques(3, |_| true)
+                    .len()
+            },
+        );
     }
 
     #[test]
-    fn test_part_2() {
-        assert_eq!(part_2("src/sample.txt"), "co,de,ka,ta");
+    fn test_generated() {
+        for seed in 0..5 {
+            let (input, party) = crate::generate::network(&mut Rng::new(seed), 60);
+            let (triangles, password) = with_input(&input, |f| (part_1(f), part_2(f)));
+            assert_eq!(password, party.iter().sorted().join(","));
+            // Count the triangles with a `t` computer by checking every triple of names
+            let links = input
+                .lines()
+                .flat_map(|l| [(&l[..2], &l[3..]), (&l[3..], &l[..2])])
+                .collect::<std::collections::HashSet<_>>();
+            let names = links.iter().map(|&(a, _)| a).unique().collect_vec();
+            let brute = names
+                .iter()
+                .tuple_combinations()
+                .filter(|(a, b, c)| [a, b, c].iter().any(|n| n.starts_with('t')))
+                .filter(|&(&a, &b, &c)| {
+                    links.contains(&(a, b)) && links.contains(&(b, c)) && links.contains(&(a, c))
+                })
+                .count();
+            assert_eq!(triangles, brute);
+        }
     }
 }
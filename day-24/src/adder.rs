@@ -0,0 +1,321 @@
+use crate::netlist::{Netlist, Operand};
+use itertools::Itertools;
+use std::fmt::Display;
+
+/// What a gate is doing that a ripple-carry adder never does. Each bit `i` should be
+///
+/// ```text
+/// s = x ^ y,  z = s ^ carry_in,  a = x & y,  b = s & carry_in,  carry_out = a | b
+/// ```
+///
+/// except bit 0, which is a half adder, and the top output, which is the last carry.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Problem {
+    /// An output other than the last one that isn't `s ^ carry_in`
+    SumNotXor,
+    /// The last output is the carry out of the top bit, so it should be an OR
+    CarryOutNotOr,
+    /// An XOR that isn't on the inputs or driving an output
+    StrayXor,
+    /// `x ^ y` should go on to both the XOR and the AND of its bit
+    HalfSumUnused,
+    /// Both ANDs in each bit feed that bit's carry OR
+    AndNotIntoOr,
+    /// A carry should go on to both the XOR and the AND of the next bit
+    CarryUnused,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::SumNotXor => write!(f, "drives a sum output but isn't s ^ carry"),
+            Problem::CarryOutNotOr => write!(f, "drives the carry out but isn't an OR"),
+            Problem::StrayXor => write!(f, "is an XOR that doesn't belong there"),
+            Problem::HalfSumUnused => write!(f, "is x ^ y but doesn't feed an XOR and an AND"),
+            Problem::AndNotIntoOr => write!(f, "is an AND that doesn't feed a carry OR"),
+            Problem::CarryUnused => write!(f, "is a carry that doesn't feed an XOR and an AND"),
+        }
+    }
+}
+
+/// A gate that breaks the adder, pinned to the bit it belongs to
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Fault {
+    pub bit: usize,
+    pub wire: String,
+    pub operand: Operand,
+    pub problem: Problem,
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "bit {}: {} ({}) {}",
+            self.bit, self.wire, self.operand, self.problem
+        )
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Report {
+    pub width: usize,
+    /// Everything the structural check didn't like, ordered by bit
+    pub faults: Vec<Fault>,
+    /// The lowest output bit that came out wrong in simulation
+    pub first_failing_bit: Option<usize>,
+}
+
+/// The `x`, `y` and `z` buses, checked to be the right shape for an adder. Swapping gate
+/// outputs never renumbers wires, so these stay put while we search for a repair.
+struct Buses {
+    x: Vec<usize>,
+    y: Vec<usize>,
+    z: Vec<usize>,
+}
+
+impl Buses {
+    fn new(netlist: &Netlist) -> Buses {
+        let (x, y, z) = (netlist.bus('x'), netlist.bus('y'), netlist.bus('z'));
+        if x.is_empty() || y.len() != x.len() || z.len() != x.len() + 1 {
+            panic!(
+                "Not shaped like an adder: {} x bits, {} y bits and {} z bits",
+                x.len(),
+                y.len(),
+                z.len()
+            );
+        }
+        Buses { x, y, z }
+    }
+
+    fn width(&self) -> usize {
+        self.x.len()
+    }
+}
+
+/// Which bit every wire belongs to: the highest input bit anywhere upstream of it. That puts
+/// all five gates of bit `i` at `i`, wherever their outputs have been swapped to.
+fn wire_bits(netlist: &Netlist, buses: &Buses) -> Vec<usize> {
+    let mut bits = vec![0; netlist.len()];
+    for (i, (&xi, &yi)) in buses.x.iter().zip(&buses.y).enumerate() {
+        bits[xi] = i;
+        bits[yi] = i;
+    }
+    for gate in netlist.gates() {
+        bits[gate.output] = bits[gate.left].max(bits[gate.right]);
+    }
+    bits
+}
+
+/// Checks every gate against the role it must be playing in a ripple-carry adder, going
+/// by what it reads, what it drives and what reads it
+pub fn structural_faults(netlist: &Netlist) -> Vec<Fault> {
+    let buses = Buses::new(netlist);
+    let (x, y, z) = (&buses.x, &buses.y, &buses.z);
+    let width = buses.width();
+    let bits = wire_bits(netlist, &buses);
+    let mut readers: Vec<Vec<Operand>> = vec![Vec::new(); netlist.len()];
+    for gate in netlist.gates() {
+        readers[gate.left].push(gate.operand);
+        readers[gate.right].push(gate.operand);
+    }
+    let is_input = |w: usize| x.contains(&w) || y.contains(&w);
+    let feeds_next_bit =
+        |w: usize| readers[w].contains(&Operand::Xor) && readers[w].contains(&Operand::And);
+
+    let mut faults: Vec<Fault> = Vec::new();
+    for gate in netlist.gates() {
+        let bit = bits[gate.output];
+        let on_inputs = is_input(gate.left) && is_input(gate.right);
+        let problem = match (z.iter().position(|&w| w == gate.output), gate.operand) {
+            // A one bit adder's carry out is just x & y
+            (Some(k), Operand::And) if k == width && width == 1 => None,
+            (Some(k), Operand::Or) if k == width => None,
+            (Some(k), _) if k == width => Some(Problem::CarryOutNotOr),
+            (Some(0), Operand::Xor) if on_inputs => None,
+            (Some(k), Operand::Xor) if k > 0 && !on_inputs => None,
+            (Some(_), _) => Some(Problem::SumNotXor),
+            // Bit 0's x ^ y is the sum itself, so it should have been an output
+            (None, Operand::Xor) if !on_inputs || bit == 0 => Some(Problem::StrayXor),
+            (None, Operand::Xor) if !feeds_next_bit(gate.output) => Some(Problem::HalfSumUnused),
+            (None, Operand::Xor) => None,
+            // Bit 0's x & y is its carry out
+            (None, Operand::And) if bit == 0 && on_inputs => {
+                (!feeds_next_bit(gate.output)).then_some(Problem::CarryUnused)
+            }
+            (None, Operand::And) => {
+                (!readers[gate.output].contains(&Operand::Or)).then_some(Problem::AndNotIntoOr)
+            }
+            (None, Operand::Or) => (!feeds_next_bit(gate.output)).then_some(Problem::CarryUnused),
+        };
+        if let Some(problem) = problem {
+            faults.push(Fault {
+                bit,
+                wire: netlist.name(gate.output).to_string(),
+                operand: gate.operand,
+                problem,
+            });
+        }
+    }
+    faults.sort_by(|a, b| (a.bit, &a.wire).cmp(&(b.bit, &b.wire)));
+    faults
+}
+
+/// How many batches of 64 random additions to try before believing the circuit adds
+const ROUNDS: usize = 16;
+
+/// Adds random numbers 64 at a time (one per lane) and compares every output bit against
+/// a ripple-carry adder done by hand, returning the lowest bit that's ever wrong
+pub fn first_failing_bit(netlist: &Netlist) -> Option<usize> {
+    failing_bit(netlist, &Buses::new(netlist), ROUNDS)
+}
+
+fn failing_bit(netlist: &Netlist, buses: &Buses, rounds: usize) -> Option<usize> {
+    let (x, y, z) = (&buses.x, &buses.y, &buses.z);
+    let width = buses.width();
+    // Fixed seed, so a circuit always passes or fails the same way
+    let mut seed: u64 = 0x2024_1224;
+    let mut random = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let mut first: Option<usize> = None;
+    let mut values = vec![0; netlist.len()];
+    for round in 0..rounds {
+        for (i, (&xi, &yi)) in x.iter().zip(y).enumerate() {
+            values[xi] = random();
+            values[yi] = random();
+            if round == 0 {
+                // Lanes 0 and 1 carry all the way up: all ones plus one, and all ones twice
+                values[xi] |= 0b11;
+                values[yi] = values[yi] & !0b11 | 0b10 | (i == 0) as u64;
+            }
+        }
+        netlist.evaluate(&mut values);
+        let mut carry = 0;
+        for i in 0..=first.unwrap_or(width) {
+            let expected = if i < width {
+                let (a, b) = (values[x[i]], values[y[i]]);
+                let sum = a ^ b ^ carry;
+                carry = a & b | carry & (a ^ b);
+                sum
+            } else {
+                carry
+            };
+            if values[z[i]] != expected {
+                first = Some(i);
+                break;
+            }
+        }
+    }
+    first
+}
+
+pub fn verify(netlist: &Netlist) -> Report {
+    Report {
+        width: Buses::new(netlist).width(),
+        faults: structural_faults(netlist),
+        first_failing_bit: first_failing_bit(netlist),
+    }
+}
+
+/// Finds the fewest pairs of gate outputs to swap to make the circuit add, trying at most
+/// `max_swaps` pairs.
+///
+/// Works up from the lowest failing bit. A swap that fixes bit `i` has to involve a gate
+/// of bit `i` (or the carry out of bit `i - 1`), and the other side can be anywhere, so
+/// we try every pairing of those and keep the ones that move the first failure higher up.
+/// Two swaps tangled up in the same bit might only fix it together, so when that finds
+/// nothing we look again with the same number of swaps, also trying every two swaps
+/// between the gates of the failing bit.
+pub fn repair(netlist: &Netlist, max_swaps: usize) -> Option<Vec<(String, String)>> {
+    let buses = Buses::new(netlist);
+    let swaps = (0..=max_swaps).find_map(|limit| {
+        search(netlist, &buses, limit, false, &mut Vec::new())
+            .or_else(|| search(netlist, &buses, limit, true, &mut Vec::new()))
+    })?;
+    Some(
+        swaps
+            .into_iter()
+            .map(|(a, b)| (netlist.name(a).to_string(), netlist.name(b).to_string()))
+            .collect(),
+    )
+}
+
+/// Whether `swapped` gets further than `bit` before it first goes wrong
+fn fixes(swapped: &Netlist, buses: &Buses, bit: usize) -> bool {
+    // One round weeds out almost everything before the full check
+    failing_bit(swapped, buses, 1).is_none_or(|f| f > bit)
+        && failing_bit(swapped, buses, ROUNDS).is_none_or(|f| f > bit)
+}
+
+fn search(
+    netlist: &Netlist,
+    buses: &Buses,
+    limit: usize,
+    tangled: bool,
+    swaps: &mut Vec<(usize, usize)>,
+) -> Option<Vec<(usize, usize)>> {
+    let Some(bit) = failing_bit(netlist, buses, ROUNDS) else {
+        return Some(swaps.clone());
+    };
+    if swaps.len() == limit {
+        return None;
+    }
+    let bits = wire_bits(netlist, buses);
+    let outputs = netlist.gates().iter().map(|g| g.output).collect::<Vec<_>>();
+    let local = outputs
+        .iter()
+        .filter(|&&w| bits[w] + 1 >= bit && bits[w] <= bit)
+        .copied()
+        .collect::<Vec<_>>();
+    for &a in &local {
+        for &b in &outputs {
+            // Local pairs come up twice, only try them once
+            if a == b || (local.contains(&b) && b < a) {
+                continue;
+            }
+            let Ok(swapped) = netlist.swap_outputs(a, b) else {
+                continue;
+            };
+            if !fixes(&swapped, buses, bit) {
+                continue;
+            }
+            swaps.push((a, b));
+            if let Some(found) = search(&swapped, buses, limit, tangled, swaps) {
+                return Some(found);
+            }
+            swaps.pop();
+        }
+    }
+    if !tangled || swaps.len() + 2 > limit {
+        return None;
+    }
+    // Neither swap gets past `bit` by itself, but both together might
+    let pairs = local
+        .iter()
+        .copied()
+        .tuple_combinations()
+        .collect::<Vec<_>>();
+    for (i, &(a, b)) in pairs.iter().enumerate() {
+        for &(c, d) in &pairs[i + 1..] {
+            if [c, d].iter().any(|w| [a, b].contains(w)) {
+                continue;
+            }
+            let Ok(swapped) = netlist.swap_all(&[(a, b), (c, d)]) else {
+                continue;
+            };
+            if !fixes(&swapped, buses, bit) {
+                continue;
+            }
+            swaps.extend([(a, b), (c, d)]);
+            if let Some(found) = search(&swapped, buses, limit, tangled, swaps) {
+                return Some(found);
+            }
+            swaps.truncate(swaps.len() - 2);
+        }
+    }
+    None
+}
@@ -0,0 +1,102 @@
+use std::collections::{HashMap, HashSet};
+use utils::generate::Rng;
+
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    circuit(rng, size).0
+}
+
+/// A ripple-carry adder for `size` bit numbers with random inputs, random names for the
+/// internal wires and the gates shuffled. Up to four pairs of gate outputs are swapped,
+/// each within a single bit and at least one bit apart from the others, so the adder can
+/// be repaired one bit at a time. The swapped wires come back alongside the circuit.
+pub fn circuit(rng: &mut Rng, size: usize) -> (String, Vec<String>) {
+    let width = size.clamp(2, 64);
+    // Every gate as (left, operand, right, output), with the internal wires named by role
+    let wire = |role: char, bit: usize| format!("{}{:02}", role, bit);
+    let mut gates = vec![
+        (wire('x', 0), "XOR", wire('y', 0), wire('z', 0)),
+        (wire('x', 0), "AND", wire('y', 0), wire('c', 0)),
+    ];
+    for bit in 1..width {
+        let carry = if bit == width - 1 {
+            wire('z', width)
+        } else {
+            wire('c', bit)
+        };
+        let (x, y, s) = (wire('x', bit), wire('y', bit), wire('s', bit));
+        let (a, b, carry_in) = (wire('a', bit), wire('b', bit), wire('c', bit - 1));
+        gates.push((x.clone(), "XOR", y.clone(), s.clone()));
+        gates.push((s.clone(), "XOR", carry_in.clone(), wire('z', bit)));
+        gates.push((x, "AND", y, a.clone()));
+        gates.push((s, "AND", carry_in, b.clone()));
+        gates.push((a, "OR", b, carry));
+    }
+
+    // The first two bits and the last are special cases, so keep the swaps away from them
+    let mut bits: Vec<usize> = Vec::new();
+    for _ in 0..100 {
+        if bits.len() == 4 || width < 5 {
+            break;
+        }
+        let bit = rng.below(width - 3) + 2;
+        if bits.iter().all(|&b| b.abs_diff(bit) > 1) {
+            bits.push(bit);
+        }
+    }
+    let mut swapped = Vec::new();
+    for bit in bits {
+        let pair = *rng.pick(&[('s', 'a'), ('z', 'c'), ('z', 'b'), ('z', 'a')]);
+        let (first, second) = (wire(pair.0, bit), wire(pair.1, bit));
+        for gate in gates.iter_mut() {
+            if gate.3 == first {
+                gate.3 = second.clone();
+            } else if gate.3 == second {
+                gate.3 = first.clone();
+            }
+        }
+        swapped.extend([first, second]);
+    }
+
+    // Give the internal wires three letter names that can't be mistaken for x, y or z
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut used = HashSet::new();
+    for (_, _, _, output) in &gates {
+        if output.starts_with('z') {
+            continue;
+        }
+        let name = loop {
+            let name = (0..3)
+                .map(|i| (b'a' + rng.below(if i == 0 { 23 } else { 26 }) as u8) as char)
+                .collect::<String>();
+            if used.insert(name.clone()) {
+                break name;
+            }
+        };
+        names.insert(output.clone(), name);
+    }
+    let rename = |w: &String| names.get(w).unwrap_or(w).clone();
+
+    let mut input = String::new();
+    for prefix in ['x', 'y'] {
+        for bit in 0..width {
+            input.push_str(&format!("{}: {}\n", wire(prefix, bit), rng.below(2)));
+        }
+    }
+    input.push('\n');
+    rng.shuffle(&mut gates);
+    for (left, operand, right, output) in &gates {
+        let (left, right) = if rng.chance(0.5) {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        input.push_str(&format!(
+            "{} {} {} -> {}\n",
+            rename(left),
+            operand,
+            rename(right),
+            rename(output)
+        ));
+    }
+    (input, swapped.iter().map(rename).collect())
+}
@@ -1,308 +1,478 @@
+use crate::adder::{repair, verify};
+use crate::netlist::{Equation, Netlist, Operand};
 use itertools::Itertools;
-use regex::Regex;
-use std::cmp::Ordering;
-use utils::{read_file, time};
+use utils::export::GraphExport;
+use utils::parse::{read_input, sections, AocError};
+use utils::{scan, time};
+
+mod adder;
+mod generate;
+mod netlist;
 
 fn main() {
+    // `day-24 generate <seed> [size]` prints a broken `size` bit adder,
+    // `day-24 export <file.dot|file.graphml> [input]` writes the simulated circuit out for
+    // graph tools, `day-24 add <x> <y> [input]` runs it on other inputs and
+    // `day-24 verify [input]` lists everything that stops it being an adder
+    if utils::generate::command(45, generate::input) {
+        return;
+    }
+    let args = std::env::args().collect_vec();
+    if args.get(1).map(|a| a.as_str()) == Some("verify") {
+        let input = args.get(2).map(|a| a.as_str()).unwrap_or("src/input.txt");
+        let report = verify(&compile(&parse_circuit(input)));
+        println!("{} bit adder", report.width);
+        report.faults.iter().for_each(|f| println!("{}", f));
+        match report.first_failing_bit {
+            Some(bit) => println!("First wrong output in simulation: z{:02}", bit),
+            None => println!("Adds correctly in simulation"),
+        }
+        return;
+    }
+    if args.get(1).map(|a| a.as_str()) == Some("add") {
+        let input = args.get(4).map(|a| a.as_str()).unwrap_or("src/input.txt");
+        let netlist = compile(&parse_circuit(input));
+        let (x, y) = (args[2].parse().unwrap(), args[3].parse().unwrap());
+        println!("{} + {} -> {}", x, y, netlist.simulate(x, y));
+        return;
+    }
+    if args.get(1).map(|a| a.as_str()) == Some("export") {
+        let input = args.get(3).map(|a| a.as_str()).unwrap_or("src/input.txt");
+        let (netlist, values) = simulate_file(input);
+        let export = export_circuit(&netlist, &values);
+        let written = if args[2].ends_with(".graphml") {
+            export.write_graphml(&args[2])
+        } else {
+            export.write_dot(&args[2])
+        };
+        written.unwrap();
+        return;
+    }
     let (part1, time1) = time(|| part_1("src/input.txt"));
     println!("Part 1: {} (took {} secs)", part1, time1.as_secs_f64());
     let (part2, time2) = time(|| part_2("src/input.txt"));
     println!("Part 2: {} (took {} secs)", part2, time2.as_secs_f64());
 }
 
-#[derive(Debug, Clone, Ord, Eq, PartialEq, Hash)]
-struct Term {
-    name: String,
-    value: Option<u8>,
-}
+/// The input wires and the values they start with
+type Initial = Vec<(String, u8)>;
 
-impl PartialOrd for Term {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.name.cmp(&other.name))
-    }
+/// The initial wire values and the gates
+fn parse_circuit(filename: &str) -> (Initial, Vec<Equation>) {
+    parse(&read_input(filename)).unwrap_or_else(|e| panic!("{}: {}", filename, e))
 }
 
-#[derive(Debug, Clone)]
-struct Equation {
-    left: Term,
-    right: Term,
-    operand: Operand,
-    result: Term,
+fn parse(input: &str) -> Result<(Initial, Vec<Equation>), AocError> {
+    let [wires, gates] = sections(input)[..] else {
+        return Err(AocError::at(
+            input,
+            0,
+            "expected the wires and the gates separated by a blank line",
+        ));
+    };
+    let initial = wires
+        .numbered_lines()
+        .map(|(line, text)| scan!(text, "{}: {}" => String, u8).map_err(|e| e.below(line - 1)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let equations = gates
+        .numbered_lines()
+        .map(|(line, text)| {
+            let (left, operand, right, result) =
+                scan!(text, "{} {} {} -> {}" => String, String, String, String)
+                    .map_err(|e| e.below(line - 1))?;
+            let operand = match operand.as_str() {
+                "XOR" => Operand::Xor,
+                "AND" => Operand::And,
+                "OR" => Operand::Or,
+                _ => {
+                    let message = format!("`{}` isn't a gate", operand);
+                    return Err(AocError::at(text, left.len() + 1, message).below(line - 1));
+                }
+            };
+            Ok(Equation {
+                left,
+                right,
+                operand,
+                result,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((initial, equations))
 }
 
-#[derive(Eq, PartialEq, Copy, Clone, Debug)]
-enum Operand {
-    XOR,
-    AND,
-    OR,
+fn compile((initial, equations): &(Initial, Vec<Equation>)) -> Netlist {
+    let inputs = initial.iter().map(|(name, _)| name.as_str()).collect_vec();
+    Netlist::compile(&inputs, equations).unwrap_or_else(|e| panic!("{}", e))
 }
 
-impl Equation {
-    fn solved(&self) -> bool {
-        self.left.value.is_some() && self.right.value.is_some() && self.result.value.is_some()
+/// Compiles the circuit and runs it on the wire values from the file
+fn simulate_file(filename: &str) -> (Netlist, Vec<u64>) {
+    let circuit = parse_circuit(filename);
+    let netlist = compile(&circuit);
+    let mut values = vec![0; netlist.len()];
+    for (name, value) in &circuit.0 {
+        values[netlist.wire(name).unwrap()] = *value as u64;
     }
+    netlist.evaluate(&mut values);
+    (netlist, values)
+}
 
-    fn terms(&self) -> Vec<Term> {
-        vec![self.left.clone(), self.right.clone(), self.result.clone()]
+/// The circuit as an exportable graph. Every wire and every gate is a node, gates are
+/// labelled with their type, and wires and the edges they drive carry their value.
+fn export_circuit(netlist: &Netlist, values: &[u64]) -> GraphExport {
+    let mut export = GraphExport::new("circuit", true);
+    for (w, value) in values.iter().enumerate() {
+        let (name, value) = (netlist.name(w), (value & 1).to_string());
+        export.add_node(
+            name,
+            &[
+                ("label", &format!("{}={}", name, value)),
+                ("kind", "wire"),
+                ("value", &value),
+            ],
+        );
     }
-
-    fn try_solve(&mut self, terms: Vec<Term>) -> bool {
-        let left = if let Some(t) = terms
-            .iter()
-            .find(|term| term.name == self.left.name && term.value.is_some())
-        {
-            t
-        } else {
-            &self.left
-        };
-        let right = if let Some(t) = terms
-            .iter()
-            .find(|term| term.name == self.right.name && term.value.is_some())
-        {
-            t
-        } else {
-            &self.right
-        };
-        if left.value.is_some() && right.value.is_some() {
-            self.result.value = Some(match self.operand {
-                Operand::XOR => left.value.unwrap() ^ right.value.unwrap(),
-                Operand::AND => left.value.unwrap() & right.value.unwrap(),
-                Operand::OR => left.value.unwrap() | right.value.unwrap(),
-            });
-            self.left.value = left.value;
-            self.right.value = right.value;
-            true
-        } else {
-            false
+    for (i, gate) in netlist.gates().iter().enumerate() {
+        let id = format!("gate{}", i);
+        let operand = gate.operand.to_string();
+        export.add_node(
+            &id,
+            &[
+                ("label", &operand),
+                ("kind", "gate"),
+                ("gate", &operand),
+                ("shape", "box"),
+            ],
+        );
+        for w in [gate.left, gate.right] {
+            let value = (values[w] & 1).to_string();
+            export.add_edge(netlist.name(w), &id, &[("value", &value)]);
         }
+        let value = (values[gate.output] & 1).to_string();
+        export.add_edge(&id, netlist.name(gate.output), &[("value", &value)]);
     }
+    export
 }
 
-fn parse_equations(filename: &str, terms_override: Option<Vec<Term>>) -> Vec<Equation> {
-    let file = read_file(filename);
-    let mut terms = Vec::<Term>::new();
-    if let Some(to) = terms_override {
-        terms = to.clone();
-    } else {
-        for (_, [name, value]) in Regex::new(r"(\w+): (\d)")
-            .unwrap()
-            .captures_iter(&file)
-            .map(|c| c.extract())
-        {
-            terms.push(Term {
-                name: name.to_string(),
-                value: Some(value.parse::<u8>().unwrap()),
-            })
-        }
-    }
-    let mut equations = Vec::<Equation>::new();
-    for (_, [left, operand, right, result]) in Regex::new(r"(\w+) (XOR|OR|AND) (\w+) -> (\w+)")
-        .unwrap()
-        .captures_iter(&file)
-        .map(|c| c.extract())
-    {
-        equations.push(Equation {
-            left: if let Some(l) = terms.iter().find(|t| t.name == left) {
-                l.clone()
-            } else {
-                Term {
-                    name: left.to_string(),
-                    value: None,
-                }
-            },
-            right: if let Some(r) = terms.iter().find(|t| t.name == right) {
-                r.clone()
-            } else {
-                Term {
-                    name: right.to_string(),
-                    value: None,
-                }
-            },
-            result: if let Some(r) = terms.iter().find(|t| t.name == result) {
-                r.clone()
-            } else {
-                Term {
-                    name: result.to_string(),
-                    value: None,
-                }
-            },
-            operand: match operand {
-                "XOR" => Operand::XOR,
-                "AND" => Operand::AND,
-                "OR" => Operand::OR,
-                _ => unreachable!(),
-            },
-        })
-    }
-    equations
+fn part_1(filename: &str) -> u64 {
+    let (netlist, values) = simulate_file(filename);
+    netlist.read(&values, 'z')
 }
 
-fn part_1(filename: &str) -> usize {
-    let mut equations = parse_equations(filename, None);
-    while equations.iter().any(|e| !e.solved()) {
-        let terms = equations.iter().flat_map(|e| e.terms()).collect_vec();
-        for e in equations.iter_mut() {
-            if e.solved() {
-                continue;
+/// The puzzle promises exactly four swapped pairs
+const MAX_SWAPS: usize = 4;
+
+fn part_2(filename: &str) -> String {
+    let netlist = compile(&parse_circuit(filename));
+    let swaps = repair(&netlist, MAX_SWAPS)
+        .unwrap_or_else(|| panic!("No way to fix the adder with {} swaps", MAX_SWAPS));
+    swaps
+        .into_iter()
+        .flat_map(|(a, b)| [a, b])
+        .sorted()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adder::{first_failing_bit, repair, verify, Problem};
+    use crate::netlist::{Equation, Netlist, NetlistError, Operand};
+    use crate::{
+        compile, export_circuit, parse, parse_circuit, part_1, part_2, simulate_file, Initial,
+    };
+    use itertools::Itertools;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    // The sample isn't an adder, so part 2 only runs on a real input
+    aoc_test!(test_part_1, "sample.txt", part_1 => 2024);
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
+
+    #[test]
+    fn test_simulate() {
+        // Part 1's sample with other inputs, checked against evaluating the gates by hand
+        let circuit = parse_circuit("src/sample.txt");
+        let (netlist, equations) = (compile(&circuit), circuit.1);
+        assert_eq!(netlist.bus('x').len(), 5);
+        assert_eq!(netlist.bus('z').len(), 13);
+        assert_eq!(netlist.simulate(0b01101, 0b11111), 2024);
+        for (x, y) in [(0, 0), (31, 0), (0, 31), (7, 19), (31, 31)] {
+            let mut values = std::collections::HashMap::new();
+            for i in 0..5 {
+                values.insert(format!("x{:02}", i), x >> i & 1);
+                values.insert(format!("y{:02}", i), y >> i & 1);
             }
-            if e.try_solve(terms.clone()) {
-                break;
+            while values.len() < netlist.len() {
+                for e in &equations {
+                    if let (Some(&l), Some(&r)) = (values.get(&e.left), values.get(&e.right)) {
+                        values.insert(e.result.clone(), e.operand.apply(l, r));
+                    }
+                }
             }
+            let z = (0..13).fold(0, |acc, i| acc | values[&format!("z{:02}", i)] << i);
+            assert_eq!(netlist.simulate(x, y), z);
         }
     }
-    let mut terms = equations
-        .iter()
-        .flat_map(|e| e.terms())
-        .unique()
-        .filter(|t| t.name.starts_with("z"))
-        .collect_vec();
-    terms.sort();
-    let binary = terms.iter().rev().map(|t| t.value.unwrap()).join("");
-    usize::from_str_radix(&binary, 2).unwrap()
-}
-
-fn part_2(filename: &str) -> String {
-    let mut equations = parse_equations(filename, None);
-    let mut problems = Vec::<String>::new();
 
-    let find = |n1: String, n2: String, op: Operand, e: Vec<Equation>| -> Option<String> {
-        if let Some(&ref n) = e.iter().find(|e| {
-            e.operand == op
-                && ((e.left.name == n1 && e.right.name == n2)
-                    || (e.left.name == n2 && e.right.name == n1))
-        }) {
-            Some(n.result.name.clone())
-        } else {
-            None
+    fn gate(left: &str, operand: Operand, right: &str, result: &str) -> Equation {
+        Equation {
+            left: left.to_string(),
+            right: right.to_string(),
+            operand,
+            result: result.to_string(),
         }
-    };
+    }
 
-    // As we go, we'll need to identify the carry node
-    let mut carry = "".to_string();
-    // Gate 0 will be a half-adder
-    let (x, y, _) = ("x00", "y00", "z00");
-    // for this first half-adder really we only care about the carry bit
-    if let Some(c00) = find(
-        x.to_string(),
-        y.to_string(),
-        Operand::AND,
-        equations.clone(),
-    ) {
-        // We found the carry bit, store it
-        carry = c00;
+    #[test]
+    fn test_parse_errors() {
+        let error = parse("x00: 1\ny00: 0\n\nx00 AND y00 -> z00\nx00 NAND y00 -> z01\n");
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "line 5, column 5: `NAND` isn't a gate"
+        );
+        let error = parse("x00: 1\ny00: 2x\n\nx00 AND y00 -> z00\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 6));
+        assert!(parse("x00 AND y00 -> z00\n").is_err());
     }
 
-    // All of 1 - 44 should be full-adders
-    for i in 1..=44 {
-        // define the terms we want
-        let (x, y, z) = (
-            format!("x{:02}", i),
-            format!("y{:02}", i),
-            format!("z{:02}", i),
+    #[test]
+    fn test_compile_errors() {
+        let cycle = [
+            gate("x00", Operand::And, "c", "a"),
+            gate("a", Operand::Or, "y00", "b"),
+            gate("b", Operand::Xor, "x01", "c"),
+            gate("c", Operand::And, "c", "z00"),
+        ];
+        match Netlist::compile(&["x00", "y00", "x01"], &cycle) {
+            Err(NetlistError::Cycle { wires }) => {
+                assert_eq!(wires.len(), 3);
+                assert!(["a", "b", "c"]
+                    .iter()
+                    .all(|w| wires.contains(&w.to_string())));
+                // In the order the signal flows
+                let i = wires.iter().position(|w| w == "a").unwrap();
+                assert_eq!(wires[(i + 1) % 3], "b");
+            }
+            other => panic!("expected a cycle, got {:?}", other),
+        }
+        let undriven = [gate("x00", Operand::And, "q", "z00")];
+        assert_eq!(
+            Netlist::compile(&["x00"], &undriven).err(),
+            Some(NetlistError::Undriven {
+                wire: "q".to_string()
+            })
         );
-        // first let's find n1 (x ^ y)
-        let n1 = find(x.clone(), y.clone(), Operand::XOR, equations.clone()).unwrap();
-        // next n2 (x & y)
-        let n2 = find(x.clone(), y.clone(), Operand::AND, equations.clone()).unwrap();
-        // Now find n3 (carry & n1)
-        let mut n3 = find(
-            n1.clone(),
-            carry.to_string(),
-            Operand::AND,
-            equations.clone(),
+        let twice = [
+            gate("x00", Operand::And, "y00", "z00"),
+            gate("x00", Operand::Or, "y00", "z00"),
+        ];
+        assert_eq!(
+            Netlist::compile(&["x00", "y00"], &twice).err(),
+            Some(NetlistError::MultiplyDriven {
+                wire: "z00".to_string()
+            })
+        );
+        // Inputs come from the initial values, not from how wires are named
+        let into_input = [gate("x00", Operand::And, "y00", "y00")];
+        assert_eq!(
+            Netlist::compile(&["x00", "y00"], &into_input).err(),
+            Some(NetlistError::MultiplyDriven {
+                wire: "y00".to_string()
+            })
         );
-        // Now let's find Z (n1 ^ carry)
-        let zn = find(
-            n1.clone(),
-            carry.to_string(),
-            Operand::XOR,
-            equations.clone(),
+        let x_named = [
+            gate("x00", Operand::And, "y00", "xkd"),
+            gate("xkd", Operand::Or, "y00", "z00"),
+        ];
+        let netlist = Netlist::compile(&["x00", "y00"], &x_named).unwrap();
+        assert_eq!(netlist.simulate(1, 1), 1);
+    }
+
+    #[test]
+    fn test_export_circuit() {
+        let (netlist, values) = simulate_file("src/sample.txt");
+        let export = export_circuit(&netlist, &values);
+        let dot = export.to_dot();
+        assert!(dot.starts_with("digraph \"circuit\" {"));
+        // 36 gates, each with two inputs and one output
+        assert_eq!(dot.matches("kind=\"gate\"").count(), 36);
+        assert_eq!(dot.matches(" -> ").count(), 108);
+        assert!(dot.contains("\"z00\" [label=\"z00=0\", kind=\"wire\", value=\"0\"];"));
+        assert!(dot.contains("\"z03\" [label=\"z03=1\", kind=\"wire\", value=\"1\"];"));
+        let graphml = export.to_graphml();
+        assert!(graphml.contains("edgedefault=\"directed\""));
+        assert_eq!(
+            graphml
+                .matches("<data key=\"node_gate\">XOR</data>")
+                .count(),
+            10
         );
-        // now that we've collected all the vars, let's figure out if anything is wrong
-        if zn.is_none() || n3.is_none() {
-            // something's wrong with either the previous carry or n1, most likely n1
-            // try swapping n1 and n2
-            problems.push(n1.clone());
-            problems.push(n2.clone());
-            let n1_eq = equations
-                .iter_mut()
-                .find(|e| e.result.name == n1.to_string())
-                .unwrap();
-            n1_eq.result.name = n2.clone();
-            let n2_eq = equations
-                .iter_mut()
-                .find(|e| e.result.name == n2.clone())
-                .unwrap();
-            n2_eq.result.name = n1.clone();
-            println!("Swapped {} and {}", n1.clone(), n2.clone());
-            n3 = find(
-                n2.clone(),
-                carry.to_string(),
-                Operand::AND,
-                equations.clone(),
+    }
+
+    /// A ripple-carry adder of any width, with the internal wires named by role and bit and
+    /// every input starting at 0
+    fn ripple_carry(width: usize) -> (Initial, Vec<Equation>) {
+        let initial = (0..width)
+            .flat_map(|i| [format!("x{:02}", i), format!("y{:02}", i)])
+            .map(|name| (name, 0))
+            .collect();
+        let mut equations = vec![
+            gate("x00", Operand::Xor, "y00", "z00"),
+            gate("x00", Operand::And, "y00", "c00"),
+        ];
+        for i in 1..width {
+            let (x, y, z) = (
+                format!("x{:02}", i),
+                format!("y{:02}", i),
+                format!("z{:02}", i),
             );
-            carry = find(n1, n3.unwrap(), Operand::OR, equations.clone()).unwrap();
-        } else {
-            if zn.clone().unwrap() != z {
-                println!(
-                    "Something's wrong with {}, zn points to {} instead",
-                    z,
-                    zn.clone().unwrap()
-                );
-                let zn_name = zn.clone().unwrap().to_string();
-                problems.push(z.clone());
-                problems.push(zn_name.clone());
-                let z_eq = equations
-                    .iter_mut()
-                    .find(|e| e.result.name == z.to_string())
-                    .unwrap();
-                z_eq.result.name = zn_name.clone();
-                let zn_eq = equations
-                    .iter_mut()
-                    .find(|e| e.result.name == zn_name.clone())
-                    .unwrap();
-                zn_eq.result.name = z.to_string();
-                if z == n1.clone() {
-                    n3 = find(
-                        n2.clone(),
-                        zn.clone().unwrap(),
-                        Operand::AND,
-                        equations.clone(),
-                    );
-                    carry = find(n2.clone(), n3.unwrap(), Operand::OR, equations.clone()).unwrap();
-                } else if z == n2.clone() {
-                    carry =
-                        find(zn_name.clone(), n3.unwrap(), Operand::OR, equations.clone()).unwrap();
-                } else if z == n3.clone().unwrap() {
-                    carry =
-                        find(n2.clone(), zn_name.clone(), Operand::OR, equations.clone()).unwrap();
-                } else {
-                    // z was wired to carry
-                    carry = zn.clone().unwrap()
-                }
+            let (s, a, b) = (
+                format!("s{:02}", i),
+                format!("a{:02}", i),
+                format!("b{:02}", i),
+            );
+            let (carry_in, carry) = (format!("c{:02}", i - 1), format!("c{:02}", i));
+            let carry = if i == width - 1 {
+                format!("z{:02}", width)
             } else {
-                // Finally, the next carry
-                carry = find(n2, n3.unwrap(), Operand::OR, equations.clone()).unwrap();
+                carry
+            };
+            equations.push(gate(&x, Operand::Xor, &y, &s));
+            equations.push(gate(&s, Operand::Xor, &carry_in, &z));
+            equations.push(gate(&x, Operand::And, &y, &a));
+            equations.push(gate(&s, Operand::And, &carry_in, &b));
+            equations.push(gate(&a, Operand::Or, &b, &carry));
+        }
+        (initial, equations)
+    }
+
+    fn swap(equations: &mut [Equation], a: &str, b: &str) {
+        for e in equations.iter_mut() {
+            if e.result == a {
+                e.result = b.to_string();
+            } else if e.result == b {
+                e.result = a.to_string();
             }
         }
     }
 
-    problems.sort();
-    problems.iter().join(",")
-}
+    #[test]
+    fn test_correct_adders() {
+        for width in [2, 5, 13, 45] {
+            let netlist = compile(&ripple_carry(width));
+            let report = verify(&netlist);
+            assert_eq!(report.width, width);
+            assert_eq!(report.faults, vec![]);
+            assert_eq!(report.first_failing_bit, None);
+            assert_eq!(repair(&netlist, 4), Some(vec![]));
+            let mask = (1 << width) - 1;
+            assert_eq!(
+                netlist.simulate(12345, 6789),
+                (12345 & mask) + (6789 & mask)
+            );
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use crate::{part_1, part_2};
+    #[test]
+    fn test_faults_pinned_to_bits() {
+        let mut circuit = ripple_carry(16);
+        swap(&mut circuit.1, "s05", "a05");
+        swap(&mut circuit.1, "z09", "c09");
+        let report = verify(&compile(&circuit));
+        assert_eq!(report.first_failing_bit, Some(5));
+        let faults = report
+            .faults
+            .iter()
+            .map(|f| (f.bit, f.wire.as_str(), f.problem))
+            .collect_vec();
+        assert_eq!(
+            faults,
+            vec![
+                (5, "a05", Problem::HalfSumUnused),
+                (5, "s05", Problem::AndNotIntoOr),
+                (9, "c09", Problem::StrayXor),
+                (9, "z09", Problem::SumNotXor),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tangled_repair() {
+        // Either swap undone on its own still leaves bit 5 broken, so they only show up
+        // as a pair
+        let mut circuit = ripple_carry(16);
+        swap(&mut circuit.1, "s05", "a05");
+        swap(&mut circuit.1, "b05", "z05");
+        swap(&mut circuit.1, "z11", "c11");
+        let netlist = compile(&circuit);
+        assert_eq!(repair(&netlist, 2), None);
+        let found = repair(&netlist, 3).unwrap();
+        assert_eq!(
+            found
+                .into_iter()
+                .flat_map(|(a, b)| [a, b])
+                .sorted()
+                .join(","),
+            "a05,b05,c11,s05,z05,z11"
+        );
+    }
 
     #[test]
-    fn test_part_1() {
-        assert_eq!(part_1("src/sample.txt"), 2024);
+    fn test_repair() {
+        let mut circuit = ripple_carry(45);
+        let swaps = [
+            ("s05", "a05"),
+            ("z11", "c11"),
+            ("z20", "b20"),
+            ("z30", "c38"),
+        ];
+        for (a, b) in swaps {
+            swap(&mut circuit.1, a, b);
+        }
+        let netlist = compile(&circuit);
+        assert!(first_failing_bit(&netlist).is_some());
+        assert_eq!(repair(&netlist, 3), None);
+        let found = repair(&netlist, 4).unwrap();
+        assert_eq!(found.len(), 4);
+        assert_eq!(
+            found
+                .into_iter()
+                .flat_map(|(a, b)| [a, b])
+                .sorted()
+                .join(","),
+            "a05,b20,c11,c38,s05,z11,z20,z30"
+        );
     }
 
     #[test]
-    fn test_part_2() {
-        part_2("src/input.txt");
-        assert_eq!(true, true);
+    fn test_generated() {
+        for seed in 0..3 {
+            let (input, swapped) = crate::generate::circuit(&mut Rng::new(seed), 20);
+            assert_eq!(swapped.len(), 8);
+            let netlist = compile(&with_input(&input, parse_circuit));
+            assert!(!verify(&netlist).faults.is_empty());
+            assert_eq!(
+                with_input(&input, part_2),
+                swapped.iter().sorted().join(",")
+            );
+        }
+        // Too narrow for any swaps, so it's a working adder
+        let (input, swapped) = crate::generate::circuit(&mut Rng::new(0), 4);
+        assert!(swapped.is_empty());
+        let circuit = with_input(&input, parse_circuit);
+        let initial = &circuit.0;
+        let bits = |prefix: char| {
+            initial
+                .iter()
+                .filter(|(name, _)| name.starts_with(prefix))
+                .map(|(name, v)| (*v as u64) << name[1..].parse::<u64>().unwrap())
+                .sum::<u64>()
+        };
+        assert_eq!(
+            compile(&circuit).simulate(bits('x'), bits('y')),
+            bits('x') + bits('y')
+        );
+        assert_eq!(with_input(&input, part_1), bits('x') + bits('y'));
     }
 }
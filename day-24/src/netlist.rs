@@ -0,0 +1,230 @@
+use std::fmt::Display;
+use std::rc::Rc;
+use utils::graph::{Graph, Interner};
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Operand {
+    Xor,
+    And,
+    Or,
+}
+
+impl Operand {
+    pub fn apply(&self, left: u64, right: u64) -> u64 {
+        match self {
+            Operand::Xor => left ^ right,
+            Operand::And => left & right,
+            Operand::Or => left | right,
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Xor => write!(f, "XOR"),
+            Operand::And => write!(f, "AND"),
+            Operand::Or => write!(f, "OR"),
+        }
+    }
+}
+
+/// One gate as written in the puzzle input, `left OP right -> result`
+#[derive(Debug, Clone)]
+pub struct Equation {
+    pub left: String,
+    pub right: String,
+    pub operand: Operand,
+    pub result: String,
+}
+
+/// A gate with its wires resolved to indexes into the netlist
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Gate {
+    pub operand: Operand,
+    pub left: usize,
+    pub right: usize,
+    pub output: usize,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NetlistError {
+    /// These wires feed each other in a loop, in order
+    Cycle { wires: Vec<String> },
+    /// Read by a gate but not an input and not driven by anything
+    Undriven { wire: String },
+    /// Driven by more than one gate, or by a gate and an input
+    MultiplyDriven { wire: String },
+}
+
+impl Display for NetlistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetlistError::Cycle { wires } => {
+                write!(f, "wires form a cycle: {}", wires.join(" -> "))
+            }
+            NetlistError::Undriven { wire } => write!(f, "wire {} is never driven", wire),
+            NetlistError::MultiplyDriven { wire } => {
+                write!(f, "wire {} is driven more than once", wire)
+            }
+        }
+    }
+}
+
+/// A circuit compiled down to numbered wires and a list of gates in evaluation order.
+///
+/// Wire values are `u64`s where every bit is a separate lane, so one pass over the gates
+/// simulates 64 sets of inputs at once. Most of the time only the lowest lane matters.
+#[derive(Debug, Clone)]
+pub struct Netlist {
+    /// Shared between a netlist and its rewirings, which all have the same wires
+    names: Rc<Interner>,
+    /// Input wires are interned first, so they're every wire numbered below this
+    inputs: usize,
+    /// Every gate comes after the gates driving its inputs
+    gates: Vec<Gate>,
+}
+
+impl Netlist {
+    /// `inputs` are the wires given starting values, everything else has to come out of a
+    /// gate
+    pub fn compile(inputs: &[&str], equations: &[Equation]) -> Result<Netlist, NetlistError> {
+        let mut names: Interner = Interner::new();
+        inputs.iter().for_each(|&w| {
+            names.intern(w);
+        });
+        let inputs = names.len();
+        let gates = equations
+            .iter()
+            .map(|e| Gate {
+                operand: e.operand,
+                left: names.intern(&e.left),
+                right: names.intern(&e.right),
+                output: names.intern(&e.result),
+            })
+            .collect::<Vec<_>>();
+        Netlist::link(Rc::new(names), inputs, gates)
+    }
+
+    /// Checks every wire is driven exactly once and sorts the gates into evaluation order
+    fn link(names: Rc<Interner>, inputs: usize, gates: Vec<Gate>) -> Result<Netlist, NetlistError> {
+        let name = |w: usize| names.resolve(w).clone();
+        let mut driver: Vec<Option<usize>> = vec![None; names.len()];
+        for (g, gate) in gates.iter().enumerate() {
+            if driver[gate.output].is_some() || gate.output < inputs {
+                return Err(NetlistError::MultiplyDriven {
+                    wire: name(gate.output),
+                });
+            }
+            driver[gate.output] = Some(g);
+        }
+        for gate in &gates {
+            for w in [gate.left, gate.right] {
+                if driver[w].is_none() && w >= inputs {
+                    return Err(NetlistError::Undriven { wire: name(w) });
+                }
+            }
+        }
+
+        // Wires in signal order put each gate after the gates driving its inputs
+        let mut wiring = Graph::new(names.len(), true);
+        for gate in &gates {
+            wiring.add_edge(gate.left, gate.output);
+            wiring.add_edge(gate.right, gate.output);
+        }
+        let order = wiring.toposort().map_err(|cycle| NetlistError::Cycle {
+            wires: cycle.into_iter().map(name).collect(),
+        })?;
+        Ok(Netlist {
+            gates: order
+                .into_iter()
+                .filter_map(|w| driver[w].map(|g| gates[g]))
+                .collect(),
+            names,
+            inputs,
+        })
+    }
+
+    /// The same circuit with the outputs of the gates driving `a` and `b` exchanged, which
+    /// can fail if that closes a loop
+    pub fn swap_outputs(&self, a: usize, b: usize) -> Result<Netlist, NetlistError> {
+        self.swap_all(&[(a, b)])
+    }
+
+    /// Several swaps at once, so a loop one of them closes on its own can be opened again
+    /// by another. The pairs shouldn't share any wires.
+    pub fn swap_all(&self, pairs: &[(usize, usize)]) -> Result<Netlist, NetlistError> {
+        let gates = self
+            .gates
+            .iter()
+            .map(|&g| {
+                let output = pairs.iter().find_map(|&(a, b)| match g.output {
+                    o if o == a => Some(b),
+                    o if o == b => Some(a),
+                    _ => None,
+                });
+                Gate {
+                    output: output.unwrap_or(g.output),
+                    ..g
+                }
+            })
+            .collect();
+        Netlist::link(self.names.clone(), self.inputs, gates)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn wire(&self, name: &str) -> Option<usize> {
+        self.names.id(name)
+    }
+
+    pub fn name(&self, wire: usize) -> &str {
+        self.names.resolve(wire)
+    }
+
+    pub fn gates(&self) -> &[Gate] {
+        &self.gates
+    }
+
+    /// The wires `prefix00`, `prefix01`, ... least significant bit first
+    pub fn bus(&self, prefix: char) -> Vec<usize> {
+        (0..)
+            .map(|i| self.wire(&format!("{}{:02}", prefix, i)))
+            .take_while(|w| w.is_some())
+            .flatten()
+            .collect()
+    }
+
+    /// Fills in every gate output from the values already on the input wires
+    pub fn evaluate(&self, values: &mut [u64]) {
+        for gate in &self.gates {
+            values[gate.output] = gate.operand.apply(values[gate.left], values[gate.right]);
+        }
+    }
+
+    /// Puts `value` on a bus in the lowest lane, dropping any bits the bus is too narrow for
+    pub fn write(&self, values: &mut [u64], prefix: char, value: u64) {
+        for (i, w) in self.bus(prefix).into_iter().enumerate() {
+            values[w] = value >> i & 1;
+        }
+    }
+
+    /// Reads a bus back out of the lowest lane
+    pub fn read(&self, values: &[u64], prefix: char) -> u64 {
+        self.bus(prefix)
+            .into_iter()
+            .enumerate()
+            .fold(0, |acc, (i, w)| acc | (values[w] & 1) << i)
+    }
+
+    /// Runs the circuit with `x` and `y` on the input buses and returns `z`
+    pub fn simulate(&self, x: u64, y: u64) -> u64 {
+        let mut values = vec![0; self.len()];
+        self.write(&mut values, 'x', x);
+        self.write(&mut values, 'y', y);
+        self.evaluate(&mut values);
+        self.read(&values, 'z')
+    }
+}
@@ -0,0 +1,23 @@
+use itertools::Itertools;
+use utils::generate::Rng;
+
+/// `size` random locks and keys with five pins in seven rows, like the real thing
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let heights = (0..5).map(|_| rng.below(6)).collect_vec();
+            let lock = rng.chance(0.5);
+            (0..7)
+                .map(|row| {
+                    // Locks hang down from the top row, keys stand up from the bottom one
+                    let filled = if lock { row } else { 6 - row };
+                    heights
+                        .iter()
+                        .map(|&h| if filled <= h { '#' } else { '.' })
+                        .collect::<String>()
+                })
+                .join("\n")
+        })
+        .join("\n\n")
+        + "\n"
+}
@@ -1,65 +1,161 @@
+use crate::schematic::Schematics;
 use itertools::Itertools;
 use utils::{read_file, time};
 
+mod generate;
+mod schematic;
+
 fn main() {
+    // `day-25 generate <seed> [size]` prints `size` random locks and keys,
+    // `day-25 pairs [input]` lists every key that fits every lock
+    if utils::generate::command(500, generate::input) {
+        return;
+    }
+    let args = std::env::args().collect_vec();
+    if args.get(1).map(|a| a.as_str()) == Some("pairs") {
+        let input = args.get(2).map(|a| a.as_str()).unwrap_or("src/input.txt");
+        let schematics = parse(input);
+        for (l, k) in schematics.fitting_pairs() {
+            println!(
+                "Lock {} ({}) fits key {} ({})",
+                l,
+                schematics.heights(schematics.locks[l]).iter().join(","),
+                k,
+                schematics.heights(schematics.keys[k]).iter().join(",")
+            );
+        }
+        return;
+    }
     let (part1, time1) = time(|| part_1("src/input.txt"));
     println!("Part 1: {} (took {} secs)", part1, time1.as_secs_f64());
 }
 
-fn part_1(filename: &str) -> usize {
-    let (locks, keys) = parse_keys_and_locks(filename);
-    let mut successes = 0usize;
-    for lock in &locks {
-        for key in &keys {
-            let mut fail = false;
-            for i in 0..5 {
-                if lock[i] + key[i] > 5 {
-                    fail = true;
-                    break;
-                }
-            }
-            if !fail {
-                successes += 1;
-            }
-        }
-    }
-    successes
+fn parse(filename: &str) -> Schematics {
+    Schematics::parse(&read_file(filename)).unwrap_or_else(|e| panic!("{}: {}", filename, e))
 }
 
-fn parse_keys_and_locks(filename: &str) -> (Vec<Vec<i8>>, Vec<Vec<i8>>) {
-    let file = read_file(filename);
-    let mut keys = Vec::<Vec<i8>>::new();
-    let mut locks = Vec::<Vec<i8>>::new();
-    for item in file.split("\n\n") {
-        let lock = item.starts_with("#");
-        let lines = if lock {
-            item.lines().into_iter().collect_vec()
-        } else {
-            item.lines().into_iter().rev().collect_vec()
-        };
-        let mut r = vec![-1i8; 5];
-        for (line_no, line) in lines.into_iter().enumerate() {
-            for (col, char) in line.chars().enumerate() {
-                if char == '.' && r[col] == -1 {
-                    r[col] = line_no as i8 - 1;
-                }
-            }
-        }
-        if lock {
-            locks.push(r);
-        } else {
-            keys.push(r);
-        }
-    }
-    (locks, keys)
+fn part_1(filename: &str) -> usize {
+    parse(filename).fitting_pairs().len()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::part_1;
+    use crate::schematic::{ErrorKind, SchematicError, Schematics};
+    use crate::{parse, part_1};
+    use itertools::Itertools;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::parse::sections;
+    use utils::test_support::with_input;
+
+    // Day 25 only has the one part
+    aoc_test!(test_part_1, "sample.txt", part_1 => 3);
+    aoc_test!(test_input, optional "input.txt", part_1);
 
     #[test]
-    fn test_part_1() {
-        assert_eq!(part_1("src/sample.txt"), 3);
+    fn test_fitting_pairs() {
+        let schematics = parse("src/sample.txt");
+        assert_eq!((schematics.pins, schematics.height), (5, 7));
+        let pairs = schematics
+            .fitting_pairs()
+            .into_iter()
+            .map(|(l, k)| {
+                (
+                    schematics.heights(schematics.locks[l]),
+                    schematics.heights(schematics.keys[k]),
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            pairs,
+            vec![
+                (vec![0, 5, 3, 4, 3], vec![3, 0, 2, 0, 1]),
+                (vec![1, 2, 0, 5, 3], vec![4, 3, 4, 0, 2]),
+                (vec![1, 2, 0, 5, 3], vec![3, 0, 2, 0, 1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inferred_geometry() {
+        // Three pins, four rows, Windows line endings and extra blank lines
+        let input = "###\r\n#.#\r\n...\r\n...\r\n\r\n\r\n...\r\n...\r\n#.#\r\n###\r\n";
+        let schematics = Schematics::parse(input).unwrap();
+        assert_eq!((schematics.pins, schematics.height), (3, 4));
+        assert_eq!(schematics.heights(schematics.locks[0]), vec![1, 0, 1]);
+        assert_eq!(schematics.heights(schematics.keys[0]), vec![1, 0, 1]);
+        assert_eq!(schematics.fitting_pairs(), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = crate::generate::input(&mut Rng::new(seed), 100);
+            // Count pins by hand and try every lock against every key
+            let (locks, keys): (Vec<_>, Vec<_>) = sections(&input)
+                .iter()
+                .map(|block| {
+                    let rows = block.lines().collect_vec();
+                    let heights = (0..5)
+                        .map(|c| rows.iter().filter(|r| r.as_bytes()[c] == b'#').count() - 1)
+                        .collect_vec();
+                    (rows[0] == "#####", heights)
+                })
+                .partition(|(lock, _)| *lock);
+            let brute = locks
+                .iter()
+                .cartesian_product(&keys)
+                .filter(|((_, l), (_, k))| l.iter().zip(k).all(|(l, k)| l + k <= 5))
+                .count();
+            assert_eq!(with_input(&input, part_1), brute);
+        }
+    }
+
+    fn error(input: &str) -> SchematicError {
+        Schematics::parse(input).unwrap_err()
+    }
+
+    #[test]
+    fn test_malformed() {
+        let error_at = |line, column, kind| SchematicError { line, column, kind };
+        assert_eq!(
+            error("###\n#.#\n..\n...\n"),
+            error_at(
+                3,
+                3,
+                ErrorKind::Ragged {
+                    expected: 3,
+                    found: 2
+                }
+            )
+        );
+        assert_eq!(
+            error("###\n#.#\n...\n\n...\n###\n"),
+            error_at(
+                5,
+                1,
+                ErrorKind::WrongHeight {
+                    expected: 3,
+                    found: 2
+                }
+            )
+        );
+        // The top row isn't solid, so this isn't a lock even though it starts with `#`
+        assert_eq!(
+            error("#.#\n#.#\n...\n"),
+            error_at(1, 1, ErrorKind::NotLockOrKey)
+        );
+        assert_eq!(
+            error("###\n..#\n#..\n...\n"),
+            error_at(3, 1, ErrorKind::BrokenPin)
+        );
+        assert_eq!(
+            error("###\n#x#\n...\n"),
+            error_at(2, 2, ErrorKind::UnexpectedChar('x'))
+        );
+        assert_eq!(
+            error(&"#########\n".repeat(8)),
+            error_at(1, 1, ErrorKind::TooLarge { cells: 72 })
+        );
     }
 }
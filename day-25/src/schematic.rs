@@ -0,0 +1,196 @@
+use std::fmt::Display;
+use utils::parse::{normalize, sections};
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ErrorKind {
+    /// A row that isn't as wide as the first row of the first schematic
+    Ragged {
+        expected: usize,
+        found: usize,
+    },
+    /// A schematic with a different number of rows to the first one
+    WrongHeight {
+        expected: usize,
+        found: usize,
+    },
+    UnexpectedChar(char),
+    /// Neither a lock (full top row, empty bottom row) nor a key (the other way up)
+    NotLockOrKey,
+    /// A column with a gap in it, or a pin that doesn't start from the lock or key body
+    BrokenPin,
+    /// Too many cells to fit in a bitmask
+    TooLarge {
+        cells: usize,
+    },
+}
+
+/// Where a schematic went wrong. Lines and columns count from 1, like an editor.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct SchematicError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ErrorKind,
+}
+
+impl Display for SchematicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match self.kind {
+            ErrorKind::Ragged { expected, found } => {
+                write!(f, "row is {} wide, expected {}", found, expected)
+            }
+            ErrorKind::WrongHeight { expected, found } => {
+                write!(f, "schematic is {} rows tall, expected {}", found, expected)
+            }
+            ErrorKind::UnexpectedChar(c) => write!(f, "unexpected `{}`", c),
+            ErrorKind::NotLockOrKey => write!(f, "not a lock or a key"),
+            ErrorKind::BrokenPin => write!(f, "pin has a gap in it"),
+            ErrorKind::TooLarge { cells } => {
+                write!(f, "{} cells is too many for a 64 bit mask", cells)
+            }
+        }
+    }
+}
+
+/// Every lock and key as a bitmask of its `#` cells, one bit per cell in reading order.
+/// The lock body is the top row and a key body is the bottom row, so a key fits a lock
+/// exactly when no cell is filled in both.
+#[derive(Debug, Clone)]
+pub struct Schematics {
+    pub pins: usize,
+    pub height: usize,
+    pub locks: Vec<u64>,
+    pub keys: Vec<u64>,
+}
+
+impl Schematics {
+    /// Schematics are separated by blank lines. The number of pins and the height come from
+    /// the first one, and every other one has to match.
+    pub fn parse(input: &str) -> Result<Schematics, SchematicError> {
+        let input = normalize(input);
+        let blocks = sections(&input)
+            .iter()
+            .map(|s| s.numbered_lines().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut schematics = Schematics {
+            pins: blocks.first().map_or(0, |b| b[0].1.chars().count()),
+            height: blocks.first().map_or(0, |b| b.len()),
+            locks: Vec::new(),
+            keys: Vec::new(),
+        };
+        let cells = schematics.pins * schematics.height;
+        if cells > 64 {
+            return Err(SchematicError {
+                line: blocks[0][0].0,
+                column: 1,
+                kind: ErrorKind::TooLarge { cells },
+            });
+        }
+        for block in &blocks {
+            schematics.add(block)?;
+        }
+        Ok(schematics)
+    }
+
+    fn add(&mut self, rows: &[(usize, &str)]) -> Result<(), SchematicError> {
+        let error =
+            |line: usize, column: usize, kind: ErrorKind| SchematicError { line, column, kind };
+        let (first_line, _) = rows[0];
+        if rows.len() != self.height {
+            return Err(error(
+                first_line,
+                1,
+                ErrorKind::WrongHeight {
+                    expected: self.height,
+                    found: rows.len(),
+                },
+            ));
+        }
+        let mut grid: Vec<Vec<bool>> = Vec::new();
+        for &(line, row) in rows {
+            let width = row.chars().count();
+            if width != self.pins {
+                return Err(error(
+                    line,
+                    width.min(self.pins) + 1,
+                    ErrorKind::Ragged {
+                        expected: self.pins,
+                        found: width,
+                    },
+                ));
+            }
+            let cells = row
+                .chars()
+                .enumerate()
+                .map(|(col, c)| match c {
+                    '#' => Ok(true),
+                    '.' => Ok(false),
+                    _ => Err(error(line, col + 1, ErrorKind::UnexpectedChar(c))),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            grid.push(cells);
+        }
+
+        let (top, bottom) = (&grid[0], &grid[self.height - 1]);
+        let lock = if top.iter().all(|&c| c) && bottom.iter().all(|&c| !c) {
+            true
+        } else if top.iter().all(|&c| !c) && bottom.iter().all(|&c| c) {
+            false
+        } else {
+            return Err(error(first_line, 1, ErrorKind::NotLockOrKey));
+        };
+        // Pins grow down from the top of a lock and up from the bottom of a key, so walking
+        // from the body outwards a column can only ever go from `#` to `.`
+        let mut ended = vec![false; self.pins];
+        let order = (0..self.height).map(|r| if lock { r } else { self.height - 1 - r });
+        for row in order {
+            for (col, &cell) in grid[row].iter().enumerate() {
+                match (cell, ended[col]) {
+                    (false, _) => ended[col] = true,
+                    (true, false) => (),
+                    (true, true) => {
+                        return Err(error(rows[row].0, col + 1, ErrorKind::BrokenPin));
+                    }
+                }
+            }
+        }
+
+        let mask = grid
+            .iter()
+            .flatten()
+            .enumerate()
+            .fold(0u64, |mask, (i, &c)| mask | (c as u64) << i);
+        if lock {
+            self.locks.push(mask);
+        } else {
+            self.keys.push(mask);
+        }
+        Ok(())
+    }
+
+    /// Every `(lock, key)` pair of indexes where the key fits the lock
+    pub fn fitting_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for (l, lock) in self.locks.iter().enumerate() {
+            for (k, key) in self.keys.iter().enumerate() {
+                if lock & key == 0 {
+                    pairs.push((l, k));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// The puzzle's way of describing a lock or key: how far each pin reaches past the body
+    pub fn heights(&self, mask: u64) -> Vec<usize> {
+        (0..self.pins)
+            .map(|col| {
+                let filled = (0..self.height)
+                    .filter(|row| mask >> (row * self.pins + col) & 1 == 1)
+                    .count();
+                filled - 1
+            })
+            .collect()
+    }
+}
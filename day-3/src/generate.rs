@@ -0,0 +1,46 @@
+use utils::generate::Rng;
+
+/// Near misses that shouldn't count for anything
+const JUNK: [&str; 16] = [
+    "mul[3,7]",
+    "mul ( 2 , 4 )",
+    "mul(4*",
+    "?(12,34)",
+    "select()",
+    "from()",
+    "what()",
+    "where()",
+    "%&",
+    "!@^",
+    "#",
+    "+",
+    "<",
+    "~",
+    "don't",
+    "do_not_",
+];
+
+/// Corrupted memory with `size` real instructions buried in junk, mostly `mul`s with the
+/// odd `do()` and `don't()`, wrapped onto lines of about 50 pieces like the real thing
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    let mut pieces = 0;
+    for _ in 0..size {
+        for _ in 0..rng.below(3) {
+            let junk = rng.pick(&JUNK);
+            input.push_str(junk);
+        }
+        let instruction = match rng.below(10) {
+            0 => "do()".to_string(),
+            1 => "don't()".to_string(),
+            _ => format!("mul({},{})", rng.range(1..1000), rng.range(1..1000)),
+        };
+        input.push_str(&instruction);
+        pieces += 1;
+        if pieces % 50 == 0 {
+            input.push('\n');
+        }
+    }
+    input.push('\n');
+    input
+}
@@ -1,24 +1,42 @@
-use regex::Regex;
-use utils::read_file;
+use utils::parse::read_input;
+use utils::scan;
+
+mod generate;
 
 fn main() {
-    let corrupted_memory = read_file("src/input.txt");
-    let result = uncorrupt_memory(corrupted_memory.as_str());
-    println!("Uncorrupted sum: {}", result);
+    // `day-3 generate <seed> [size]` prints a random input with `size` instructions
+    if utils::generate::command(700, generate::input) {
+        return;
+    }
+    println!("Part 1: {}", part_1("src/input.txt"));
+    println!("Uncorrupted sum: {}", part_2("src/input.txt"));
 }
 
-fn uncorrupt_memory(memory: &str) -> i32 {
-    let instruction_re = Regex::new(r"(mul|do|don't)\((\d*),*(\d*)\)").unwrap();
+fn part_1(filename: &str) -> i32 {
+    uncorrupt_memory(&read_input(filename), false)
+}
+
+fn part_2(filename: &str) -> i32 {
+    uncorrupt_memory(&read_input(filename), true)
+}
+
+/// Sums the `mul`s, and if `conditionals` is set, skips the ones between `don't()` and `do()`
+fn uncorrupt_memory(memory: &str, conditionals: bool) -> i32 {
     let mut result = 0;
     let mut mult_enabled = true;
-    for (_, [inst, num1, num2]) in instruction_re.captures_iter(memory).map(|c| c.extract()) {
-        if inst == "mul" && mult_enabled {
-            let r = num1.parse::<i32>().unwrap() * num2.parse::<i32>().unwrap();
-            result += r;
-        } else if inst == "do" {
+    for (i, _) in memory.char_indices() {
+        let rest = &memory[i..];
+        if rest.starts_with("do()") {
             mult_enabled = true;
-        } else if inst == "don't" {
-            mult_enabled = false;
+        } else if rest.starts_with("don't()") {
+            mult_enabled = !conditionals;
+        } else if let Some((args, _)) = rest.strip_prefix("mul(").and_then(|r| r.split_once(')')) {
+            // Anything but bare numbers in the brackets is corruption, even a sign or a space
+            if mult_enabled && args.bytes().all(|b| b.is_ascii_digit() || b == b',') {
+                if let Ok((a, b)) = scan!(args, "{},{}" => i32, i32) {
+                    result += a * b;
+                }
+            }
         }
     }
     result
@@ -27,24 +45,23 @@ fn uncorrupt_memory(memory: &str) -> i32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
 
-    #[test]
-    fn test_part_1() {
-        assert_eq!(
-            uncorrupt_memory(
-                "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))"
-            ),
-            161
-        );
-    }
+    aoc_test!(test_part_1, "sample-1.txt", part_1 => 161);
+    aoc_test!(test_part_2, "sample-2.txt", part_1 => 161, part_2 => 48);
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
 
     #[test]
-    fn test_part_2() {
-        assert_eq!(
-            uncorrupt_memory(
-                "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))"
-            ),
-            48
-        );
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 200);
+            let (all, enabled) = with_input(&input, |f| (part_1(f), part_2(f)));
+            assert!(enabled < all);
+            // Without any don'ts everything is enabled
+            let input = input.replace("don't()", "");
+            assert_eq!(uncorrupt_memory(&input, true), all);
+        }
     }
 }
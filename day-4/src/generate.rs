@@ -0,0 +1,12 @@
+use utils::generate::Rng;
+
+/// A `size` by `size` word search of random `X`, `M`, `A` and `S`, which turns up plenty
+/// of both kinds of XMAS by chance
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..size {
+        input.extend((0..size).map(|_| *rng.pick(&['X', 'M', 'A', 'S'])));
+        input.push('\n');
+    }
+    input
+}
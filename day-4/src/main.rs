@@ -1,15 +1,25 @@
 use utils::read_file;
 
+mod generate;
+
 fn main() {
-    let input = read_file("src/input.txt");
-    let puzzle = parse_word_search(input);
-    let part_1 = count_xmas_in_word_search(&puzzle);
-    println!("Part 1: {}", part_1);
-    let part_2 = count_x_mas_in_word_search(&puzzle);
-    println!("Part 2: {}", part_2);
+    // `day-4 generate <seed> [size]` prints a random `size` square word search
+    if utils::generate::command(140, generate::input) {
+        return;
+    }
+    println!("Part 1: {}", part_1("src/input.txt"));
+    println!("Part 2: {}", part_2("src/input.txt"));
 }
 
-fn count_xmas_in_word_search(word_search: &Vec<Vec<char>>) -> u32 {
+fn part_1(filename: &str) -> u32 {
+    count_xmas_in_word_search(&parse_word_search(read_file(filename)))
+}
+
+fn part_2(filename: &str) -> u32 {
+    count_x_mas_in_word_search(&parse_word_search(read_file(filename)))
+}
+
+fn count_xmas_in_word_search(word_search: &[Vec<char>]) -> u32 {
     let valid_words_lut = vec![
         vec![('X', 0, 0), ('M', 0, -1), ('A', 0, -2), ('S', 0, -3)], // up
         vec![('X', 0, 0), ('M', 1, -1), ('A', 2, -2), ('S', 3, -3)], // up, right
@@ -18,7 +28,7 @@ fn count_xmas_in_word_search(word_search: &Vec<Vec<char>>) -> u32 {
         vec![('X', 0, 0), ('M', 0, 1), ('A', 0, 2), ('S', 0, 3)],    // down
         vec![('X', 0, 0), ('M', -1, 1), ('A', -2, 2), ('S', -3, 3)], // down, left
         vec![('X', 0, 0), ('M', -1, 0), ('A', -2, 0), ('S', -3, 0)], // left
-        vec![('X', 0, 0), ('M', -1, -1), ('A', -2, -2), ('S', -3, -3)] // up, left
+        vec![('X', 0, 0), ('M', -1, -1), ('A', -2, -2), ('S', -3, -3)], // up, left
     ];
     let mut result = 0;
     for x in 0..word_search[0].len() {
@@ -31,12 +41,36 @@ fn count_xmas_in_word_search(word_search: &Vec<Vec<char>>) -> u32 {
     result
 }
 
-fn count_x_mas_in_word_search(word_search: &Vec<Vec<char>>) -> u32 {
+fn count_x_mas_in_word_search(word_search: &[Vec<char>]) -> u32 {
     let valid_words_lut = vec![
-        vec![('A', 0, 0), ('M', -1, -1), ('S', 1, 1), ('M', 1, -1), ('S', -1, 1)],
-        vec![('A', 0, 0), ('S', -1, -1), ('M', 1, 1), ('M', 1, -1), ('S', -1, 1)],
-        vec![('A', 0, 0), ('M', -1, -1), ('S', 1, 1), ('S', 1, -1), ('M', -1, 1)],
-        vec![('A', 0, 0), ('S', -1, -1), ('M', 1, 1), ('S', 1, -1), ('M', -1, 1)]
+        vec![
+            ('A', 0, 0),
+            ('M', -1, -1),
+            ('S', 1, 1),
+            ('M', 1, -1),
+            ('S', -1, 1),
+        ],
+        vec![
+            ('A', 0, 0),
+            ('S', -1, -1),
+            ('M', 1, 1),
+            ('M', 1, -1),
+            ('S', -1, 1),
+        ],
+        vec![
+            ('A', 0, 0),
+            ('M', -1, -1),
+            ('S', 1, 1),
+            ('S', 1, -1),
+            ('M', -1, 1),
+        ],
+        vec![
+            ('A', 0, 0),
+            ('S', -1, -1),
+            ('M', 1, 1),
+            ('S', 1, -1),
+            ('M', -1, 1),
+        ],
     ];
     let mut result = 0;
     for x in 0..word_search[0].len() {
@@ -50,10 +84,10 @@ fn count_x_mas_in_word_search(word_search: &Vec<Vec<char>>) -> u32 {
 }
 
 fn match_patterns(
-    word_search: &Vec<Vec<char>>,
+    word_search: &[Vec<char>],
     start_x: usize,
     start_y: usize,
-    patterns: &Vec<Vec<(char, i32, i32)>>,
+    patterns: &[Vec<(char, i32, i32)>],
 ) -> u32 {
     let mut result = 0;
     for pattern in patterns {
@@ -88,38 +122,25 @@ fn parse_word_search(raw: String) -> Vec<Vec<char>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
 
-    #[test]
-    fn test_part_1() {
-        let test_data = "MMMSXXMASM
-MSAMXMSMSA
-AMXSXMAAMM
-MSAMASMSMX
-XMASAMXAMM
-XXAMMXXAMA
-SMSMSASXSS
-SAXAMASAAA
-MAMMMXMMMM
-MXMXAXMASX"
-            .to_string();
-        let search = parse_word_search(test_data);
-        assert_eq!(count_xmas_in_word_search(&search), 18);
-    }
+    aoc_test!(test_sample, "sample.txt", part_1 => 18, part_2 => 9);
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
 
     #[test]
-    fn test_part_2() {
-        let test_data = "MMMSXXMASM
-MSAMXMSMSA
-AMXSXMAAMM
-MSAMASMSMX
-XMASAMXAMM
-XXAMMXXAMA
-SMSMSASXSS
-SAXAMASAAA
-MAMMMXMMMM
-MXMXAXMASX"
-            .to_string();
-        let search = parse_word_search(test_data);
-        assert_eq!(count_x_mas_in_word_search(&search), 9);
+    fn test_generated() {
+        for seed in 0..5 {
+            let search = parse_word_search(generate::input(&mut Rng::new(seed), 40));
+            // Both kinds of XMAS look the same flipped along the diagonal
+            let flipped = (0..40)
+                .map(|c| search.iter().map(|row| row[c]).collect())
+                .collect::<Vec<_>>();
+            let xmas = count_xmas_in_word_search(&search);
+            let x_mas = count_x_mas_in_word_search(&search);
+            assert!(xmas > 0 && x_mas > 0);
+            assert_eq!(count_xmas_in_word_search(&flipped), xmas);
+            assert_eq!(count_x_mas_in_word_search(&flipped), x_mas);
+        }
     }
 }
@@ -0,0 +1,37 @@
+use utils::generate::Rng;
+
+/// A puzzle input with `size` different pages (at most 90), laid out like the real one:
+/// the ordering rules, a blank line, then the updates. There's a rule for every pair of
+/// pages, so every update has exactly one correct order. About half the updates are
+/// already in it.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let size = size.clamp(5, 90);
+    let mut order = (10..100).collect::<Vec<u32>>();
+    rng.shuffle(&mut order);
+    order.truncate(size);
+
+    let mut rules = Vec::new();
+    for (i, first) in order.iter().enumerate() {
+        for second in &order[i + 1..] {
+            rules.push(format!("{}|{}", first, second));
+        }
+    }
+    rng.shuffle(&mut rules);
+
+    let mut updates = Vec::new();
+    for _ in 0..size * 4 {
+        let length = rng.range(2..(size as i64 + 1).min(24) / 2 + 1) as usize * 2 - 1;
+        let mut picked = (0..size).collect::<Vec<_>>();
+        rng.shuffle(&mut picked);
+        picked.truncate(length);
+        if rng.chance(0.5) {
+            picked.sort();
+        }
+        let pages = picked
+            .iter()
+            .map(|&p| order[p].to_string())
+            .collect::<Vec<_>>();
+        updates.push(pages.join(","));
+    }
+    format!("{}\n\n{}\n", rules.join("\n"), updates.join("\n"))
+}
@@ -1,138 +1,173 @@
-use utils::read_lines;
+use std::collections::HashMap;
+use utils::graph::Graph;
+use utils::parse::{ints, read_input, sections, AocError};
+use utils::scan;
 
+mod generate;
+
+/// `first|second`: if an update has both pages, `first` has to come before `second`
+#[derive(Debug)]
 struct Rule {
     first_page: u32,
     second_page: u32,
 }
 
 impl Rule {
-    fn new(raw_string: &String) -> Rule {
-        let split: Vec<&str> = raw_string.split("|").collect();
-        Rule {
-            first_page: split[0].parse::<u32>().unwrap(),
-            second_page: split[1].parse::<u32>().unwrap(),
-        }
-    }
-
-    fn validate(&self, pages: &Vec<u32>) -> bool {
-        let first_pos = pages.iter().position(|&r| r == self.first_page);
-        let second_pos = pages.iter().position(|&r| r == self.second_page);
-        (first_pos.is_none() || second_pos.is_none())
-            || (first_pos.is_some()
-                && second_pos.is_some()
-                && first_pos.unwrap() < second_pos.unwrap())
-    }
-
-    fn correct(&self, pages: &Vec<u32>) -> Vec<u32> {
-        let first_pos = pages.iter().position(|&r| r == self.first_page);
-        let second_pos = pages.iter().position(|&r| r == self.second_page);
-        if first_pos.is_some() && second_pos.is_some() {
-            let mut tmp = pages.clone();
-            tmp.remove(second_pos.unwrap());
-            let fp = tmp.iter().position(|&r| r == self.first_page);
-            let mut new_pages = tmp[0..fp.unwrap() + 1].to_vec();
-            new_pages.push(self.second_page);
-            let back_half = tmp[fp.unwrap() + 1..tmp.len()].iter();
-            new_pages.extend(back_half);
-            return new_pages;
-        }
-        pages.clone()
+    fn new(line: &str) -> Result<Rule, AocError> {
+        let (first_page, second_page) = scan!(line, "{}|{}" => u32, u32)?;
+        Ok(Rule {
+            first_page,
+            second_page,
+        })
     }
 }
 
 fn main() {
-    let rules: Vec<Rule> = read_lines("src/rules.txt").iter().map(Rule::new).collect();
-    let pages: Vec<Vec<u32>> = parse_pages(read_lines("src/pages.txt"));
-    let valid_pages = validate_pages(&rules, &pages);
-    let center_sums = sum_center_pages(&valid_pages);
-    println!(
-        "There are {} valid pages, and their center sum is {}.",
-        valid_pages.len(),
-        center_sums
-    );
-    let broken_pages = find_broken_pages(&rules, &pages);
-    let mut fixed_pages = fix_broken_pages(&rules, &broken_pages);
-    while !(validate_pages(&rules, &fixed_pages).len() == broken_pages.len()) {
-        fixed_pages = fix_broken_pages(&rules, &fixed_pages);
+    // `day-5 generate <seed> [size]` prints a random input with `size` different pages
+    if utils::generate::command(49, generate::input) {
+        return;
     }
-    let fixed_sums = sum_center_pages(&fixed_pages);
-    println!(
-        "There are {} broken pages, and their center sum is {} after repair.",
-        broken_pages.len(),
-        fixed_sums
-    );
+    println!("Part 1: {}", part_1("src/input.txt"));
+    println!("Part 2: {}", part_2("src/input.txt"));
 }
 
-fn parse_pages(pages: Vec<String>) -> Vec<Vec<u32>> {
-    pages
-        .iter()
-        .map(|p| p.split(",").map(|n| n.parse::<u32>().unwrap()).collect())
-        .collect()
+/// The rules, a blank line, then one update per line
+fn parse(input: &str) -> Result<(Vec<Rule>, Vec<Vec<u32>>), AocError> {
+    let [rules, pages] = sections(input)[..] else {
+        return Err(AocError::at(input, 0, "expected rules and updates"));
+    };
+    let rules = rules
+        .numbered_lines()
+        .map(|(i, line)| Rule::new(line).map_err(|e| e.below(i - 1)))
+        .collect::<Result<_, _>>()?;
+    let pages = pages
+        .numbered_lines()
+        .map(|(i, line)| ints(line).map_err(|e| e.below(i - 1)))
+        .collect::<Result<_, _>>()?;
+    Ok((rules, pages))
 }
 
-fn validate_pages(rules: &Vec<Rule>, pages: &Vec<Vec<u32>>) -> Vec<Vec<u32>> {
-    pages
-        .iter()
-        .filter(|&p| rules.iter().all(|r| r.validate(p)))
-        .cloned()
-        .collect()
+fn parse_input(filename: &str) -> (Vec<Rule>, Vec<Vec<u32>>) {
+    parse(&read_input(filename)).unwrap_or_else(|e| panic!("{}: {}", filename, e))
 }
 
-fn find_broken_pages(rules: &Vec<Rule>, pages: &Vec<Vec<u32>>) -> Vec<Vec<u32>> {
-    pages
-        .iter()
-        .filter(|&p| !rules.iter().all(|r| r.validate(p)))
-        .cloned()
-        .collect()
+/// Center sum of the updates that are already in order
+fn part_1(filename: &str) -> u32 {
+    let (rules, pages) = parse_input(filename);
+    let valid = pages
+        .into_iter()
+        .filter(|p| in_order(&rules, p))
+        .collect::<Vec<_>>();
+    sum_center_pages(&valid)
 }
 
-fn fix_broken_pages(rules: &Vec<Rule>, pages: &Vec<Vec<u32>>) -> Vec<Vec<u32>> {
-    pages
+/// Center sum of the broken updates, once they've been put in order
+fn part_2(filename: &str) -> u32 {
+    let (rules, pages) = parse_input(filename);
+    let fixed = pages
         .iter()
-        .map(|p| {
-            let mut new_p = p.clone();
-            for rule in rules {
-                if !rule.validate(&new_p) {
-                    new_p = rule.correct(&new_p);
-                }
+        .filter(|p| !in_order(&rules, p))
+        .map(|p| put_in_order(&rules, p))
+        .collect::<Vec<_>>();
+    sum_center_pages(&fixed)
+}
+
+/// The rules that apply to one update, as a graph where page `i` of the update is node `i`.
+/// A page that shows up more than once gets a node for each time.
+fn rule_graph(rules: &[Rule], pages: &[u32]) -> Graph {
+    let mut positions: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (i, &page) in pages.iter().enumerate() {
+        positions.entry(page).or_default().push(i);
+    }
+    let mut graph = Graph::new(pages.len(), true);
+    for rule in rules {
+        if let (Some(firsts), Some(seconds)) = (
+            positions.get(&rule.first_page),
+            positions.get(&rule.second_page),
+        ) {
+            for (&a, &b) in firsts
+                .iter()
+                .flat_map(|a| seconds.iter().map(move |b| (a, b)))
+            {
+                graph.add_edge(a, b);
             }
-            new_p
-        })
-        .collect()
+        }
+    }
+    graph
 }
 
-fn sum_center_pages(pages: &Vec<Vec<u32>>) -> u32 {
+/// Nodes are numbered in update order, so it's in order if every rule points forwards
+fn in_order(rules: &[Rule], pages: &[u32]) -> bool {
+    rule_graph(rules, pages).edges().all(|(a, b)| a < b)
+}
+
+fn put_in_order(rules: &[Rule], pages: &[u32]) -> Vec<u32> {
+    match rule_graph(rules, pages).toposort() {
+        Ok(order) => order.into_iter().map(|i| pages[i]).collect(),
+        Err(cycle) => panic!(
+            "rules contradict each other: {:?}",
+            cycle.iter().map(|&i| pages[i]).collect::<Vec<_>>()
+        ),
+    }
+}
+
+fn sum_center_pages(pages: &[Vec<u32>]) -> u32 {
     pages.iter().map(|p| p[p.len() / 2]).sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use utils::read_lines;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(test_sample, "sample.txt", part_1 => 143, part_2 => 123);
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
+
+    #[test]
+    fn test_put_in_order() {
+        let (rules, _) = parse_input("src/sample.txt");
+        assert!(in_order(&rules, &[75, 47, 61, 53, 29]));
+        assert!(!in_order(&rules, &[97, 13, 75, 29, 47]));
+        assert_eq!(
+            put_in_order(&rules, &[75, 97, 47, 61, 53]),
+            [97, 75, 47, 61, 53]
+        );
+        assert_eq!(
+            put_in_order(&rules, &[97, 13, 75, 29, 47]),
+            [97, 75, 47, 29, 13]
+        );
+        // A repeated page still has to follow the rules both times
+        assert!(in_order(&rules, &[75, 47, 47, 61, 53]));
+        assert!(!in_order(&rules, &[75, 47, 61, 47, 53]));
+        assert_eq!(put_in_order(&rules, &[47, 75, 47, 53]), [75, 47, 47, 53]);
+    }
 
     #[test]
-    fn test_part_1() {
-        let test_rules: Vec<Rule> = read_lines("src/test-rules.txt")
-            .iter()
-            .map(Rule::new)
-            .collect();
-        let test_pages: Vec<Vec<u32>> = parse_pages(read_lines("src/test-pages.txt"));
-        let valid_pages = validate_pages(&test_rules, &test_pages);
-        assert_eq!(sum_center_pages(&valid_pages), 143);
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 20);
+            let (valid, fixed) = with_input(&input, |f| (part_1(f), part_2(f)));
+            // Every update put in order with a plain sort, using the rules to compare
+            let (rules, pages) = parse(&input).unwrap();
+            let sorted = pages
+                .into_iter()
+                .map(|mut update| {
+                    update.sort_by_key(|&p| rules.iter().filter(|r| r.second_page == p).count());
+                    update
+                })
+                .collect::<Vec<_>>();
+            assert!(valid > 0 && fixed > 0);
+            assert_eq!(valid + fixed, sum_center_pages(&sorted));
+        }
     }
 
     #[test]
-    fn test_part_2() {
-        let test_rules: Vec<Rule> = read_lines("src/test-rules.txt")
-            .iter()
-            .map(Rule::new)
-            .collect();
-        let test_pages: Vec<Vec<u32>> = parse_pages(read_lines("src/test-pages.txt"));
-        let broken_pages = find_broken_pages(&test_rules, &test_pages);
-        let fixed_pages: Vec<Vec<u32>> = fix_broken_pages(&test_rules, &broken_pages);
-        // while !validate_pages(&test_rules, &fixed_pages).len() == broken_pages.len() {
-        //     fixed_pages = fix_broken_pages(&test_rules, &broken_pages);
-        // }
-        assert_eq!(sum_center_pages(&fixed_pages), 123);
+    fn test_malformed() {
+        let error = parse("47|53\n97-13\n\n75,47").unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 3: expected `|`");
+        let error = parse("47|53\n\n75,47\n\n61").unwrap_err();
+        assert_eq!(error.message, "expected rules and updates");
     }
 }
@@ -0,0 +1,51 @@
+use std::collections::HashSet;
+use utils::generate::Rng;
+
+/// A `size` by `size` lab with the guard facing up somewhere in it. Maps where the guard
+/// walks in a loop from the start are thrown away, so she always gets out, and of the
+/// first ten that are left we keep the one with the longest walk.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(2);
+    let mut best: Option<(usize, String)> = None;
+    let mut candidates = 0;
+    while candidates < 10 {
+        let mut grid = (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| if rng.chance(0.05) { '#' } else { '.' })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let (row, col) = (rng.below(size), rng.below(size));
+        grid[row][col] = '^';
+        let Some(steps) = walk(&grid, row, col) else {
+            continue;
+        };
+        candidates += 1;
+        if best.as_ref().is_none_or(|(most, _)| steps > *most) {
+            let map = grid.iter().map(|r| r.iter().collect::<String>() + "\n");
+            best = Some((steps, map.collect()));
+        }
+    }
+    best.unwrap().1
+}
+
+/// How many steps the guard takes to get out, or `None` if she never does
+fn walk(grid: &[Vec<char>], mut row: usize, mut col: usize) -> Option<usize> {
+    let size = grid.len() as isize;
+    let mut direction = (-1, 0);
+    let mut seen = HashSet::new();
+    while seen.insert((row, col, direction)) {
+        let (r, c) = (row as isize + direction.0, col as isize + direction.1);
+        if r < 0 || c < 0 || r >= size || c >= size {
+            return Some(seen.len());
+        }
+        if grid[r as usize][c as usize] == '#' {
+            // Turn right
+            direction = (direction.1, -direction.0);
+        } else {
+            (row, col) = (r as usize, c as usize);
+        }
+    }
+    None
+}
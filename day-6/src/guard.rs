@@ -23,8 +23,8 @@ impl Guard {
     pub fn new(row: usize, column: usize) -> Guard {
         Guard {
             direction: Direction::North,
-            row: row,
-            column: column,
+            row,
+            column,
             escaped: false,
             stuck: false,
         }
@@ -33,6 +33,7 @@ impl Guard {
     /// Walk implements these two basic rules:
     /// - If there is something directly in front of you, turn right 90 degrees.
     /// - Otherwise, take a step forward.
+    ///
     /// When the guard leaves the map, the "escaped" flag will be set and further
     /// calls to `walk()` will have no effect.
     ///
@@ -86,6 +87,6 @@ impl Guard {
             self.column = next_col as usize;
             self.row = next_row as usize;
         }
-        return Ok(false);
+        Ok(false)
     }
 }
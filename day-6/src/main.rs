@@ -1,21 +1,65 @@
 use array2d::Array2D;
-use guard::Guard;
+use guard::{Direction, Guard};
 use map_tile::MapTile;
+use utils::viz::{Cell, Color, Grid, Recorder};
 use utils::{read_lines, time};
 
+mod generate;
 mod guard;
 mod map_tile;
 
 fn main() {
-    let (map, guard) = build_map("src/map.txt");
-    let visited_tiles = time(|| walk_map(&mut map.clone(), &mut guard.clone()));
-    println!("Part 1: The Guard visited {} tiles (took {} seconds)", visited_tiles.0, visited_tiles.1.as_secs_f32());
-    let blocked_tiles = time(|| block_map(&mut map.clone(), &mut guard.clone()));
-    println!("Part 2: There are {} possible block positions (took {} seconds)", blocked_tiles.0, blocked_tiles.1.as_secs_f32());
+    // `day-6 generate <seed> [size]` prints a random `size` square map
+    if utils::generate::command(130, generate::input) {
+        return;
+    }
+    // `day-6 show [dir]` plays the guard's walk, or writes the frames to `dir`
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(|a| a.as_str()) == Some("show") {
+        let (mut map, mut guard) = build_map("src/map.txt");
+        let mut recorder = Recorder::new(1000);
+        let mut steps = 0;
+        let visited = trace_walk(&mut map, &mut guard, |map, guard| {
+            steps += 1;
+            recorder.record(&Patrol { map, guard }, &format!("step {}", steps));
+        });
+        let caption = format!("escaped after visiting {} tiles", visited);
+        let frames = recorder.finish(
+            &Patrol {
+                map: &map,
+                guard: &guard,
+            },
+            &caption,
+        );
+        utils::viz::present(&frames, args.get(2).map(|a| a.as_str()), 60.0).unwrap();
+        return;
+    }
+    let visited_tiles = time(|| part_1("src/map.txt"));
+    println!(
+        "Part 1: The Guard visited {} tiles (took {} seconds)",
+        visited_tiles.0,
+        visited_tiles.1.as_secs_f32()
+    );
+    let blocked_tiles = time(|| part_2("src/map.txt"));
+    println!(
+        "Part 2: There are {} possible block positions (took {} seconds)",
+        blocked_tiles.0,
+        blocked_tiles.1.as_secs_f32()
+    );
+}
+
+fn part_1(filename: &str) -> usize {
+    let (mut map, mut guard) = build_map(filename);
+    walk_map(&mut map, &mut guard)
+}
+
+fn part_2(filename: &str) -> usize {
+    let (map, guard) = build_map(filename);
+    block_map(&map, &guard)
 }
 
 fn build_map(filename: &str) -> (Array2D<MapTile>, Guard) {
-    let lines = read_lines(&filename);
+    let lines = read_lines(filename);
     let guard_row = lines.iter().position(|l| l.contains("^")).unwrap();
     let guard_col = lines[guard_row].find("^").unwrap();
     let raw_tiles: Vec<Vec<MapTile>> = lines
@@ -29,7 +73,17 @@ fn build_map(filename: &str) -> (Array2D<MapTile>, Guard) {
 }
 
 fn walk_map(map: &mut Array2D<MapTile>, guard: &mut Guard) -> usize {
+    trace_walk(map, guard, |_, _| ())
+}
+
+/// Walks the guard off the map, calling `on_step` before every step she takes
+fn trace_walk(
+    map: &mut Array2D<MapTile>,
+    guard: &mut Guard,
+    mut on_step: impl FnMut(&Array2D<MapTile>, &Guard),
+) -> usize {
     while !guard.escaped {
+        on_step(map, guard);
         let r = guard.walk(map);
         if r.is_ok_and(|t| t) {
             break;
@@ -38,6 +92,44 @@ fn walk_map(map: &mut Array2D<MapTile>, guard: &mut Guard) -> usize {
     map.elements_row_major_iter().filter(|t| t.visited).count()
 }
 
+/// The map with the guard on it and her route so far drawn in
+struct Patrol<'a> {
+    map: &'a Array2D<MapTile>,
+    guard: &'a Guard,
+}
+
+impl Grid for Patrol<'_> {
+    fn size(&self) -> (usize, usize) {
+        (self.map.num_rows(), self.map.num_columns())
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Cell {
+        if !self.guard.escaped && (row, col) == (self.guard.row, self.guard.column) {
+            let arrow = match self.guard.direction {
+                Direction::North => '^',
+                Direction::East => '>',
+                Direction::South => 'v',
+                Direction::West => '<',
+            };
+            return Cell::new(arrow, Color::Red);
+        }
+        let tile = &self.map[(row, col)];
+        if tile.obstacle {
+            return Cell::new('#', Color::Gray);
+        }
+        let vertical =
+            tile.has_been_visited(Direction::North) || tile.has_been_visited(Direction::South);
+        let horizontal =
+            tile.has_been_visited(Direction::East) || tile.has_been_visited(Direction::West);
+        match (vertical, horizontal) {
+            (true, true) => Cell::new('+', Color::Yellow),
+            (true, false) => Cell::new('|', Color::Yellow),
+            (false, true) => Cell::new('-', Color::Yellow),
+            (false, false) => '.'.into(),
+        }
+    }
+}
+
 fn block_map(map: &Array2D<MapTile>, guard: &Guard) -> usize {
     // Walk a copy of the map from the starting point so that we can use it
     // to filter which tiles should be tried
@@ -60,7 +152,7 @@ fn block_map(map: &Array2D<MapTile>, guard: &Guard) -> usize {
             } else {
                 test_tile.obstacle = true;
             }
-            while !guard.escaped && !guard.stuck {
+            while !new_guard.escaped && !new_guard.stuck {
                 let r = new_guard.walk(&mut new_map);
                 if r.is_err() {
                     // got stuck, increment possibility counter
@@ -78,18 +170,64 @@ fn block_map(map: &Array2D<MapTile>, guard: &Guard) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(test_sample, "test-map.txt", part_1 => 41, part_2 => 6);
+    aoc_test!(test_input, optional "map.txt", part_1, part_2);
 
     #[test]
-    fn test_part_1() {
+    fn test_frames() {
         let (mut map, mut guard) = build_map("src/test-map.txt");
-        let visited_tiles = walk_map(&mut map, &mut guard);
-        assert_eq!(visited_tiles, 41);
+        let mut recorder = Recorder::new(1000);
+        trace_walk(&mut map, &mut guard, |map, guard| {
+            recorder.record(&Patrol { map, guard }, "")
+        });
+        let frames = recorder.finish(
+            &Patrol {
+                map: &map,
+                guard: &guard,
+            },
+            "",
+        );
+        assert_eq!(frames[0].to_plain().lines().nth(6).unwrap(), ".#..^.....");
+        // Her whole route, with `+` wherever she turned or crossed her own path
+        assert_eq!(
+            frames.last().unwrap().to_plain(),
+            "\
+....#.....
+....+---+#
+....|...|.
+..#.|...|.
+..+-+-+#|.
+..|.|.|.|.
+.#+-+-+-+.
+.+----++#.
+#+----+|..
+......#|..
+"
+        );
     }
 
     #[test]
-    fn test_part_2() {
+    fn test_block_map() {
         let (map, guard) = build_map("src/test-map.txt");
-        let blocked_tiles = block_map(&map, &guard);
-        assert_eq!(blocked_tiles, 6);
+        assert_eq!(block_map(&map, &guard), 6);
+        // She goes round three sides and out the left, and only blocking her way out
+        // turns her back onto her own path
+        let (map, guard) = with_input(".#..\n...#\n.^..\n..#.\n", build_map);
+        assert_eq!(block_map(&map, &guard), 1);
+    }
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 25);
+            let (visited, blocks) = with_input(&input, |f| (part_1(f), part_2(f)));
+            // Only tiles on her route are worth blocking, and never the one she starts on
+            assert!(visited > 0);
+            assert!(blocks < visited);
+        }
     }
 }
@@ -1,19 +1,18 @@
 use crate::guard::Direction;
 
-#[derive(Clone)]
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct MapTile {
     pub obstacle: bool,
     pub visited: bool,
-    visited_from: Vec<Direction>
+    visited_from: Vec<Direction>,
 }
 
- impl MapTile {
+impl MapTile {
     pub fn new(raw_string: char) -> MapTile {
         MapTile {
             obstacle: raw_string == '#',
             visited: false,
-            visited_from: Vec::new()
+            visited_from: Vec::new(),
         }
     }
 
@@ -25,4 +24,4 @@ pub struct MapTile {
     pub fn has_been_visited(&self, direction: Direction) -> bool {
         self.visited && self.visited_from.contains(&direction)
     }
-}
\ No newline at end of file
+}
@@ -8,7 +8,7 @@ pub struct Equation {
 }
 
 impl Equation {
-    pub fn new(line: &String) -> Equation {
+    pub fn new(line: &str) -> Equation {
         let halves: Vec<&str> = line.split(":").collect();
         let expected_result = halves[0].parse::<u128>().unwrap();
         let components: Vec<&str> = halves[1].split_whitespace().collect();
@@ -22,11 +22,11 @@ impl Equation {
         }
     }
 
-    pub fn validate(&mut self, operators: &Vec<char>) -> bool {
-        let operands: Vec<Vec<&char>> = std::iter::repeat(operators.iter())
-            .take(self.components.len() - 1)
-            .multi_cartesian_product()
-            .collect();
+    pub fn validate(&mut self, operators: &[char]) -> bool {
+        let operands: Vec<Vec<&char>> =
+            std::iter::repeat_n(operators.iter(), self.components.len() - 1)
+                .multi_cartesian_product()
+                .collect();
         for ops in operands {
             let mut result = self.components[0];
             for (i, &op) in ops.iter().enumerate() {
@@ -0,0 +1,35 @@
+use utils::generate::Rng;
+
+/// Keeps results in the same range as the real input
+const LIMIT: u128 = 100_000_000_000_000;
+
+/// `size` calibration equations. Each one is worked out from random numbers and operators,
+/// so a third can be made with `+` and `*`, a third need `||` as well, and the rest have
+/// their result nudged so that they probably can't be made at all.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..size {
+        let kind = rng.below(3);
+        let numbers = (0..rng.range(2..11))
+            .map(|_| {
+                let most = if rng.chance(0.7) { 100 } else { 1000 };
+                rng.range(1..most) as u128
+            })
+            .collect::<Vec<_>>();
+        let mut result = numbers[0];
+        for &n in &numbers[1..] {
+            let concatenated = format!("{}{}", result, n).parse::<u128>().unwrap();
+            result = match rng.below(3) {
+                0 if kind == 1 && concatenated < LIMIT => concatenated,
+                1 if result * n < LIMIT => result * n,
+                _ => result + n,
+            };
+        }
+        if kind == 2 {
+            result += rng.range(1..10) as u128;
+        }
+        let numbers = numbers.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        input.push_str(&format!("{}: {}\n", result, numbers.join(" ")));
+    }
+    input
+}
@@ -2,38 +2,75 @@ use eq::Equation;
 use utils::{read_lines, time};
 
 mod eq;
+mod generate;
 
 fn main() {
+    // `day-7 generate <seed> [size]` prints a random input with `size` equations
+    if utils::generate::command(850, generate::input) {
+        return;
+    }
     let part_1 = time(|| validate_equations("src/input.txt", vec!['+', '*']));
-    println!("Part 1 result: {} (took {} seconds)", part_1.0, part_1.1.as_secs_f64());
+    println!(
+        "Part 1 result: {} (took {} seconds)",
+        part_1.0,
+        part_1.1.as_secs_f64()
+    );
     let part_2 = time(|| validate_equations("src/input.txt", vec!['+', '*', '|']));
-    println!("Part 2 result: {} (took {} seconds)", part_2.0, part_2.1.as_secs_f64());
+    println!(
+        "Part 2 result: {} (took {} seconds)",
+        part_2.0,
+        part_2.1.as_secs_f64()
+    );
 }
 
 fn validate_equations(filename: &str, operators: Vec<char>) -> u128 {
     let mut equations: Vec<Equation> = read_lines(filename)
-            .iter()
-            .map(Equation::new)
-            .collect();
+        .iter()
+        .map(|l| Equation::new(l))
+        .collect();
     for eq in &mut equations {
         eq.validate(&operators);
     }
-    equations.iter().filter(|&e| e.valid.unwrap_or(false)).map(|e| e.result).sum()
+    equations
+        .iter()
+        .filter(|&e| e.valid.unwrap_or(false))
+        .map(|e| e.result)
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
 
-    #[test]
-    fn test_part_1() {
-        let result = validate_equations("src/example.txt", vec!['+', '*']);
-        assert_eq!(result, 3749);
-    }
+    aoc_test!(
+        test_sample,
+        "example.txt",
+        |f| validate_equations(f, vec!['+', '*']) => 3749,
+        |f| validate_equations(f, vec!['+', '*', '|']) => 11387,
+    );
+    aoc_test!(
+        test_input,
+        optional "input.txt",
+        |f| validate_equations(f, vec!['+', '*']),
+        |f| validate_equations(f, vec!['+', '*', '|']),
+    );
 
     #[test]
-    fn test_part_2() {
-        let result = validate_equations("src/example.txt", vec!['+', '*', '|']);
-        assert_eq!(result, 11387);
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 100);
+            let (two, three) = with_input(&input, |f| {
+                (
+                    validate_equations(f, vec!['+', '*']),
+                    validate_equations(f, vec!['+', '*', '|']),
+                )
+            });
+            // Concatenation can only ever make more equations work
+            assert!(two > 0);
+            assert!(three > two);
+        }
     }
 }
@@ -0,0 +1,19 @@
+use utils::generate::Rng;
+
+const FREQUENCIES: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// A `size` by `size` map with a few antennas on each of a handful of frequencies, about
+/// as crowded as the real one
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(2);
+    let mut map = vec![vec![b'.'; size]; size];
+    let frequencies = (size / 2).clamp(1, FREQUENCIES.len());
+    for &frequency in &FREQUENCIES[..frequencies] {
+        for _ in 0..rng.range(2..6) {
+            map[rng.below(size)][rng.below(size)] = frequency;
+        }
+    }
+    map.into_iter()
+        .map(|row| String::from_utf8(row).unwrap() + "\n")
+        .collect()
+}
@@ -1,8 +1,10 @@
-use std::iter::repeat;
+use std::iter::repeat_n;
 
 use itertools::Itertools;
 use utils::{read_lines, time};
 
+mod generate;
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 struct Antenna {
     frequency: char,
@@ -40,13 +42,17 @@ impl Antenna {
 }
 
 fn main() {
-    let part1 = time(|| parse_antennas("src/input.txt", 1, false));
+    // `day-8 generate <seed> [size]` prints a random `size` square map
+    if utils::generate::command(50, generate::input) {
+        return;
+    }
+    let part1 = time(|| part_1("src/input.txt"));
     println!(
         "Part 1: there are {} unique antinodes (took {} seconds)",
         part1.0,
         part1.1.as_secs_f64()
     );
-    let part2 = time(|| parse_antennas("src/input.txt", 25, true));
+    let part2 = time(|| part_2("src/input.txt"));
     println!(
         "Part 2: there are {} unique antinodes (took {} seconds)",
         part2.0,
@@ -54,6 +60,17 @@ fn main() {
     );
 }
 
+fn part_1(filename: &str) -> usize {
+    parse_antennas(filename, 1, false)
+}
+
+/// Nothing further out than the map is wide (or tall) can land on it
+fn part_2(filename: &str) -> usize {
+    let lines = read_lines(filename);
+    let size = lines.len().max(lines.first().map_or(0, |l| l.len()));
+    parse_antennas(filename, size as i32, true)
+}
+
 fn parse_antennas(filename: &str, limit: i32, include_antennas: bool) -> usize {
     let lines = read_lines(filename);
     let map_w = lines[0].len() as i32;
@@ -70,13 +87,11 @@ fn parse_antennas(filename: &str, limit: i32, include_antennas: bool) -> usize {
                 None
             })
         })
-        .filter(|a| a.is_some())
-        .map(|a| a.unwrap())
+        .flatten()
         .into_group_map_by(|a| a.frequency)
-        .iter()
-        .flat_map(|(_, antennas)| {
-            repeat(antennas.iter())
-                .take(2)
+        .values()
+        .flat_map(|antennas| {
+            repeat_n(antennas.iter(), 2)
                 .multi_cartesian_product()
                 .filter(|a| !a.iter().all_equal())
                 .flat_map(|c| c[0].antinodes(c[1], limit, include_antennas))
@@ -90,16 +105,26 @@ fn parse_antennas(filename: &str, limit: i32, include_antennas: bool) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
 
-    #[test]
-    fn test_part_1() {
-        let antinodes = parse_antennas("src/test-map.txt", 1, false);
-        assert_eq!(antinodes, 14);
-    }
+    aoc_test!(test_sample, "test-map.txt", part_1 => 14, part_2 => 34);
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
 
     #[test]
-    fn test_part_2() {
-        let antinodes = parse_antennas("src/test-map.txt", 50, true);
-        assert_eq!(antinodes, 34);
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 30);
+            let (pairs, lines) = with_input(&input, |f| (part_1(f), part_2(f)));
+            // Every antenna with a partner is on a line, and so are both of its antinodes
+            let partnered = input
+                .bytes()
+                .filter(|&b| b != b'.' && b != b'\n')
+                .filter(|&b| input.bytes().filter(|&c| c == b).count() > 1)
+                .count();
+            assert!(pairs > 0);
+            assert!(lines >= pairs.max(partnered));
+        }
     }
 }
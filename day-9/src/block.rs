@@ -13,10 +13,10 @@ impl Block {
         for _ in 0..size {
             result.push(Block {
                 id: position,
-                empty: empty,
+                empty,
                 shadow_id: Uuid::new_v4(),
             });
         }
         result
     }
-}
\ No newline at end of file
+}
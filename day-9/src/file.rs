@@ -12,7 +12,7 @@ impl File {
     pub fn new(position: usize, size: u32, empty: bool) -> File {
         File {
             id: position,
-            empty: empty,
+            empty,
             size: size as usize,
             shadow_id: Uuid::new_v4(),
         }
@@ -22,6 +22,8 @@ impl File {
         if self.empty {
             return 0;
         }
-        (start_position .. start_position + self.size).map(|p| self.id * p).sum()
+        (start_position..start_position + self.size)
+            .map(|p| self.id * p)
+            .sum()
     }
-}
\ No newline at end of file
+}
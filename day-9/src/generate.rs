@@ -0,0 +1,18 @@
+use utils::generate::Rng;
+
+/// A disk map `size` digits long, alternating files of 1 to 9 blocks with gaps of 0 to 9.
+/// It always ends on a file, like the real one does.
+pub fn input(rng: &mut Rng, size: usize) -> String {
+    let mut disk = (0..size.max(1) | 1)
+        .map(|i| {
+            let digit = if i % 2 == 0 {
+                rng.range(1..10)
+            } else {
+                rng.range(0..10)
+            };
+            char::from_digit(digit as u32, 10).unwrap()
+        })
+        .collect::<String>();
+    disk.push('\n');
+    disk
+}
@@ -1,5 +1,6 @@
 mod block;
 mod file;
+mod generate;
 
 use block::Block;
 use file::File;
@@ -8,16 +9,18 @@ use itertools::Itertools;
 use utils::{read_file, time};
 
 fn main() {
-    let part1_disk = parse_disk_to_blocks(&read_file("src/input.txt"));
-    let (result1, time1) = time(|| defrag_stupidly(&part1_disk));
+    // `day-9 generate <seed> [size]` prints a random disk map `size` digits long
+    if utils::generate::command(19999, generate::input) {
+        return;
+    }
+    let (result1, time1) = time(|| part_1("src/input.txt"));
     println!(
         "Part 1 Checksum: {} (took {} seconds)",
         result1,
         time1.as_secs_f64()
     );
 
-    let part2_disk = parse_disk_to_files(&read_file("src/input.txt"));
-    let (result2, time2) = time(|| defrag(&part2_disk));
+    let (result2, time2) = time(|| part_2("src/input.txt"));
     println!(
         "Part 2 Checksum: {} (took {} seconds)",
         result2,
@@ -25,6 +28,14 @@ fn main() {
     );
 }
 
+fn part_1(filename: &str) -> usize {
+    defrag_stupidly(&parse_disk_to_blocks(read_file(filename).trim()))
+}
+
+fn part_2(filename: &str) -> usize {
+    defrag(&parse_disk_to_files(read_file(filename).trim()))
+}
+
 fn parse_disk_to_blocks(disk_str: &str) -> Vec<Block> {
     let mut disk: Vec<Block> = Vec::new();
     let mut data_block_idx = 0;
@@ -61,15 +72,15 @@ fn parse_disk_to_files(disk_str: &str) -> Vec<File> {
     disk
 }
 
-fn defrag_stupidly(disk: &Vec<Block>) -> usize {
-    let mut new_disk = disk.clone();
+fn defrag_stupidly(disk: &[Block]) -> usize {
+    let mut new_disk = disk.to_vec();
     let mut disk_iter = disk.iter();
     let mut front = disk_iter.next();
     let mut front_idx = 0;
     let mut back = disk_iter.next_back();
     let mut back_idx = disk.len() - 1;
     while front.is_some() && back.is_some() {
-        while !front.unwrap().empty {
+        while front.is_some_and(|f| !f.empty) {
             front = disk_iter.next();
             front_idx += 1;
         }
@@ -96,7 +107,7 @@ fn defrag_stupidly(disk: &Vec<Block>) -> usize {
         .sum()
 }
 
-fn defrag(disk: &Vec<File>) -> usize {
+fn defrag(disk: &[File]) -> usize {
     let max_disk: Vec<usize> = disk
         .iter()
         .filter(|&d| !d.empty)
@@ -105,7 +116,7 @@ fn defrag(disk: &Vec<File>) -> usize {
         .map(|d| d.id)
         .collect();
     let bar = ProgressBar::new(max_disk[0] as u64);
-    let mut new_disk = disk.clone();
+    let mut new_disk = disk.to_vec();
     for file_id in disk
         .iter()
         .filter(|&d| !d.empty)
@@ -153,20 +164,122 @@ fn defrag(disk: &Vec<File>) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use utils::aoc_test;
+    use utils::differential::{check, shrink_vec};
+    use utils::generate::Rng;
+    use utils::test_support::with_input;
+
+    aoc_test!(test_sample, "sample.txt", part_1 => 1928, part_2 => 2858);
+    aoc_test!(test_input, optional "input.txt", part_1, part_2);
 
     #[test]
-    fn test_part_1() {
-        let example_disk = "2333133121414131402";
-        let disk = parse_disk_to_blocks(example_disk);
-        let defrag_result = defrag_stupidly(&disk);
-        assert_eq!(defrag_result, 1928);
+    fn test_generated() {
+        for seed in 0..5 {
+            let input = generate::input(&mut Rng::new(seed), 201);
+            assert_eq!(input.trim().len(), 201);
+            let (blocks, files) = with_input(&input, |f| (part_1(f), part_2(f)));
+            // Whole files can't always move, so they never end up further left than blocks
+            assert!(files >= blocks);
+            // Squeezing out the gaps by hand means there's nothing left to move
+            let packed = input
+                .trim()
+                .chars()
+                .enumerate()
+                .map(|(i, c)| if i % 2 == 0 { c } else { '0' })
+                .collect::<String>();
+            assert_eq!(
+                defrag_stupidly(&parse_disk_to_blocks(&packed)),
+                defrag(&parse_disk_to_files(&packed))
+            );
+        }
+    }
+
+    /// Every block on the disk, `None` for free space
+    fn expand(disk: &str) -> Vec<Option<usize>> {
+        disk.chars()
+            .enumerate()
+            .flat_map(|(i, c)| {
+                let id = if i % 2 == 0 { Some(i / 2) } else { None };
+                std::iter::repeat_n(id, c.to_digit(10).unwrap() as usize)
+            })
+            .collect()
+    }
+
+    fn checksum(blocks: &[Option<usize>]) -> usize {
+        blocks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| i * b.unwrap_or(0))
+            .sum()
+    }
+
+    /// Swaps the last block into the first gap until every gap is at the end
+    fn naive_blocks(disk: &str) -> usize {
+        let mut blocks = expand(disk);
+        while let (Some(gap), Some(last)) = (
+            blocks.iter().position(|b| b.is_none()),
+            blocks.iter().rposition(|b| b.is_some()),
+        ) {
+            if gap > last {
+                break;
+            }
+            blocks.swap(gap, last);
+        }
+        checksum(&blocks)
+    }
+
+    /// Tries each file once, highest id first, in the leftmost gap it fits that's left of it
+    fn naive_files(disk: &str) -> usize {
+        let mut blocks = expand(disk);
+        for id in (0..disk.len().div_ceil(2)).rev() {
+            let start = blocks.iter().position(|&b| b == Some(id)).unwrap();
+            let size = blocks.iter().filter(|&&b| b == Some(id)).count();
+            let gap = (0..start).find(|&g| blocks[g..g + size].iter().all(|b| b.is_none()));
+            if let Some(gap) = gap {
+                (0..size).for_each(|i| blocks.swap(gap + i, start + i));
+            }
+        }
+        checksum(&blocks)
+    }
+
+    /// Drops files along with the gap after them, or makes one file or gap a block smaller
+    fn shrink_disk(disk: &str) -> Vec<String> {
+        let pairs = disk.as_bytes().chunks(2).collect::<Vec<_>>();
+        let mut smaller = shrink_vec(&pairs)
+            .into_iter()
+            .filter(|p| !p.is_empty())
+            .map(|p| String::from_utf8(p.concat()).unwrap())
+            .collect::<Vec<_>>();
+        for (i, c) in disk.bytes().enumerate() {
+            // Files keep at least one block, or there'd be nothing to find
+            if c > if i % 2 == 0 { b'1' } else { b'0' } {
+                let mut fewer = disk.as_bytes().to_vec();
+                fewer[i] -= 1;
+                smaller.push(String::from_utf8(fewer).unwrap());
+            }
+        }
+        smaller
     }
 
     #[test]
-    fn test_part_2() {
-        let example_disk = "2333133121414131402";
-        let disk = parse_disk_to_files(example_disk);
-        let defrag_result = defrag(&disk);
-        assert_eq!(defrag_result, 2858);
+    fn test_against_naive() {
+        let generate = |rng: &mut Rng| {
+            let size = rng.below(30) + 1;
+            generate::input(rng, size).trim().to_string()
+        };
+        check(
+            300,
+            generate,
+            |d| shrink_disk(d),
+            |d| naive_blocks(d),
+            |d| defrag_stupidly(&parse_disk_to_blocks(d)),
+        );
+        check(
+            300,
+            generate,
+            |d| shrink_disk(d),
+            |d| naive_files(d),
+            |d| defrag(&parse_disk_to_files(d)),
+        );
     }
 }
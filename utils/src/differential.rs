@@ -0,0 +1,191 @@
+use crate::generate::Rng;
+use std::fmt::Debug;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// What a solver gave back, or what it panicked with
+pub type Outcome<R> = Result<R, String>;
+
+/// An input the two solvers disagree on, shrunk as far as it would go
+#[derive(Debug)]
+pub struct Mismatch<T, R> {
+    /// The seed of the generated case that first disagreed
+    pub seed: u64,
+    pub input: T,
+    pub reference: Outcome<R>,
+    pub optimized: Outcome<R>,
+    /// How many times a smaller input still disagreed
+    pub shrinks: usize,
+}
+
+/// Gives up shrinking after this many steps, in case a shrinker never bottoms out
+const MAX_SHRINKS: usize = 1000;
+
+fn run<T, R>(solver: &impl Fn(&T) -> R, input: &T) -> Outcome<R> {
+    catch_unwind(AssertUnwindSafe(|| solver(input))).map_err(|payload| {
+        payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panicked".to_string())
+    })
+}
+
+/// Runs `reference` and `optimized` on `cases` inputs from `generate` (seeded `0..cases`) and
+/// returns the first one they disagree on, if any. A panic counts as an answer, so a solver
+/// that blows up disagrees with one that doesn't. The input is then shrunk greedily: the
+/// first candidate from `shrink` that still disagrees replaces it, until none do.
+pub fn find_mismatch<T, R: PartialEq>(
+    cases: u64,
+    generate: impl Fn(&mut Rng) -> T,
+    shrink: impl Fn(&T) -> Vec<T>,
+    reference: impl Fn(&T) -> R,
+    optimized: impl Fn(&T) -> R,
+) -> Option<Mismatch<T, R>> {
+    let (seed, input, outcomes) = (0..cases).find_map(|seed| {
+        let input = generate(&mut Rng::new(seed));
+        let outcomes = (run(&reference, &input), run(&optimized, &input));
+        (outcomes.0 != outcomes.1).then_some((seed, input, outcomes))
+    })?;
+    let mut mismatch = Mismatch {
+        seed,
+        input,
+        reference: outcomes.0,
+        optimized: outcomes.1,
+        shrinks: 0,
+    };
+    while mismatch.shrinks < MAX_SHRINKS {
+        let smaller = shrink(&mismatch.input).into_iter().find_map(|candidate| {
+            let outcomes = (run(&reference, &candidate), run(&optimized, &candidate));
+            (outcomes.0 != outcomes.1).then_some((candidate, outcomes))
+        });
+        let Some((input, (reference, optimized))) = smaller else {
+            break;
+        };
+        mismatch.input = input;
+        mismatch.reference = reference;
+        mismatch.optimized = optimized;
+        mismatch.shrinks += 1;
+    }
+    Some(mismatch)
+}
+
+/// [`find_mismatch`] for tests: panics with the smallest counterexample it finds
+pub fn check<T: Debug, R: PartialEq + Debug>(
+    cases: u64,
+    generate: impl Fn(&mut Rng) -> T,
+    shrink: impl Fn(&T) -> Vec<T>,
+    reference: impl Fn(&T) -> R,
+    optimized: impl Fn(&T) -> R,
+) {
+    if let Some(m) = find_mismatch(cases, generate, shrink, reference, optimized) {
+        panic!(
+            "solvers disagree on case {} (shrunk {} times)\ninput: {:#?}\nreference: {:?}\noptimized: {:?}",
+            m.seed, m.shrinks, m.input, m.reference, m.optimized
+        );
+    }
+}
+
+/// Shorter versions of `items`: without each half, then each quarter, and so on down to
+/// without each single item. Bigger cuts come first so shrinking gets small quickly.
+pub fn shrink_vec<T: Clone>(items: &[T]) -> Vec<Vec<T>> {
+    let mut smaller = Vec::new();
+    let mut chunk = items.len() / 2;
+    while chunk > 0 {
+        for start in (0..items.len()).step_by(chunk) {
+            let end = (start + chunk).min(items.len());
+            smaller.push([&items[..start], &items[end..]].concat());
+        }
+        chunk /= 2;
+    }
+    if items.len() == 1 {
+        smaller.push(Vec::new());
+    }
+    smaller
+}
+
+/// Numbers closer to zero than `n`: zero itself, half way, and one step closer
+pub fn shrink_int(n: i64) -> Vec<i64> {
+    let mut smaller = vec![0, n / 2, n - n.signum()];
+    smaller.dedup();
+    smaller.retain(|&s| s != n);
+    smaller
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sum_small(items: &[i64]) -> i64 {
+        // Forgets anything over 50
+        items.iter().filter(|&&i| i <= 50).sum()
+    }
+
+    #[test]
+    fn test_shrinks_to_minimal() {
+        let generate = |rng: &mut Rng| (0..20).map(|_| rng.range(0..100)).collect::<Vec<_>>();
+        let shrink = |items: &Vec<i64>| {
+            let mut smaller = shrink_vec(items);
+            for (i, &item) in items.iter().enumerate() {
+                for s in shrink_int(item) {
+                    let mut fewer = items.clone();
+                    fewer[i] = s;
+                    smaller.push(fewer);
+                }
+            }
+            smaller
+        };
+        let mismatch =
+            find_mismatch(10, generate, shrink, |i| i.iter().sum(), |i| sum_small(i)).unwrap();
+        assert_eq!(mismatch.seed, 0);
+        assert_eq!(mismatch.input, vec![51]);
+        assert_eq!((mismatch.reference, mismatch.optimized), (Ok(51), Ok(0)));
+        assert!(mismatch.shrinks > 0);
+    }
+
+    #[test]
+    fn test_agreeing_solvers() {
+        let generate = |rng: &mut Rng| (0..20).map(|_| rng.range(0..50)).collect::<Vec<_>>();
+        let sum = |items: &Vec<i64>| items.iter().sum::<i64>();
+        assert!(find_mismatch(100, generate, |i| shrink_vec(i), sum, |i| sum_small(i)).is_none());
+        check(100, generate, |i| shrink_vec(i), sum, |i| sum_small(i));
+    }
+
+    #[test]
+    fn test_panics_disagree() {
+        let generate = |rng: &mut Rng| rng.range(-10..10);
+        let mismatch = find_mismatch(
+            100,
+            generate,
+            |&n| shrink_int(n),
+            |&n| n.abs(),
+            |&n| {
+                assert!(n >= 0, "negative");
+                n
+            },
+        )
+        .unwrap();
+        assert_eq!(mismatch.input, -1);
+        assert_eq!(mismatch.optimized, Err("negative".to_string()));
+    }
+
+    #[test]
+    fn test_shrinkers() {
+        assert_eq!(
+            shrink_vec(&[1, 2, 3, 4]),
+            vec![
+                vec![3, 4],
+                vec![1, 2],
+                vec![2, 3, 4],
+                vec![1, 3, 4],
+                vec![1, 2, 4],
+                vec![1, 2, 3]
+            ]
+        );
+        assert_eq!(shrink_vec(&[1]), vec![Vec::<i32>::new()]);
+        assert!(shrink_vec::<i32>(&[]).is_empty());
+        assert_eq!(shrink_int(10), vec![0, 5, 9]);
+        assert_eq!(shrink_int(-3), vec![0, -1, -2]);
+        assert_eq!(shrink_int(1), vec![0]);
+        assert!(shrink_int(0).is_empty());
+    }
+}
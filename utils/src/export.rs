@@ -0,0 +1,209 @@
+use std::fmt::Write;
+use std::fs;
+
+/// Ordered `(name, value)` pairs, so output is stable from run to run
+pub type Attributes = Vec<(String, String)>;
+
+/// A graph with string attributes on its nodes and edges that can be written out as
+/// Graphviz DOT or GraphML, for poking at puzzle structures in standard graph tools
+#[derive(Debug, Clone, Default)]
+pub struct GraphExport {
+    pub name: String,
+    pub directed: bool,
+    nodes: Vec<(String, Attributes)>,
+    edges: Vec<(String, String, Attributes)>,
+}
+
+fn to_attributes(attributes: &[(&str, &str)]) -> Attributes {
+    attributes
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl GraphExport {
+    pub fn new(name: &str, directed: bool) -> GraphExport {
+        GraphExport {
+            name: name.to_string(),
+            directed,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_node(&mut self, id: &str, attributes: &[(&str, &str)]) {
+        self.nodes.push((id.to_string(), to_attributes(attributes)));
+    }
+
+    pub fn add_edge(&mut self, from: &str, to: &str, attributes: &[(&str, &str)]) {
+        self.edges
+            .push((from.to_string(), to.to_string(), to_attributes(attributes)));
+    }
+
+    fn dot_attributes(attributes: &Attributes) -> String {
+        if attributes.is_empty() {
+            return String::new();
+        }
+        let inner = attributes
+            .iter()
+            .map(|(k, v)| format!("{}=\"{}\"", k, escape_dot(v)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(" [{}]", inner)
+    }
+
+    pub fn to_dot(&self) -> String {
+        let (kind, arrow) = if self.directed {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+        let mut out = format!("{} \"{}\" {{\n", kind, escape_dot(&self.name));
+        for (id, attributes) in &self.nodes {
+            let _ = writeln!(
+                out,
+                "  \"{}\"{};",
+                escape_dot(id),
+                Self::dot_attributes(attributes)
+            );
+        }
+        for (from, to, attributes) in &self.edges {
+            let _ = writeln!(
+                out,
+                "  \"{}\" {} \"{}\"{};",
+                escape_dot(from),
+                arrow,
+                escape_dot(to),
+                Self::dot_attributes(attributes)
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Every attribute name used, in first-seen order, for the GraphML `<key>` declarations
+    fn keys<'a>(attributes: impl Iterator<Item = &'a Attributes>) -> Vec<&'a str> {
+        let mut keys: Vec<&str> = Vec::new();
+        for (k, _) in attributes.flatten() {
+            if !keys.contains(&k.as_str()) {
+                keys.push(k);
+            }
+        }
+        keys
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let node_keys = Self::keys(self.nodes.iter().map(|(_, a)| a));
+        let edge_keys = Self::keys(self.edges.iter().map(|(_, _, a)| a));
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        );
+        for (kind, keys) in [("node", &node_keys), ("edge", &edge_keys)] {
+            for k in keys.iter() {
+                let _ = writeln!(
+                    out,
+                    "  <key id=\"{}_{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"string\"/>",
+                    kind,
+                    escape_xml(k),
+                    kind,
+                    escape_xml(k)
+                );
+            }
+        }
+        let _ = writeln!(
+            out,
+            "  <graph id=\"{}\" edgedefault=\"{}\">",
+            escape_xml(&self.name),
+            if self.directed {
+                "directed"
+            } else {
+                "undirected"
+            }
+        );
+        let data = |out: &mut String, kind: &str, attributes: &Attributes| {
+            for (k, v) in attributes {
+                let _ = writeln!(
+                    out,
+                    "      <data key=\"{}_{}\">{}</data>",
+                    kind,
+                    escape_xml(k),
+                    escape_xml(v)
+                );
+            }
+        };
+        for (id, attributes) in &self.nodes {
+            let _ = writeln!(out, "    <node id=\"{}\">", escape_xml(id));
+            data(&mut out, "node", attributes);
+            out.push_str("    </node>\n");
+        }
+        for (from, to, attributes) in &self.edges {
+            let _ = writeln!(
+                out,
+                "    <edge source=\"{}\" target=\"{}\">",
+                escape_xml(from),
+                escape_xml(to)
+            );
+            data(&mut out, "edge", attributes);
+            out.push_str("    </edge>\n");
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    pub fn write_dot(&self, filename: &str) -> std::io::Result<()> {
+        fs::write(filename, self.to_dot())
+    }
+
+    pub fn write_graphml(&self, filename: &str) -> std::io::Result<()> {
+        fs::write(filename, self.to_graphml())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> GraphExport {
+        let mut g = GraphExport::new("lan", false);
+        g.add_node("a", &[("color", "red")]);
+        g.add_node("b\"", &[]);
+        g.add_edge("a", "b\"", &[("weight", "1 < 2")]);
+        g
+    }
+
+    #[test]
+    fn test_dot() {
+        assert_eq!(
+            sample().to_dot(),
+            "graph \"lan\" {\n  \"a\" [color=\"red\"];\n  \"b\\\"\";\n  \"a\" -- \"b\\\"\" [weight=\"1 < 2\"];\n}\n"
+        );
+        let mut directed = sample();
+        directed.directed = true;
+        assert!(directed.to_dot().starts_with("digraph"));
+        assert!(directed.to_dot().contains("\"a\" -> \"b\\\"\""));
+    }
+
+    #[test]
+    fn test_graphml() {
+        let xml = sample().to_graphml();
+        assert!(xml.contains(
+            "<key id=\"node_color\" for=\"node\" attr.name=\"color\" attr.type=\"string\"/>"
+        ));
+        assert!(xml.contains("<graph id=\"lan\" edgedefault=\"undirected\">"));
+        assert!(xml.contains("<node id=\"b&quot;\">"));
+        assert!(xml.contains("<data key=\"edge_weight\">1 &lt; 2</data>"));
+        assert_eq!(xml.matches("<node ").count(), 2);
+        assert_eq!(xml.matches("<edge ").count(), 1);
+    }
+}
@@ -0,0 +1,128 @@
+use std::ops::Range;
+
+/// A small seeded random number generator (SplitMix64), so a generated input can be
+/// recreated from its seed on any machine
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Anything in `0..n`. The modulo bias is far too small to matter for puzzle inputs.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "can't pick from an empty range");
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn range(&mut self, range: Range<i64>) -> i64 {
+        assert!(range.start < range.end, "can't pick from an empty range");
+        let span = range.end.abs_diff(range.start);
+        range.start.wrapping_add((self.next_u64() % span) as i64)
+    }
+
+    /// True with probability `p`
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// A maze with exactly one route between any two open cells, as rows of `true` for open
+/// and `false` for wall. Open cells sit on odd coordinates inside a solid border, so the
+/// size is rounded up to odd and at least 3.
+pub fn maze(rng: &mut Rng, width: usize, height: usize) -> Vec<Vec<bool>> {
+    let (width, height) = (width.max(3) | 1, height.max(3) | 1);
+    let mut open = vec![vec![false; width]; height];
+    // Depth first with a stack: knock through to a random unvisited neighbour, or back up
+    // when there isn't one
+    let mut stack = vec![(1, 1)];
+    open[1][1] = true;
+    while let Some(&(row, col)) = stack.last() {
+        let mut next = [(0, 2), (2, 0), (0, -2), (-2, 0)]
+            .into_iter()
+            .map(|(dr, dc)| (row as isize + dr, col as isize + dc))
+            .filter(|&(r, c)| r > 0 && c > 0 && r < height as isize && c < width as isize)
+            .map(|(r, c)| (r as usize, c as usize))
+            .filter(|&(r, c)| !open[r][c])
+            .collect::<Vec<_>>();
+        if next.is_empty() {
+            stack.pop();
+            continue;
+        }
+        rng.shuffle(&mut next);
+        let (r, c) = next[0];
+        open[(row + r) / 2][(col + c) / 2] = true;
+        open[r][c] = true;
+        stack.push((r, c));
+    }
+    open
+}
+
+/// Handles `day-N generate <seed> [size]` by printing whatever `generator` makes of it.
+/// Returns whether that was the command, so main knows to stop.
+pub fn command(default_size: usize, generator: impl Fn(&mut Rng, usize) -> String) -> bool {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(|a| a.as_str()) != Some("generate") {
+        return false;
+    }
+    let seed = args
+        .get(2)
+        .map_or(0, |s| s.parse().expect("seed should be a number"));
+    let size = args.get(3).map_or(default_size, |s| {
+        s.parse().expect("size should be a number")
+    });
+    print!("{}", generator(&mut Rng::new(seed), size));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+        assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+        assert!((0..1000).all(|_| a.below(6) < 6));
+        assert!((0..1000).all(|_| (-3..4).contains(&a.range(-3..4))));
+        let mut items = (0..20).collect::<Vec<_>>();
+        a.shuffle(&mut items);
+        items.sort();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_maze() {
+        let mut rng = Rng::new(2024);
+        let maze = maze(&mut rng, 20, 11);
+        assert_eq!((maze.len(), maze[0].len()), (11, 21));
+        assert!(maze[0].iter().chain(&maze[10]).all(|&c| !c));
+        // Every cell is reachable and there are no loops, so open cells make a tree:
+        // one less passage than cells
+        let cells = (maze.len() / 2) * (maze[0].len() / 2);
+        let open = maze.iter().flatten().filter(|&&c| c).count();
+        assert_eq!(open, cells + cells - 1);
+    }
+}
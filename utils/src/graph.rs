@@ -0,0 +1,658 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Hands out a dense id for each distinct value, in the order they first show up, so
+/// graphs can be built over plain indexes instead of names
+#[derive(Debug, Clone)]
+pub struct Interner<T = String> {
+    values: Vec<T>,
+    index: HashMap<T, usize>,
+}
+
+impl<T> Default for Interner<T> {
+    fn default() -> Self {
+        Interner {
+            values: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> Interner<T> {
+    pub fn new() -> Interner<T> {
+        Interner::default()
+    }
+
+    /// The id of `value`, giving it the next one if it hasn't been seen before
+    pub fn intern<Q>(&mut self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = T> + ?Sized,
+    {
+        if let Some(&id) = self.index.get(value) {
+            return id;
+        }
+        self.values.push(value.to_owned());
+        self.index.insert(value.to_owned(), self.values.len() - 1);
+        self.values.len() - 1
+    }
+
+    pub fn id<Q>(&self, value: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.get(value).copied()
+    }
+
+    pub fn resolve(&self, id: usize) -> &T {
+        &self.values[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// A fixed size set of node indexes, one bit per node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new(size: usize) -> BitSet {
+        BitSet {
+            words: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    pub fn full(size: usize) -> BitSet {
+        let mut set = BitSet::new(size);
+        (0..size).for_each(|i| set.insert(i));
+        set
+    }
+
+    pub fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn remove(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        self.words[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn intersection(&self, other: &BitSet) -> BitSet {
+        BitSet {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a & b)
+                .collect(),
+        }
+    }
+
+    pub fn union(&self, other: &BitSet) -> BitSet {
+        BitSet {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a | b)
+                .collect(),
+        }
+    }
+
+    pub fn difference(&self, other: &BitSet) -> BitSet {
+        BitSet {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a & !b)
+                .collect(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+/// A graph over the nodes `0..len` stored as adjacency lists. Undirected edges are kept
+/// in both nodes' lists. Names, if there are any, live in an [`Interner`] alongside.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    directed: bool,
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    pub fn new(len: usize, directed: bool) -> Graph {
+        Graph {
+            directed,
+            adjacency: vec![Vec::new(); len],
+        }
+    }
+
+    /// A graph of every name that shows up in `edges`, and the interner that numbers them
+    pub fn from_edges<S: AsRef<str>>(edges: &[(S, S)], directed: bool) -> (Graph, Interner) {
+        let mut names = Interner::new();
+        let ids = edges
+            .iter()
+            .map(|(a, b)| (names.intern(a.as_ref()), names.intern(b.as_ref())))
+            .collect::<Vec<_>>();
+        let mut graph = Graph::new(names.len(), directed);
+        ids.into_iter().for_each(|(a, b)| graph.add_edge(a, b));
+        (graph, names)
+    }
+
+    pub fn add_node(&mut self) -> usize {
+        self.adjacency.push(Vec::new());
+        self.adjacency.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        self.adjacency[from].push(to);
+        if !self.directed && from != to {
+            self.adjacency[to].push(from);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.adjacency.is_empty()
+    }
+
+    pub fn neighbors(&self, node: usize) -> &[usize] {
+        &self.adjacency[node]
+    }
+
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.adjacency[from].contains(&to)
+    }
+
+    /// Every edge once. Undirected ones come out as `(a, b)` with `a <= b`.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.adjacency.iter().enumerate().flat_map(move |(a, n)| {
+            n.iter()
+                .filter(move |&&b| self.directed || a <= b)
+                .map(move |&b| (a, b))
+        })
+    }
+
+    /// Every node with each one before all the nodes its edges lead to, or if there's no
+    /// such order, one cycle as the nodes along it in edge order
+    pub fn toposort(&self) -> Result<Vec<usize>, Vec<usize>> {
+        assert!(
+            self.directed,
+            "only a directed graph has a topological order"
+        );
+        // Kahn's algorithm: a node is ready once everything leading into it is placed
+        let mut waiting = vec![0; self.len()];
+        self.adjacency
+            .iter()
+            .flatten()
+            .for_each(|&b| waiting[b] += 1);
+        let mut ready = (0..self.len())
+            .filter(|&n| waiting[n] == 0)
+            .collect::<VecDeque<_>>();
+        let mut order = Vec::with_capacity(self.len());
+        while let Some(n) = ready.pop_front() {
+            order.push(n);
+            for &next in &self.adjacency[n] {
+                waiting[next] -= 1;
+                if waiting[next] == 0 {
+                    ready.push_back(next);
+                }
+            }
+        }
+        if order.len() == self.len() {
+            return Ok(order);
+        }
+        // Everything left over is on or behind a cycle, so has a left over node leading
+        // into it. Walk backwards through those until we come round to one we've seen.
+        let mut predecessors = vec![Vec::new(); self.len()];
+        for (a, next) in self.adjacency.iter().enumerate() {
+            if waiting[a] == 0 {
+                continue;
+            }
+            for &b in next {
+                if waiting[b] > 0 {
+                    predecessors[b].push(a);
+                }
+            }
+        }
+        let mut path: Vec<usize> = Vec::new();
+        let mut n = (0..self.len()).find(|&n| waiting[n] > 0).unwrap();
+        while !path.contains(&n) {
+            path.push(n);
+            n = predecessors[n]
+                .iter()
+                .copied()
+                .find(|&p| waiting[p] > 0)
+                .unwrap();
+        }
+        let start = path.iter().position(|&p| p == n).unwrap();
+        Err(path[start..].iter().rev().copied().collect())
+    }
+
+    /// Tarjan's algorithm, with an explicit stack so long chains don't overflow ours. Each
+    /// component comes out after every component it has edges into.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        const UNSEEN: usize = usize::MAX;
+        let mut index = vec![UNSEEN; self.len()];
+        let mut low = vec![0; self.len()];
+        let mut on_stack = vec![false; self.len()];
+        let mut stack: Vec<usize> = Vec::new();
+        let mut components: Vec<Vec<usize>> = Vec::new();
+        let mut next_index = 0;
+        for root in 0..self.len() {
+            if index[root] != UNSEEN {
+                continue;
+            }
+            // Each entry is a node and how far through its neighbours we've got
+            let mut work = vec![(root, 0)];
+            while let Some((n, i)) = work.pop() {
+                if i == 0 {
+                    (index[n], low[n]) = (next_index, next_index);
+                    next_index += 1;
+                    stack.push(n);
+                    on_stack[n] = true;
+                }
+                if let Some(&next) = self.adjacency[n].get(i) {
+                    work.push((n, i + 1));
+                    if index[next] == UNSEEN {
+                        work.push((next, 0));
+                    } else if on_stack[next] {
+                        low[n] = low[n].min(index[next]);
+                    }
+                    continue;
+                }
+                if let Some(&(parent, _)) = work.last() {
+                    low[parent] = low[parent].min(low[n]);
+                }
+                if low[n] == index[n] {
+                    let mut component = Vec::new();
+                    while let Some(m) = stack.pop() {
+                        on_stack[m] = false;
+                        component.push(m);
+                        if m == n {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Every node there's a path to from `from`, including itself
+    pub fn reachable(&self, from: usize) -> BitSet {
+        let mut seen = BitSet::new(self.len());
+        seen.insert(from);
+        let mut to_visit = vec![from];
+        while let Some(n) = to_visit.pop() {
+            for &next in &self.adjacency[n] {
+                if !seen.contains(next) {
+                    seen.insert(next);
+                    to_visit.push(next);
+                }
+            }
+        }
+        seen
+    }
+
+    pub fn is_clique(&self, nodes: &[usize]) -> bool {
+        nodes.iter().enumerate().all(|(i, &a)| {
+            nodes[i + 1..]
+                .iter()
+                .all(|&b| self.has_edge(a, b) && self.has_edge(b, a))
+        })
+    }
+
+    /// Neighbours as one bitset per node, which is what the clique searches work on
+    fn bit_adjacency(&self) -> Vec<BitSet> {
+        self.adjacency
+            .iter()
+            .map(|neighbors| {
+                let mut set = BitSet::new(self.len());
+                neighbors.iter().for_each(|&n| set.insert(n));
+                set
+            })
+            .collect()
+    }
+
+    /// The biggest clique, by Bron–Kerbosch with Tomita pivoting. At each step we only
+    /// branch on candidates that aren't neighbours of the pivot, picking the pivot that
+    /// rules out the most of them.
+    pub fn max_clique(&self) -> Vec<usize> {
+        assert!(!self.directed, "cliques are for undirected graphs");
+        let mut best: Vec<usize> = Vec::new();
+        let mut r: Vec<usize> = Vec::new();
+        bron_kerbosch(
+            &self.bit_adjacency(),
+            &mut r,
+            BitSet::full(self.len()),
+            BitSet::new(self.len()),
+            &mut best,
+        );
+        best
+    }
+
+    /// Every clique of exactly `k` nodes that `keep` accepts, each listed once with its
+    /// nodes in ascending order
+    pub fn k_cliques(&self, k: usize, keep: impl Fn(&[usize]) -> bool) -> Vec<Vec<usize>> {
+        assert!(!self.directed, "cliques are for undirected graphs");
+        let mut found: Vec<Vec<usize>> = Vec::new();
+        let mut clique: Vec<usize> = Vec::new();
+        extend_clique(
+            &self.bit_adjacency(),
+            k,
+            &mut clique,
+            BitSet::full(self.len()),
+            &keep,
+            &mut found,
+        );
+        found
+    }
+}
+
+fn bron_kerbosch(
+    adjacency: &[BitSet],
+    r: &mut Vec<usize>,
+    p: BitSet,
+    x: BitSet,
+    best: &mut Vec<usize>,
+) {
+    if p.is_empty() {
+        if x.is_empty() && r.len() > best.len() {
+            *best = r.clone();
+        }
+        return;
+    }
+    // Can't beat what we've got even if every candidate joins
+    if r.len() + p.len() <= best.len() {
+        return;
+    }
+    let pivot = p
+        .union(&x)
+        .iter()
+        .max_by_key(|&u| p.intersection(&adjacency[u]).len())
+        .unwrap();
+    let (mut p, mut x) = (p, x);
+    for v in p.difference(&adjacency[pivot]).iter().collect::<Vec<_>>() {
+        r.push(v);
+        bron_kerbosch(
+            adjacency,
+            r,
+            p.intersection(&adjacency[v]),
+            x.intersection(&adjacency[v]),
+            best,
+        );
+        r.pop();
+        p.remove(v);
+        x.insert(v);
+    }
+}
+
+fn extend_clique(
+    adjacency: &[BitSet],
+    k: usize,
+    clique: &mut Vec<usize>,
+    candidates: BitSet,
+    keep: &impl Fn(&[usize]) -> bool,
+    found: &mut Vec<Vec<usize>>,
+) {
+    if clique.len() == k {
+        if keep(clique) {
+            found.push(clique.clone());
+        }
+        return;
+    }
+    for v in candidates.iter() {
+        // Only look at higher numbered nodes from here so each clique comes up once
+        let mut next = candidates.intersection(&adjacency[v]);
+        (0..=v).for_each(|u| next.remove(u));
+        if clique.len() + 1 + next.len() < k {
+            continue;
+        }
+        clique.push(v);
+        extend_clique(adjacency, k, clique, next, keep, found);
+        clique.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::differential::{check, shrink_vec};
+    use crate::generate::Rng;
+    use itertools::Itertools;
+
+    /// A directed graph on 8 nodes as its edge list, sparse enough to often have no cycle
+    fn random_edges(rng: &mut Rng) -> Vec<(usize, usize)> {
+        let chance = [0.05, 0.1, 0.2][rng.below(3)];
+        (0..8)
+            .cartesian_product(0..8)
+            .filter(|_| rng.chance(chance))
+            .collect()
+    }
+
+    fn build(edges: &[(usize, usize)], directed: bool) -> Graph {
+        let mut graph = Graph::new(8, directed);
+        edges.iter().for_each(|&(a, b)| graph.add_edge(a, b));
+        graph
+    }
+
+    /// Whether there's a path from `a` to `b`, by repeatedly adding one more step
+    fn brute_reachable(edges: &[(usize, usize)], a: usize, b: usize) -> bool {
+        let mut reached = vec![a];
+        loop {
+            let more = edges
+                .iter()
+                .filter(|(from, to)| reached.contains(from) && !reached.contains(to))
+                .map(|&(_, to)| to)
+                .collect_vec();
+            if more.is_empty() {
+                return reached.contains(&b);
+            }
+            reached.extend(more);
+        }
+    }
+
+    #[test]
+    fn test_interner() {
+        let mut names: Interner = Interner::new();
+        assert_eq!(names.intern("kh"), 0);
+        assert_eq!(names.intern("tc"), 1);
+        assert_eq!(names.intern("kh"), 0);
+        assert_eq!((names.id("tc"), names.id("qp")), (Some(1), None));
+        assert_eq!(names.resolve(1), "tc");
+        let mut pages: Interner<u32> = Interner::new();
+        assert_eq!((pages.intern(&75), pages.intern(&47)), (0, 1));
+        assert_eq!(pages.len(), 2);
+    }
+
+    #[test]
+    fn test_from_edges() {
+        let (graph, names) = Graph::from_edges(&[("a", "b"), ("b", "c"), ("c", "a")], false);
+        assert_eq!((graph.len(), names.len()), (3, 3));
+        assert_eq!(graph.neighbors(names.id("a").unwrap()), [1, 2]);
+        assert_eq!(graph.edges().collect_vec(), [(0, 1), (0, 2), (1, 2)]);
+        assert_eq!(
+            graph.max_clique().into_iter().sorted().collect_vec(),
+            [0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn test_reachable() {
+        check(
+            200,
+            random_edges,
+            |edges| shrink_vec(edges),
+            |edges| {
+                (0..8)
+                    .map(|a| {
+                        (0..8)
+                            .filter(|&b| brute_reachable(edges, a, b))
+                            .collect_vec()
+                    })
+                    .collect_vec()
+            },
+            |edges| {
+                let graph = build(edges, true);
+                (0..8)
+                    .map(|a| graph.reachable(a).iter().collect_vec())
+                    .collect_vec()
+            },
+        );
+    }
+
+    #[test]
+    fn test_toposort() {
+        check(
+            300,
+            random_edges,
+            |edges| shrink_vec(edges),
+            // There's an order exactly when no node can get back to itself
+            |edges| {
+                (0..8).all(|n| {
+                    edges
+                        .iter()
+                        .all(|&(a, b)| a != n || !brute_reachable(edges, b, n))
+                })
+            },
+            |edges| match build(edges, true).toposort() {
+                Ok(order) => {
+                    assert_eq!(
+                        order.iter().copied().sorted().collect_vec(),
+                        (0..8).collect_vec()
+                    );
+                    let position = |n| order.iter().position(|&o| o == n).unwrap();
+                    assert!(edges.iter().all(|&(a, b)| position(a) < position(b)));
+                    true
+                }
+                Err(cycle) => {
+                    assert!(cycle.iter().all_unique());
+                    let next = cycle.iter().cycle().skip(1);
+                    assert!(cycle
+                        .iter()
+                        .zip(next)
+                        .all(|(a, b)| edges.contains(&(*a, *b))));
+                    false
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        check(
+            300,
+            random_edges,
+            |edges| shrink_vec(edges),
+            // Two nodes are in the same component when each can reach the other
+            |edges| {
+                (0..8)
+                    .map(|a| {
+                        (0..8)
+                            .filter(|&b| {
+                                brute_reachable(edges, a, b) && brute_reachable(edges, b, a)
+                            })
+                            .collect_vec()
+                    })
+                    .collect_vec()
+            },
+            |edges| {
+                let components = build(edges, true).strongly_connected_components();
+                // And come out sinks first
+                for (i, later) in components.iter().enumerate() {
+                    for earlier in &components[..i] {
+                        assert!(!edges
+                            .iter()
+                            .any(|&(a, b)| earlier.contains(&a) && later.contains(&b)));
+                    }
+                }
+                (0..8)
+                    .map(|a| {
+                        let component = components.iter().find(|c| c.contains(&a)).unwrap();
+                        component.iter().copied().sorted().collect_vec()
+                    })
+                    .collect_vec()
+            },
+        );
+        // A long chain would be too deep to recurse through
+        let mut chain = Graph::new(100_000, true);
+        (1..chain.len()).for_each(|n| chain.add_edge(n - 1, n));
+        chain.add_edge(chain.len() - 1, 0);
+        assert_eq!(chain.strongly_connected_components().len(), 1);
+    }
+
+    #[test]
+    fn test_cliques() {
+        // Undirected this time, so only the pairs with a < b matter
+        let generate = |rng: &mut Rng| {
+            (0..8)
+                .tuple_combinations()
+                .filter(|_| rng.below(3) != 0)
+                .collect_vec()
+        };
+        let all_cliques = |edges: &Vec<(usize, usize)>| {
+            let graph = build(edges, false);
+            (0..1u32 << 8)
+                .map(|mask| (0..8).filter(|i| mask >> i & 1 == 1).collect_vec())
+                .filter(|nodes| graph.is_clique(nodes))
+                .collect_vec()
+        };
+        check(
+            100,
+            generate,
+            |edges| shrink_vec(edges),
+            |edges| {
+                let cliques = all_cliques(edges);
+                let biggest = cliques.iter().map(|c| c.len()).max().unwrap();
+                let triangles = cliques.iter().filter(|c| c.len() == 3).count();
+                (biggest, triangles)
+            },
+            |edges| {
+                let graph = build(edges, false);
+                let clique = graph.max_clique();
+                assert!(graph.is_clique(&clique));
+                (clique.len(), graph.k_cliques(3, |_| true).len())
+            },
+        );
+    }
+}
@@ -1,4 +1,13 @@
+pub mod differential;
+pub mod export;
+pub mod generate;
+pub mod graph;
+pub mod math;
+pub mod parse;
 mod pathfinding;
+pub mod render;
+pub mod test_support;
+pub mod viz;
 
 pub use pathfinding::*;
 
@@ -46,7 +55,7 @@ impl<T: Eq + Hash> Array2DTools<T> for Array2D<T> {
                     let c = col as i32 + x;
                     if c >= 0 && r >= 0 {
                         if let Some(t) = self.get(r as usize, c as usize) {
-                            n.push(((r as usize, c as usize), &t));
+                            n.push(((r as usize, c as usize), t));
                         }
                     }
                 }
@@ -62,7 +71,7 @@ impl<T: Eq + Hash> Array2DTools<T> for Array2D<T> {
             let c = col as i32 + x;
             if c >= 0 && r >= 0 {
                 if let Some(t) = self.get(r as usize, c as usize) {
-                    n.push(((r as usize, c as usize), &t));
+                    n.push(((r as usize, c as usize), t));
                 }
             }
         }
@@ -0,0 +1,521 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Greatest common divisor, never negative. `gcd(0, 0)` is 0.
+pub fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    i64::try_from(a).expect("gcd of i64::MIN with 0 doesn't fit in an i64")
+}
+
+/// Least common multiple, never negative, or `None` if it doesn't fit in an i64. Anything
+/// with 0 is 0.
+pub fn lcm(a: i64, b: i64) -> Option<i64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b).map(|l| l.abs())
+}
+
+/// `(g, x, y)` where `g` is `gcd(a, b)` and `a * x + b * y == g`
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (g, x, y) = extended_gcd_wide(a as i128, b as i128);
+    let narrow = |n: i128| i64::try_from(n).expect("gcd of i64::MIN with 0 doesn't fit in an i64");
+    (narrow(g), narrow(x), narrow(y))
+}
+
+fn extended_gcd_wide(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// The `x` in `0..m` with `a * x ≡ 1 (mod m)`, if `a` and `m` are coprime
+pub fn mod_inverse(a: i64, m: i64) -> Option<i64> {
+    assert!(m > 0, "modulus has to be positive");
+    let (g, x, _) = extended_gcd_wide((a as i128).rem_euclid(m as i128), m as i128);
+    (g == 1).then(|| x.rem_euclid(m as i128) as i64)
+}
+
+/// `a * b mod m`, in `0..m`, without overflowing on the way
+pub fn mul_mod(a: i64, b: i64, m: i64) -> i64 {
+    assert!(m > 0, "modulus has to be positive");
+    (a as i128 * b as i128).rem_euclid(m as i128) as i64
+}
+
+/// `base ^ exp mod m`, in `0..m`, by repeated squaring
+pub fn pow_mod(base: i64, mut exp: u64, m: i64) -> i64 {
+    let (mut base, mut result) = (base.rem_euclid(m), 1 % m);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Solves `x ≡ r (mod m)` for every `(r, m)` in `congruences` at once, returning `(x, l)`
+/// where `l` is the lcm of the moduli, `x` is in `0..l`, and every solution is `x` plus a
+/// multiple of `l`. The moduli don't need to be coprime, so there might be no solution at
+/// all, and then this is `None`. It's also `None` if `l` doesn't fit in an i64.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    let (mut x, mut l) = (0i128, 1i128);
+    for &(r, m) in congruences {
+        assert!(m > 0, "modulus has to be positive");
+        let (r, m) = ((r as i128).rem_euclid(m as i128), m as i128);
+        // x + l * k ≡ r (mod m) has a k only when gcd(l, m) divides the gap
+        let (g, p, _) = extended_gcd_wide(l, m);
+        if (r - x) % g != 0 {
+            return None;
+        }
+        let step = m / g;
+        let k = ((r - x) / g % step * p).rem_euclid(step);
+        x += l * k;
+        l = i64::try_from(l * step).ok()? as i128;
+        x = x.rem_euclid(l);
+    }
+    Some((x as i64, l as i64))
+}
+
+/// `a * b + c`, or `None` if any step overflows
+pub fn checked_mul_add(a: i128, b: i128, c: i128) -> Option<i128> {
+    a.checked_mul(b)?.checked_add(c)
+}
+
+/// The sum of the pairwise products of `a` and `b`, or `None` if any step overflows
+pub fn checked_dot(a: &[i128], b: &[i128]) -> Option<i128> {
+    a.iter()
+        .zip(b)
+        .try_fold(0i128, |sum, (&x, &y)| checked_mul_add(x, y, sum))
+}
+
+/// An exact fraction, always in lowest terms with a positive denominator. The arithmetic
+/// operators panic on overflow; the `checked_` methods give `None` instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { num: 0, den: 1 };
+    pub const ONE: Rational = Rational { num: 1, den: 1 };
+
+    pub fn new(num: i128, den: i128) -> Rational {
+        assert!(den != 0, "zero denominator");
+        let g = gcd_wide(num, den);
+        let sign = den.signum();
+        Rational {
+            num: sign * num / g,
+            den: sign * den / g,
+        }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.den
+    }
+
+    /// The value as a whole number, if it is one
+    pub fn to_integer(&self) -> Option<i128> {
+        (self.den == 1).then_some(self.num)
+    }
+
+    pub fn checked_add(self, other: Rational) -> Option<Rational> {
+        let num = checked_mul_add(self.num, other.den, other.num.checked_mul(self.den)?)?;
+        Some(Rational::new(num, self.den.checked_mul(other.den)?))
+    }
+
+    pub fn checked_sub(self, other: Rational) -> Option<Rational> {
+        self.checked_add(-other)
+    }
+
+    pub fn checked_mul(self, other: Rational) -> Option<Rational> {
+        // Cancelling across first keeps the products as small as they can be
+        let (g1, g2) = (gcd_wide(self.num, other.den), gcd_wide(other.num, self.den));
+        Some(Rational::new(
+            (self.num / g1).checked_mul(other.num / g2)?,
+            (self.den / g2).checked_mul(other.den / g1)?,
+        ))
+    }
+
+    pub fn checked_div(self, other: Rational) -> Option<Rational> {
+        assert!(other.num != 0, "division by zero");
+        self.checked_mul(Rational::new(other.den, other.num))
+    }
+}
+
+fn gcd_wide(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    // Only 0 when both are, and then dividing by 1 leaves them alone
+    a.max(1) as i128
+}
+
+impl From<i64> for Rational {
+    fn from(n: i64) -> Self {
+        Rational::new(n as i128, 1)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.den {
+            1 => write!(f, "{}", self.num),
+            den => write!(f, "{}/{}", self.num, den),
+        }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_fractions((self.num, self.den), (other.num, other.den))
+    }
+}
+
+/// Compares a/b with c/d for positive b and d. When cross-multiplying overflows, the whole
+/// parts decide it if they differ; otherwise the remainders are compared by flipping them
+/// over, which shrinks the numbers each time round like Euclid's algorithm.
+fn cmp_fractions((a, b): (i128, i128), (c, d): (i128, i128)) -> Ordering {
+    if let (Some(left), Some(right)) = (a.checked_mul(d), c.checked_mul(b)) {
+        return left.cmp(&right);
+    }
+    let (whole_a, whole_c) = (a.div_euclid(b), c.div_euclid(d));
+    if whole_a != whole_c {
+        return whole_a.cmp(&whole_c);
+    }
+    match (a.rem_euclid(b), c.rem_euclid(d)) {
+        (0, rem_c) => 0.cmp(&rem_c),
+        (rem_a, 0) => rem_a.cmp(&0),
+        // rem_a/b < rem_c/d exactly when b/rem_a > d/rem_c
+        (rem_a, rem_c) => cmp_fractions((d, rem_c), (b, rem_a)),
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational::new(-self.num, self.den)
+    }
+}
+
+macro_rules! rational_op {
+    ($trait:ident, $method:ident, $checked:ident) => {
+        impl $trait for Rational {
+            type Output = Rational;
+
+            fn $method(self, other: Rational) -> Rational {
+                self.$checked(other).expect("rational overflow")
+            }
+        }
+    };
+}
+
+rational_op!(Add, add, checked_add);
+rational_op!(Sub, sub, checked_sub);
+rational_op!(Mul, mul, checked_mul);
+rational_op!(Div, div, checked_div);
+
+/// Solves the square system `matrix * x == rhs` exactly with Gaussian elimination, or
+/// `None` if it doesn't have exactly one solution
+pub fn solve_linear(matrix: &[Vec<i64>], rhs: &[i64]) -> Option<Vec<Rational>> {
+    let n = rhs.len();
+    assert!(
+        matrix.len() == n && matrix.iter().all(|row| row.len() == n),
+        "system has to be square"
+    );
+    let mut rows = matrix
+        .iter()
+        .zip(rhs)
+        .map(|(row, &b)| {
+            row.iter()
+                .chain([&b])
+                .map(|&v| Rational::from(v))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for col in 0..n {
+        let pivot = (col..n).find(|&r| rows[r][col] != Rational::ZERO)?;
+        rows.swap(col, pivot);
+        let pivot_row = rows[col].clone();
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r != col && row[col] != Rational::ZERO {
+                let factor = row[col] / pivot_row[col];
+                for (cell, &p) in row.iter_mut().zip(&pivot_row).skip(col) {
+                    *cell = *cell - factor * p;
+                }
+            }
+        }
+    }
+    Some((0..n).map(|r| rows[r][n] / rows[r][r]).collect())
+}
+
+/// [`solve_linear`], but only when every unknown comes out a whole number
+pub fn solve_integer(matrix: &[Vec<i64>], rhs: &[i64]) -> Option<Vec<i64>> {
+    solve_linear(matrix, rhs)?
+        .iter()
+        .map(|x| x.to_integer().and_then(|n| i64::try_from(n).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::differential::{check, shrink_int};
+    use crate::generate::Rng;
+    use itertools::Itertools;
+
+    /// Shrinks each of a pair of numbers towards zero in turn
+    fn shrink_pair(&(a, b): &(i64, i64)) -> Vec<(i64, i64)> {
+        let firsts = shrink_int(a).into_iter().map(|a| (a, b));
+        firsts
+            .chain(shrink_int(b).into_iter().map(|b| (a, b)))
+            .collect()
+    }
+
+    #[test]
+    fn test_gcd_lcm() {
+        check(
+            500,
+            |rng| (rng.range(-60..61), rng.range(-60..61)),
+            shrink_pair,
+            |&(a, b)| {
+                let divisor = (1..=60).rev().find(|d| a % d == 0 && b % d == 0);
+                match (a, b) {
+                    (0, 0) => (0, 0),
+                    (0, _) | (_, 0) => (divisor.unwrap(), 0),
+                    _ => {
+                        let multiple = (1..=3600).find(|m| m % a == 0 && m % b == 0);
+                        (divisor.unwrap(), multiple.unwrap())
+                    }
+                }
+            },
+            |&(a, b)| (gcd(a, b), lcm(a, b).unwrap()),
+        );
+        assert_eq!(gcd(i64::MAX, i64::MIN + 1), i64::MAX);
+        assert_eq!(lcm(i64::MAX, i64::MAX - 1), None);
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let mut rng = Rng::new(48);
+        for _ in 0..1000 {
+            let (a, b) = (rng.range(-1 << 40..1 << 40), rng.range(-1 << 40..1 << 40));
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a as i128 * x as i128 + b as i128 * y as i128, g as i128);
+        }
+        assert_eq!(extended_gcd(0, 0), (0, 1, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit in an i64")]
+    fn test_extended_gcd_overflow() {
+        extended_gcd(i64::MIN, 0);
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        check(
+            500,
+            |rng| (rng.range(-100..100), rng.range(1..60)),
+            |&(a, m)| {
+                shrink_pair(&(a, m))
+                    .into_iter()
+                    .filter(|&(_, m)| m > 0)
+                    .collect()
+            },
+            |&(a, m)| (0..m).find(|x| (a * x).rem_euclid(m) == 1 % m),
+            |&(a, m)| mod_inverse(a, m),
+        );
+        // Too big to multiply out directly
+        let p = 1_000_000_007;
+        assert_eq!(
+            mul_mod(mod_inverse(123_456_789, p).unwrap(), 123_456_789, p),
+            1
+        );
+    }
+
+    #[test]
+    fn test_pow_mod() {
+        check(
+            500,
+            |rng| (rng.range(-20..20), rng.range(0..30), rng.range(1..50)),
+            |_| Vec::new(),
+            |&(b, e, m)| (0..e).fold(1 % m, |acc, _| (acc * b).rem_euclid(m)),
+            |&(b, e, m)| pow_mod(b, e as u64, m),
+        );
+        // Fermat: a^(p-1) ≡ 1 for a prime p
+        let p = 998_244_353;
+        assert_eq!(pow_mod(i64::MAX, p as u64 - 1, p), 1);
+    }
+
+    #[test]
+    fn test_crt() {
+        check(
+            500,
+            |rng| {
+                (0..rng.below(4))
+                    .map(|_| (rng.range(-50..50), rng.range(1..16)))
+                    .collect_vec()
+            },
+            |congruences: &Vec<(i64, i64)>| crate::differential::shrink_vec(congruences),
+            |congruences| {
+                let l = congruences.iter().fold(1, |l, &(_, m)| lcm(l, m).unwrap());
+                (0..l)
+                    .find(|x| congruences.iter().all(|&(r, m)| (x - r) % m == 0))
+                    .map(|x| (x, l))
+            },
+            |congruences| crt(congruences),
+        );
+        // The day 13 style of sizes, where the lcm is far beyond searching
+        let (x, l) = crt(&[(3, 1_000_000_007), (5, 998_244_353)]).unwrap();
+        assert_eq!((x % 1_000_000_007, x % 998_244_353), (3, 5));
+        assert_eq!(l, 1_000_000_007 * 998_244_353);
+        assert_eq!(crt(&[(0, 1 << 40), (1, (1 << 40) - 1)]), None);
+    }
+
+    #[test]
+    fn test_rational() {
+        let half = Rational::new(2, 4);
+        assert_eq!((half.numerator(), half.denominator()), (1, 2));
+        assert_eq!(Rational::new(3, -6), -half);
+        assert_eq!(half + Rational::new(1, 3), Rational::new(5, 6));
+        assert_eq!(half - Rational::ONE, Rational::new(-1, 2));
+        assert_eq!(half * Rational::from(4), Rational::from(2));
+        assert_eq!((half / Rational::new(1, 6)).to_integer(), Some(3));
+        assert_eq!(half.to_integer(), None);
+        assert!(Rational::new(-1, 2) < Rational::ZERO);
+        assert_eq!(Rational::new(-7, 3).to_string(), "-7/3");
+        assert_eq!(Rational::new(0, -3).to_string(), "0");
+        let huge = Rational::new(i128::MAX, 1);
+        assert_eq!(huge.checked_add(huge), None);
+        assert_eq!(
+            huge.checked_mul(Rational::new(1, i128::MAX)),
+            Some(Rational::ONE)
+        );
+        // Too big to cross-multiply
+        let big = i128::MAX / 2;
+        let (a, b) = (Rational::new(big, big - 1), Rational::new(big - 1, big - 2));
+        assert!(a < b && -a > -b);
+        assert!(Rational::new(big, 3) > Rational::new(big - 1, 3));
+        assert_eq!(a.cmp(&a), Ordering::Equal);
+        assert_eq!(checked_dot(&[1, 2, 3], &[4, 5, 6]), Some(32));
+        assert_eq!(checked_dot(&[i128::MAX, 1], &[1, 1]), None);
+    }
+
+    fn determinant(matrix: &[Vec<i64>]) -> i64 {
+        // Leibniz: the signed sum over every permutation
+        (0..matrix.len())
+            .permutations(matrix.len())
+            .map(|p| {
+                let inversions = p.iter().tuple_combinations().filter(|(a, b)| a > b).count();
+                let sign = if inversions % 2 == 0 { 1 } else { -1 };
+                sign * p
+                    .iter()
+                    .enumerate()
+                    .map(|(r, &c)| matrix[r][c])
+                    .product::<i64>()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_solve_linear() {
+        check(
+            500,
+            |rng| {
+                let n = rng.below(3) + 1;
+                let mut entry = || rng.range(-4..5);
+                let matrix = (0..n)
+                    .map(|_| (0..n).map(|_| entry()).collect_vec())
+                    .collect_vec();
+                let rhs = (0..n).map(|_| entry()).collect_vec();
+                (matrix, rhs)
+            },
+            |_| Vec::new(),
+            |(matrix, _)| determinant(matrix) != 0,
+            |(matrix, rhs)| match solve_linear(matrix, rhs) {
+                Some(x) => {
+                    // It had better actually be a solution
+                    for (row, &b) in matrix.iter().zip(rhs) {
+                        let lhs = row
+                            .iter()
+                            .zip(&x)
+                            .fold(Rational::ZERO, |sum, (&a, &x)| sum + Rational::from(a) * x);
+                        assert_eq!(lhs, Rational::from(b));
+                    }
+                    true
+                }
+                None => false,
+            },
+        );
+    }
+
+    #[test]
+    fn test_solve_integer() {
+        // Two buttons and a prize, as in day 13, searched for presses directly
+        check(
+            200,
+            |rng| {
+                let mut entry = || rng.range(1..30);
+                let matrix = vec![vec![entry(), entry()], vec![entry(), entry()]];
+                let (a, b) = (entry(), entry());
+                let planted = rng.chance(0.5);
+                let rhs = vec![
+                    matrix[0][0] * a + matrix[0][1] * b + !planted as i64,
+                    matrix[1][0] * a + matrix[1][1] * b,
+                ];
+                (matrix, rhs)
+            },
+            |_| Vec::new(),
+            |(matrix, rhs)| {
+                if determinant(matrix) == 0 {
+                    return None;
+                }
+                // By Cramer's rule no answer is bigger than twice the prize times a button
+                (-100_000..100_000).find_map(|a| {
+                    let rest = rhs[0] - matrix[0][0] * a;
+                    let b = rest / matrix[0][1];
+                    (rest % matrix[0][1] == 0 && matrix[1][0] * a + matrix[1][1] * b == rhs[1])
+                        .then(|| vec![a, b])
+                })
+            },
+            |(matrix, rhs)| solve_integer(matrix, rhs),
+        );
+        // The second claw machine in the day 13 sample, once the prize has moved
+        let machine = [vec![26, 67], vec![66, 21]];
+        assert_eq!(
+            solve_integer(&machine, &[10000000012748, 10000000012176]),
+            Some(vec![118679050709, 103199174542])
+        );
+    }
+}
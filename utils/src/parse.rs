@@ -0,0 +1,320 @@
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Where an input stopped making sense. Lines and columns count from 1, like an editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AocError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl AocError {
+    /// An error `offset` bytes into `input`
+    pub fn at(input: &str, offset: usize, message: impl Into<String>) -> AocError {
+        let before = &input[..offset];
+        AocError {
+            line: before.matches('\n').count() + 1,
+            column: before.rsplit('\n').next().unwrap_or("").chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    /// The same error for a piece of input that starts `lines` lines further down, e.g. a
+    /// [`Section`] parsed on its own
+    pub fn below(mut self, lines: usize) -> AocError {
+        self.line += lines;
+        self
+    }
+}
+
+impl Display for AocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for AocError {}
+
+/// The input with Windows line endings turned into plain `\n`s, borrowed if there weren't any
+pub fn normalize(input: &str) -> Cow<'_, str> {
+    if input.contains('\r') {
+        Cow::Owned(input.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// The whole of `filename`, [`normalize`]d
+pub fn read_input(filename: &str) -> String {
+    normalize(&crate::read_file(filename)).into_owned()
+}
+
+/// A run of non-blank lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section<'a> {
+    /// The line number its first line has in the whole input
+    pub line: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Section<'a> {
+    pub fn lines(&self) -> impl Iterator<Item = &'a str> {
+        self.text.lines()
+    }
+
+    /// Each line with its line number in the whole input
+    pub fn numbered_lines(&self) -> impl Iterator<Item = (usize, &'a str)> + '_ {
+        self.text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (self.line + i, l))
+    }
+}
+
+/// Splits `input` at blank lines, however many there are in a row. Blank lines at either
+/// end don't make empty sections. `input` should already be [`normalize`]d.
+pub fn sections(input: &str) -> Vec<Section<'_>> {
+    let mut sections = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for (i, line) in input.split_inclusive('\n').enumerate() {
+        let blank = line.trim().is_empty();
+        match (start, blank) {
+            (None, false) => start = Some((i + 1, offset)),
+            (Some((line, from)), true) => {
+                sections.push(Section {
+                    line,
+                    text: input[from..offset].trim_end_matches('\n'),
+                });
+                start = None;
+            }
+            _ => (),
+        }
+        offset += line.len();
+    }
+    if let Some((line, from)) = start {
+        sections.push(Section {
+            line,
+            text: input[from..].trim_end_matches('\n'),
+        });
+    }
+    sections
+}
+
+/// Parses `text`, which starts `offset` bytes into `input`, or says where it went wrong
+pub fn parse_at<T: FromStr>(input: &str, (offset, text): (usize, &str)) -> Result<T, AocError> {
+    text.parse().map_err(|_| {
+        let kind = std::any::type_name::<T>().rsplit("::").next().unwrap();
+        AocError::at(
+            input,
+            offset,
+            format!("can't read `{}` as a {}", text, kind),
+        )
+    })
+}
+
+/// Every whole number in `input`, in order. A `-` right before the digits makes it negative
+/// and anything else in between is skipped, so `p=0,4 v=3,-3` gives `[0, 4, 3, -3]`.
+pub fn ints<T: FromStr>(input: &str) -> Result<Vec<T>, AocError> {
+    let bytes = input.as_bytes();
+    let mut numbers = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let negative = i > 0 && bytes[i - 1] == b'-';
+        let start = if negative { i - 1 } else { i };
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        numbers.push(parse_at(input, (start, &input[start..i]))?);
+    }
+    Ok(numbers)
+}
+
+/// Matches `input` against `pattern`, where each `{}` stands for some text and everything
+/// else has to be there exactly. A `{}` takes everything up to the first place the text
+/// after it turns up, or the rest of the input if it's last. Gives back where each `{}`'s
+/// text starts and the text itself; [`scan!`](crate::scan) turns those into values.
+///
+/// When some text isn't there, the error points at where it should have started: just
+/// past the number or word the `{}` before it took, and past however much of it did match.
+pub fn captures<'a>(input: &'a str, pattern: &str) -> Result<Vec<(usize, &'a str)>, AocError> {
+    let mut pieces = pattern.split("{}");
+    let first = pieces.next().unwrap();
+    if !input.starts_with(first) {
+        return Err(mismatch(input, 0, first));
+    }
+    let mut at = first.len();
+    let mut captured = Vec::new();
+    for literal in pieces {
+        let end = if literal.is_empty() {
+            input.len()
+        } else {
+            match input[at..].find(literal) {
+                Some(found) => at + found,
+                None => return Err(mismatch(input, word_end(input, at), literal)),
+            }
+        };
+        captured.push((at, &input[at..end]));
+        at = end + literal.len();
+    }
+    if at < input.len() {
+        return Err(AocError::at(
+            input,
+            at,
+            format!("unexpected `{}` after the end", &input[at..]),
+        ));
+    }
+    Ok(captured)
+}
+
+/// The end of the signed number or word starting at `at`, or `at` if there isn't one
+fn word_end(input: &str, at: usize) -> usize {
+    let rest = &input[at..];
+    let word = rest.strip_prefix(['-', '+']).unwrap_or(rest);
+    match word.find(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        Some(0) => at,
+        Some(len) => input.len() - word.len() + len,
+        None if word.is_empty() => at,
+        None => input.len(),
+    }
+}
+
+/// `expected` should have been at `offset`, so the error goes where it first differs
+fn mismatch(input: &str, offset: usize, expected: &str) -> AocError {
+    let matched: usize = input[offset..]
+        .chars()
+        .zip(expected.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    AocError::at(
+        input,
+        offset + matched,
+        format!("expected `{}`", expected.escape_debug()),
+    )
+}
+
+/// Pulls typed values out of a string laid out like `pattern`, one type per `{}`:
+///
+/// ```
+/// let robot = utils::scan!("p=0,4 v=3,-3", "p={},{} v={},{}" => i32, i32, i32, i32);
+/// assert_eq!(robot, Ok((0, 4, 3, -3)));
+/// ```
+///
+/// Gives an [`AocError`](crate::parse::AocError) pointing at the first thing that
+/// doesn't match or doesn't parse.
+#[macro_export]
+macro_rules! scan {
+    ($input:expr, $pattern:expr => $($t:ty),+ $(,)?) => {{
+        let input: &str = $input;
+        (|| -> Result<_, $crate::parse::AocError> {
+            let captured = $crate::parse::captures(input, $pattern)?;
+            assert_eq!(
+                captured.len(),
+                [$(stringify!($t)),+].len(),
+                "pattern needs one type per {{}}"
+            );
+            let mut captured = captured.into_iter();
+            Ok(($($crate::parse::parse_at::<$t>(input, captured.next().unwrap())?,)+))
+        })()
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ints() {
+        assert_eq!(ints::<i32>("p=0,4 v=3,-3"), Ok(vec![0, 4, 3, -3]));
+        assert_eq!(ints::<u64>("Program: 2,4,1,1"), Ok(vec![2, 4, 1, 1]));
+        assert_eq!(ints::<i64>("x - 5 and 7-2"), Ok(vec![5, 7, -2]));
+        assert!(ints::<i32>("nothing here").unwrap().is_empty());
+        let error = ints::<u8>("1 2\n3 -4").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(error.message, "can't read `-4` as a u8");
+        assert_eq!(
+            ints::<i8>("300").unwrap_err().to_string(),
+            "line 1, column 1: can't read `300` as a i8"
+        );
+    }
+
+    #[test]
+    fn test_sections() {
+        let input = "\n\nab\ncd\n\n\n\nef\n  \ngh\n\n";
+        let found = sections(input);
+        assert_eq!(
+            found.iter().map(|s| (s.line, s.text)).collect::<Vec<_>>(),
+            [(3, "ab\ncd"), (8, "ef"), (10, "gh")]
+        );
+        assert_eq!(
+            found[0].numbered_lines().collect::<Vec<_>>(),
+            [(3, "ab"), (4, "cd")]
+        );
+        assert_eq!(sections("ab").len(), 1);
+        assert!(sections("\n \n").is_empty());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert!(matches!(normalize("a\nb"), Cow::Borrowed("a\nb")));
+        let crlf = normalize("a\r\n\r\nb\r\n");
+        assert_eq!(crlf, "a\n\nb\n");
+        assert_eq!(sections(&crlf).len(), 2);
+    }
+
+    #[test]
+    fn test_scan() {
+        assert_eq!(
+            scan!("Button A: X+94, Y+34", "Button A: X+{}, Y+{}" => u64, u64),
+            Ok((94, 34))
+        );
+        assert_eq!(
+            scan!("x00 AND y00 -> z00", "{} {} {} -> {}" => String, String, String, String),
+            Ok((
+                "x00".to_string(),
+                "AND".to_string(),
+                "y00".to_string(),
+                "z00".to_string()
+            ))
+        );
+        assert_eq!(scan!("a: 1\nb: 2", "a: {}\nb: {}" => u8, u8), Ok((1, 2)));
+        let error = scan!("a: 1\nb: x", "a: {}\nb: {}" => u8, u8).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 4));
+        let error = scan!("a: 1, 2", "a: {}; {}" => u8, u8).unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 5: expected `; `");
+        // Where the missing text should have been, not where the number before it started
+        let error = scan!("97-13", "{}|{}" => u32, u32).unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 3: expected `|`");
+        let error = scan!("x=-12 y", "x={}, y" => i32).unwrap_err();
+        assert_eq!(error.column, 6);
+        let error = scan!("Button A: Y+1", "Button A: X+{}" => u8).unwrap_err();
+        assert_eq!(error.column, 11);
+        let error = scan!("b: 1", "a: {}" => u8).unwrap_err();
+        assert_eq!(
+            (error.column, error.message.as_str()),
+            (1, "expected `a: `")
+        );
+        let error = scan!("(1) extra", "({})" => u8).unwrap_err();
+        assert_eq!(error.column, 4);
+    }
+
+    #[test]
+    fn test_below() {
+        let input = "ab\ncd\n\nef\nX";
+        let last = &sections(input)[1];
+        let error = scan!(last.text, "ef\n{}" => u8).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(error.below(last.line - 1).line, 5);
+    }
+}
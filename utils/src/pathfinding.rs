@@ -90,7 +90,7 @@ where
     println!();
 }
 
-pub fn print_map_with_path<T>(map: &HashMap<Point, T>, path: &Vec<Point>)
+pub fn print_map_with_path<T>(map: &HashMap<Point, T>, path: &[Point])
 where
     T: Display,
 {
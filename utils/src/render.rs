@@ -0,0 +1,495 @@
+use crate::viz::{Cell, Color, Frame, Grid};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+pub type Rgb = [u8; 3];
+
+/// The colour a tile is drawn in by default: its terminal colour if it has one, otherwise
+/// walls are grey, floor is dark and anything else stands out in white
+pub fn cell_color(cell: Cell) -> Rgb {
+    match (cell.color, cell.glyph) {
+        (Color::Plain, '#') => [96, 96, 96],
+        (Color::Plain, '.' | ' ') => [16, 16, 24],
+        (Color::Plain, _) => [240, 240, 240],
+        (Color::Gray, _) => [96, 96, 96],
+        (Color::Red, _) => [230, 60, 50],
+        (Color::Green, _) => [70, 200, 80],
+        (Color::Yellow, _) => [240, 200, 50],
+        (Color::Blue, _) => [60, 110, 230],
+        (Color::Magenta, _) => [210, 80, 210],
+        (Color::Cyan, _) => [70, 210, 220],
+    }
+}
+
+/// A grid drawn as blocks of colour, `scale` pixels to a tile, that can be saved as a PNG
+/// or as one frame of a GIF. Pixels are stored as indexes into a palette, which both
+/// formats want anyway, so an image can use at most 256 colours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    scale: usize,
+    palette: Vec<Rgb>,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn render(grid: &impl Grid, scale: usize, colors: impl Fn(Cell) -> Rgb) -> Image {
+        let (rows, cols) = grid.size();
+        let scale = scale.max(1);
+        let mut image = Image {
+            width: cols * scale,
+            height: rows * scale,
+            scale,
+            palette: Vec::new(),
+            pixels: vec![0; rows * cols * scale * scale],
+        };
+        for row in 0..rows {
+            for col in 0..cols {
+                image.paint(row, col, colors(grid.cell(row, col)));
+            }
+        }
+        image
+    }
+
+    /// A frame recorded for the terminal, in its default colours
+    pub fn from_frame(frame: &Frame, scale: usize) -> Image {
+        Image::render(frame, scale, cell_color)
+    }
+
+    fn color_index(&mut self, color: Rgb) -> u8 {
+        match self.palette.iter().position(|&c| c == color) {
+            Some(index) => index as u8,
+            None => {
+                assert!(
+                    self.palette.len() < 256,
+                    "an image can only have 256 colours"
+                );
+                self.palette.push(color);
+                (self.palette.len() - 1) as u8
+            }
+        }
+    }
+
+    /// Fills the tile at `row`, `col` with `color`
+    pub fn paint(&mut self, row: usize, col: usize, color: Rgb) {
+        let index = self.color_index(color);
+        for y in row * self.scale..(row + 1) * self.scale {
+            let start = y * self.width + col * self.scale;
+            self.pixels[start..start + self.scale].fill(index);
+        }
+    }
+
+    /// Paints every tile in `tiles` on top of whatever's there, for marking up paths and
+    /// points of interest. Tiles off the edge are ignored.
+    pub fn overlay(&mut self, tiles: impl IntoIterator<Item = (usize, usize)>, color: Rgb) {
+        let (rows, cols) = (self.height / self.scale, self.width / self.scale);
+        for (row, col) in tiles {
+            if row < rows && col < cols {
+                self.paint(row, col, color);
+            }
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Rgb {
+        self.palette[self.pixels[y * self.width + x] as usize]
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut header = Vec::new();
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bit palette indexes, default compression and filtering, not interlaced
+        header.extend([8, 3, 0, 0, 0]);
+        png_chunk(&mut png, b"IHDR", &header);
+        png_chunk(&mut png, b"PLTE", &self.palette.concat());
+        // Each row starts with its filter type, which is always 0 (none)
+        let raw = self
+            .pixels
+            .chunks(self.width.max(1))
+            .flat_map(|row| std::iter::once(0).chain(row.iter().copied()))
+            .collect::<Vec<_>>();
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    pub fn write_png(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, self.to_png())
+    }
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// A zlib stream that doesn't compress at all, just wraps the data in stored deflate
+/// blocks. Grid images are small enough that it isn't worth a real deflate.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        out.extend((block.len() as u16).to_le_bytes());
+        out.extend((!(block.len() as u16)).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+/// Packs codes of varying widths into bytes, lowest bits first, the way GIF wants them
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// GIF flavoured LZW. Codes start one bit wider than `min_size` and grow as the table
+/// fills, and a clear code starts the table again once it reaches 4096 entries.
+fn lzw_encode(indexes: &[u8], min_size: u32) -> Vec<u8> {
+    let clear = 1u16 << min_size;
+    let end = clear + 1;
+    let mut writer = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let (mut next, mut width) = (end + 1, min_size + 1);
+    writer.write(clear, width);
+    let Some((&first, rest)) = indexes.split_first() else {
+        writer.write(end, width);
+        return writer.finish();
+    };
+    let mut prefix = first as u16;
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        writer.write(prefix, width);
+        // The decoder is a code behind us, so widen once the table has outgrown the
+        // codes written so far
+        if next > (1 << width) - 1 && width < 12 {
+            width += 1;
+        }
+        if next < 4096 {
+            table.insert((prefix, index), next);
+            next += 1;
+        } else {
+            writer.write(clear, width);
+            table.clear();
+            next = end + 1;
+            width = min_size + 1;
+        }
+        prefix = index as u16;
+    }
+    writer.write(prefix, width);
+    if next > (1 << width) - 1 && width < 12 {
+        width += 1;
+    }
+    writer.write(end, width);
+    writer.finish()
+}
+
+/// An animated GIF of `frames`, which all need to be the same size, showing each for
+/// `delay` hundredths of a second and looping forever
+pub fn to_gif(frames: &[Image], delay: u16) -> Vec<u8> {
+    let (width, height) = frames.first().map_or((0, 0), |f| (f.width, f.height));
+    assert!(
+        frames
+            .iter()
+            .all(|f| (f.width, f.height) == (width, height)),
+        "every frame of a GIF has to be the same size"
+    );
+    // One palette for the whole animation, with each frame's pixels mapped onto it
+    let mut palette: Vec<Rgb> = Vec::new();
+    let remapped = frames
+        .iter()
+        .map(|frame| {
+            let mapping = frame
+                .palette
+                .iter()
+                .map(|color| match palette.iter().position(|c| c == color) {
+                    Some(index) => index as u8,
+                    None => {
+                        assert!(palette.len() < 256, "a GIF can only have 256 colours");
+                        palette.push(*color);
+                        (palette.len() - 1) as u8
+                    }
+                })
+                .collect::<Vec<_>>();
+            frame
+                .pixels
+                .iter()
+                .map(|&p| mapping[p as usize])
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    // The colour table has to be a power of two long, at least 4 so LZW has 2 bit codes
+    let bits = (2..=8).find(|b| 1 << b >= palette.len()).unwrap();
+    palette.resize(1 << bits, [0, 0, 0]);
+
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend((width as u16).to_le_bytes());
+    gif.extend((height as u16).to_le_bytes());
+    // Global colour table, 8 bits per channel, no background or aspect ratio
+    gif.extend([0xf0 | (bits as u8 - 1), 0, 0]);
+    gif.extend(palette.concat());
+    gif.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+    for pixels in remapped {
+        gif.extend([0x21, 0xf9, 0x04, 0x00]);
+        gif.extend(delay.to_le_bytes());
+        gif.extend([0x00, 0x00]);
+        gif.push(0x2c);
+        gif.extend([0, 0, 0, 0]);
+        gif.extend((width as u16).to_le_bytes());
+        gif.extend((height as u16).to_le_bytes());
+        gif.push(0);
+        gif.push(bits as u8);
+        for block in lzw_encode(&pixels, bits).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend(block);
+        }
+        gif.push(0);
+    }
+    gif.push(0x3b);
+    gif
+}
+
+pub fn write_gif(frames: &[Image], delay: u16, path: &Path) -> io::Result<()> {
+    std::fs::write(path, to_gif(frames, delay))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::Rng;
+    use array2d::Array2D;
+
+    /// The other half of `lzw_encode`, written the way the GIF spec describes decoding
+    fn lzw_decode(bytes: &[u8], min_size: u32) -> Vec<u8> {
+        let clear = 1usize << min_size;
+        let reset = || (0..clear + 2).map(|i| vec![i as u8]).collect::<Vec<_>>();
+        let (mut table, mut width) = (reset(), min_size + 1);
+        let (mut bit, mut out, mut previous): (usize, Vec<u8>, Option<Vec<u8>>) =
+            (0, Vec::new(), None);
+        loop {
+            let code = (0..width as usize)
+                .map(|i| ((bytes[(bit + i) / 8] >> ((bit + i) % 8)) as usize & 1) << i)
+                .sum::<usize>();
+            bit += width as usize;
+            if code == clear {
+                (table, width, previous) = (reset(), min_size + 1, None);
+                continue;
+            }
+            if code == clear + 1 {
+                return out;
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(p)) => [p.as_slice(), &p[..1]].concat(),
+                (None, None) => panic!("code {} before anything's defined", code),
+            };
+            out.extend(&entry);
+            if let Some(p) = previous {
+                if table.len() < 4096 {
+                    table.push([p.as_slice(), &entry[..1]].concat());
+                }
+            }
+            previous = Some(entry);
+            if table.len() == 1 << width && width < 12 {
+                width += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        let mut rng = Rng::new(47);
+        for (len, colors) in [
+            (0, 4),
+            (1, 4),
+            (10, 2),
+            (5000, 3),
+            (100_000, 16),
+            (20_000, 256),
+        ] {
+            let data = (0..len)
+                .map(|_| rng.below(colors) as u8)
+                .collect::<Vec<_>>();
+            let bits = (2..=8).find(|b| 1 << b >= colors).unwrap();
+            assert_eq!(lzw_decode(&lzw_encode(&data, bits), bits), data);
+        }
+        // Long runs fill the table with long entries instead
+        let runs = vec![3; 300_000];
+        assert_eq!(lzw_decode(&lzw_encode(&runs, 2), 2), runs);
+    }
+
+    #[test]
+    fn test_render() {
+        let grid = Array2D::from_rows(&[vec!['#', '.'], vec!['.', 'S']]).unwrap();
+        let mut image = Image::render(&grid, 3, cell_color);
+        assert_eq!((image.width, image.height), (6, 6));
+        assert_eq!(image.pixel(2, 2), [96, 96, 96]);
+        assert_eq!(image.pixel(3, 0), [16, 16, 24]);
+        assert_eq!(image.pixel(5, 5), [240, 240, 240]);
+        image.overlay([(0, 1), (5, 5)], [255, 0, 0]);
+        assert_eq!(image.pixel(4, 1), [255, 0, 0]);
+        assert_eq!(image.palette.len(), 4);
+    }
+
+    #[test]
+    fn test_png() {
+        let grid = Array2D::from_rows(&[vec!['#', '.', 'x']]).unwrap();
+        let png = Image::render(&grid, 2, cell_color).to_png();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"));
+        assert_eq!(png[16..26], [0, 0, 0, 6, 0, 0, 0, 2, 8, 3]);
+        // Walk the chunks, checking every length and CRC lines up
+        let mut at = 8;
+        let mut kinds = Vec::new();
+        let mut data = Vec::new();
+        while at < png.len() {
+            let len = u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as usize;
+            let body = &png[at + 4..at + 8 + len];
+            let crc = u32::from_be_bytes(png[at + 8 + len..at + 12 + len].try_into().unwrap());
+            assert_eq!(crc, crc32(body));
+            kinds.push(String::from_utf8(body[..4].to_vec()).unwrap());
+            if &body[..4] == b"IDAT" {
+                data = body[4..].to_vec();
+            }
+            at += 12 + len;
+        }
+        assert_eq!(kinds, ["IHDR", "PLTE", "IDAT", "IEND"]);
+        // One stored block holding both rows, each a 0 filter byte then six pixels
+        let row = [0, 0, 0, 1, 1, 2, 2];
+        assert_eq!(data[2..7], [1, 14, 0, !14, 0xff]);
+        assert_eq!(data[7..21], [row, row].concat());
+    }
+
+    #[test]
+    fn test_gif() {
+        let frame = |c| Image::render(&Array2D::from_rows(&[vec![c, '.']]).unwrap(), 1, cell_color);
+        let gif = to_gif(&[frame('#'), frame('x'), frame('#')], 10);
+        assert!(gif.starts_with(b"GIF89a\x02\x00\x01\x00"));
+        assert_eq!(gif[10], 0xf1);
+        // Three colours round up to a table of four
+        assert_eq!(
+            gif[13..25],
+            [96, 96, 96, 16, 16, 24, 240, 240, 240, 0, 0, 0]
+        );
+        assert_eq!(gif.iter().filter(|&&b| b == 0x2c).count(), 3);
+        assert_eq!(gif.last(), Some(&0x3b));
+    }
+
+    /// A grid of `colors` made up colours, as glyphs that map straight onto them
+    fn noise(rng: &mut Rng, rows: usize, cols: usize, colors: usize) -> Image {
+        let glyphs = (0..rows)
+            .map(|_| {
+                (0..cols)
+                    .map(|_| char::from_u32(0x100 + rng.below(colors) as u32).unwrap())
+                    .collect()
+            })
+            .collect::<Vec<Vec<char>>>();
+        Image::render(&Array2D::from_rows(&glyphs).unwrap(), 1, |cell| {
+            let k = cell.glyph as u32 - 0x100;
+            [k as u8, (k * 7) as u8, (k * 13) as u8]
+        })
+    }
+
+    #[test]
+    fn test_png_decodes_elsewhere() {
+        // Big enough to need more than one stored block
+        let image = noise(&mut Rng::new(47), 300, 250, 200);
+        let png = image.to_png();
+        let mut decoder = png::Decoder::new(png.as_slice());
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).unwrap();
+        assert_eq!((info.width, info.height), (250, 300));
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+        for (i, rgb) in buffer[..info.buffer_size()].chunks(3).enumerate() {
+            assert_eq!(rgb, image.pixel(i % 250, i / 250), "pixel {}", i);
+        }
+    }
+
+    #[test]
+    fn test_gif_decodes_elsewhere() {
+        // Enough pixels to fill the LZW table and clear it, across a few code widths
+        let mut rng = Rng::new(47);
+        let frames = [200, 2, 17].map(|colors| noise(&mut rng, 200, 200, colors));
+        let gif = to_gif(&frames, 5);
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(gif.as_slice()).unwrap();
+        let mut count = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            let image = &frames[count];
+            assert_eq!((frame.width, frame.height), (200, 200));
+            assert_eq!(frame.delay, 5);
+            for (i, rgba) in frame.buffer.chunks(4).enumerate() {
+                assert_eq!(rgba[..3], image.pixel(i % 200, i / 200), "pixel {}", i);
+            }
+            count += 1;
+        }
+        assert_eq!(count, frames.len());
+    }
+}
@@ -0,0 +1,177 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Path to a sample that's checked in next to the solution. Samples are part of the repo,
+/// so a missing one is a real failure.
+pub fn sample(name: &str) -> String {
+    let path = format!("src/{}", name);
+    assert!(Path::new(&path).exists(), "missing sample file {}", path);
+    path
+}
+
+/// Path to a puzzle input, if it's there. Real inputs aren't checked in, so on a fresh
+/// checkout this says so and the caller skips its checks.
+pub fn optional_input(name: &str) -> Option<String> {
+    let path = format!("src/{}", name);
+    if Path::new(&path).exists() {
+        Some(path)
+    } else {
+        eprintln!("skipping: {} not found", path);
+        None
+    }
+}
+
+/// Where the answers for an input go: `input-answers.txt` next to `input.txt`
+pub fn answers_path(input: &str) -> String {
+    let path = Path::new(input);
+    let stem = path.file_stem().unwrap().to_string_lossy();
+    path.with_file_name(format!("{}-answers.txt", stem))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The answers already accepted for a puzzle input, one line per part in order. They're
+/// kept untracked next to the input like the input itself, but once the input is there a
+/// missing answers file is a failure, so a real input never passes without being checked.
+pub fn answers(input: &str) -> Vec<String> {
+    let path = answers_path(input);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => text.lines().map(|l| l.trim().to_string()).collect(),
+        Err(e) => panic!(
+            "{} is there but {} isn't ({}): record its answers, one line per part",
+            input, path, e
+        ),
+    }
+}
+
+/// Writes `contents` to a scratch file and runs `f` on its path, for feeding made up inputs
+/// (like the generated ones) to parts that read from a file
+pub fn with_input<T>(contents: &str, f: impl FnOnce(&str) -> T) -> T {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "aoc-{}-{}.txt",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&path, contents).unwrap();
+    let result = f(path.to_str().unwrap());
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Declares a test that runs parts of a puzzle against a file in `src/`.
+///
+/// Each part is anything callable with the file's path (usually just `part_1`) followed by
+/// `=> expected`. For a real input the expected answers come from its [`answers`] file
+/// instead, compared with what the part displays, and every part needs one.
+///
+/// ```ignore
+/// aoc_test!(test_sample, "sample.txt", part_1 => 7, part_2 => "co,de,ka,ta");
+/// // Checked against src/input-answers.txt, and only skipped when src/input.txt isn't
+/// // there
+/// aoc_test!(test_input, optional "input.txt", part_1, part_2);
+/// ```
+#[macro_export]
+macro_rules! aoc_test {
+    (@check $path:ident, $part:expr, $expected:expr) => {
+        assert_eq!(
+            ($part)($path.as_str()),
+            $expected,
+            "{} on {}",
+            stringify!($part),
+            $path
+        );
+    };
+    (@answer $path:ident, $answers:ident, $part:expr) => {
+        match $answers.next().filter(|a| !a.is_empty()) {
+            Some(expected) => assert_eq!(
+                ($part)($path.as_str()).to_string(),
+                expected,
+                "{} on {}",
+                stringify!($part),
+                $path
+            ),
+            None => panic!(
+                "no recorded answer for {} in {}",
+                stringify!($part),
+                $crate::test_support::answers_path(&$path)
+            ),
+        }
+    };
+    ($name:ident, optional $file:literal $(, $part:expr)+ $(,)?) => {
+        #[test]
+        fn $name() {
+            let Some(path) = $crate::test_support::optional_input($file) else {
+                return;
+            };
+            let mut answers = $crate::test_support::answers(&path).into_iter();
+            $($crate::aoc_test!(@answer path, answers, $part);)+
+        }
+    };
+    ($name:ident, $file:literal $(, $part:expr => $expected:expr)+ $(,)?) => {
+        #[test]
+        fn $name() {
+            let path = $crate::test_support::sample($file);
+            $($crate::aoc_test!(@check path, $part, $expected);)+
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::read_file;
+    use std::path::Path;
+
+    fn length(filename: &str) -> usize {
+        read_file(filename).len()
+    }
+
+    aoc_test!(test_sample, "lib.rs", |f: &str| length(f) > 0 => true, |f: &str| f.len() => 10);
+    aoc_test!(test_missing_input, optional "no-such-input.txt", |_: &str| -> usize {
+        panic!("shouldn't run without an input")
+    });
+
+    #[test]
+    fn test_answers() {
+        use crate::test_support::{answers, answers_path, with_input};
+        assert_eq!(answers_path("src/input.txt"), "src/input-answers.txt");
+        assert_eq!(answers_path("src/map.txt"), "src/map-answers.txt");
+        with_input("", |input| {
+            let path = answers_path(input);
+            std::fs::write(&path, "1234\n\n co,de \n").unwrap();
+            let found = answers(input);
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(found, ["1234", "", "co,de"]);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "src/lib-answers.txt isn't")]
+    fn test_missing_answers() {
+        // The input is there, so going without its answers fails instead of skipping
+        crate::test_support::answers("src/lib.rs");
+    }
+
+    #[test]
+    #[should_panic(expected = "no recorded answer for length")]
+    fn test_missing_answer() {
+        let path = "src/lib.rs".to_string();
+        let mut answers = Vec::<String>::new().into_iter();
+        aoc_test!(@answer path, answers, length);
+    }
+
+    #[test]
+    fn test_with_input() {
+        assert_eq!(crate::test_support::with_input("a\nb\n", length), 4);
+        let first = crate::test_support::with_input("", |f| f.to_string());
+        let second = crate::test_support::with_input("", |f| f.to_string());
+        assert_ne!(first, second);
+        assert!(!Path::new(&first).exists());
+    }
+
+    #[test]
+    #[should_panic(expected = "missing sample file src/no-such-sample.txt")]
+    fn test_missing_sample() {
+        crate::test_support::sample("no-such-sample.txt");
+    }
+}
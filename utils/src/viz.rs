@@ -0,0 +1,413 @@
+use array2d::Array2D;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Color {
+    #[default]
+    Plain,
+    Gray,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl Color {
+    fn ansi(self) -> &'static str {
+        match self {
+            Color::Plain => "\x1b[0m",
+            Color::Gray => "\x1b[90m",
+            Color::Red => "\x1b[91m",
+            Color::Green => "\x1b[92m",
+            Color::Yellow => "\x1b[93m",
+            Color::Blue => "\x1b[94m",
+            Color::Magenta => "\x1b[95m",
+            Color::Cyan => "\x1b[96m",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub glyph: char,
+    pub color: Color,
+}
+
+impl Cell {
+    pub fn new(glyph: char, color: Color) -> Cell {
+        Cell { glyph, color }
+    }
+}
+
+impl From<char> for Cell {
+    fn from(glyph: char) -> Cell {
+        Cell::new(glyph, Color::Plain)
+    }
+}
+
+/// Anything that can be drawn as rows of coloured characters
+pub trait Grid {
+    /// (rows, columns)
+    fn size(&self) -> (usize, usize);
+    fn cell(&self, row: usize, col: usize) -> Cell;
+}
+
+impl<T: Clone + Into<Cell>> Grid for Array2D<T> {
+    fn size(&self) -> (usize, usize) {
+        (self.num_rows(), self.num_columns())
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Cell {
+        self[(row, col)].clone().into()
+    }
+}
+
+/// A snapshot of a grid, with a line of text to show under it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub caption: String,
+    rows: Vec<Vec<Cell>>,
+}
+
+/// Lets a recorded frame be drawn again, e.g. by [`crate::render`]
+impl Grid for Frame {
+    fn size(&self) -> (usize, usize) {
+        (self.rows.len(), self.rows.first().map_or(0, |r| r.len()))
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Cell {
+        self.rows[row][col]
+    }
+}
+
+impl Frame {
+    pub fn capture(grid: &impl Grid, caption: &str) -> Frame {
+        let (rows, cols) = grid.size();
+        Frame {
+            caption: caption.to_string(),
+            rows: (0..rows)
+                .map(|r| (0..cols).map(|c| grid.cell(r, c)).collect())
+                .collect(),
+        }
+    }
+
+    /// Just the characters, one line per row
+    pub fn to_plain(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| row.iter().map(|c| c.glyph).collect::<String>() + "\n")
+            .collect()
+    }
+
+    /// The characters with colour codes, only switching colour where it changes
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for row in &self.rows {
+            let mut color = Color::Plain;
+            for cell in row {
+                if cell.color != color {
+                    color = cell.color;
+                    out.push_str(color.ansi());
+                }
+                out.push(cell.glyph);
+            }
+            if color != Color::Plain {
+                out.push_str(Color::Plain.ansi());
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Collects frames from a running simulation. Long runs would use far too much memory
+/// if every step were kept, so once there are `max_frames` every other one is dropped and
+/// only every other step is recorded from then on. What's left is always spread evenly
+/// over the run.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    max_frames: usize,
+    every: usize,
+    steps: usize,
+    frames: Vec<Frame>,
+}
+
+impl Recorder {
+    pub fn new(max_frames: usize) -> Recorder {
+        Recorder {
+            max_frames: max_frames.max(2),
+            every: 1,
+            steps: 0,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, grid: &impl Grid, caption: &str) {
+        self.steps += 1;
+        if !(self.steps - 1).is_multiple_of(self.every) {
+            return;
+        }
+        self.frames.push(Frame::capture(grid, caption));
+        if self.frames.len() >= self.max_frames {
+            let mut keep = false;
+            self.frames.retain(|_| {
+                keep = !keep;
+                keep
+            });
+            self.every *= 2;
+        }
+    }
+
+    /// Records the end of the simulation whether or not it was due a frame
+    pub fn finish(mut self, grid: &impl Grid, caption: &str) -> Vec<Frame> {
+        self.frames.push(Frame::capture(grid, caption));
+        self.frames
+    }
+}
+
+/// Writes each frame to `frame-00000.txt` and so on in `dir` as plain text, caption first,
+/// for looking through by hand or comparing against in tests
+pub fn write_frames(frames: &[Frame], dir: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for (i, frame) in frames.iter().enumerate() {
+        let text = format!("{}\n{}", frame.caption, frame.to_plain());
+        std::fs::write(dir.join(format!("frame-{:05}.txt", i)), text)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Pause,
+    Forward,
+    Back,
+    Faster,
+    Slower,
+    Quit,
+}
+
+fn parse_key(bytes: &[u8]) -> Option<Key> {
+    match bytes {
+        b" " | b"p" => Some(Key::Pause),
+        b"\x1b[C" | b"." | b"l" => Some(Key::Forward),
+        b"\x1b[D" | b"," | b"h" => Some(Key::Back),
+        b"+" | b"=" | b"\x1b[A" => Some(Key::Faster),
+        b"-" | b"\x1b[B" => Some(Key::Slower),
+        // Ctrl-C comes through as a byte too while the terminal is in raw mode
+        b"q" | b"\x1b" | b"\x03" => Some(Key::Quit),
+        _ => None,
+    }
+}
+
+/// Plays `frames` in the terminal at `fps` frames a second. Space pauses, the left and
+/// right arrows step back and forward, up and down (or `+` and `-`) change the speed and
+/// `q` stops. Without a terminal to read keys from it just plays straight through.
+pub fn play(frames: &[Frame], fps: f64) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    let terminal = keys::RawMode::enable();
+    let (mut index, mut fps, mut paused) = (0, fps.clamp(0.25, 1000.0), false);
+    // Clear the screen and hide the cursor
+    write!(out, "\x1b[2J\x1b[?25l")?;
+    while index < frames.len() {
+        let frame = &frames[index];
+        let status = format!(
+            "frame {}/{} at {} fps{}",
+            index + 1,
+            frames.len(),
+            fps,
+            if paused { " (paused)" } else { "" }
+        );
+        write!(
+            out,
+            "\x1b[H{}{}\x1b[K\n{}\x1b[K\n",
+            frame.to_ansi(),
+            frame.caption,
+            status
+        )?;
+        out.flush()?;
+        let wait = Duration::from_secs_f64(1.0 / fps);
+        let key = match &terminal {
+            Some(terminal) if paused => terminal.wait_key(None),
+            Some(terminal) => terminal.wait_key(Some(wait)),
+            None => {
+                std::thread::sleep(wait);
+                None
+            }
+        };
+        match key {
+            Some(Key::Pause) => paused = !paused,
+            Some(Key::Forward) => index += 1,
+            Some(Key::Back) => index = index.saturating_sub(1),
+            Some(Key::Faster) => fps = (fps * 2.0).min(1000.0),
+            Some(Key::Slower) => fps = (fps / 2.0).max(0.25),
+            Some(Key::Quit) => break,
+            None if !paused => index += 1,
+            None => (),
+        }
+    }
+    write!(out, "\x1b[?25h")?;
+    out.flush()
+}
+
+/// Handles the `[dir]` argument of a day's `show` command: with a directory the frames
+/// are written there, without one they're played in the terminal
+pub fn present(frames: &[Frame], dir: Option<&str>, fps: f64) -> io::Result<()> {
+    match dir {
+        Some(dir) => write_frames(frames, Path::new(dir)),
+        None => play(frames, fps),
+    }
+}
+
+#[cfg(unix)]
+mod keys {
+    use super::{parse_key, Key};
+    use std::time::Duration;
+
+    /// Turns off line buffering, echo and signals on stdin while it's alive, so single key
+    /// presses can be read as they happen
+    pub struct RawMode {
+        original: libc::termios,
+    }
+
+    impl RawMode {
+        pub fn enable() -> Option<RawMode> {
+            // SAFETY: plain termios calls on stdin, with a zeroed struct for them to fill
+            unsafe {
+                if libc::isatty(libc::STDIN_FILENO) == 0 {
+                    return None;
+                }
+                let mut original: libc::termios = std::mem::zeroed();
+                if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                    return None;
+                }
+                let mut raw = original;
+                raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+                if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                    return None;
+                }
+                Some(RawMode { original })
+            }
+        }
+
+        /// The next key we know about, giving up after `timeout` if there is one
+        pub fn wait_key(&self, timeout: Option<Duration>) -> Option<Key> {
+            let millis = timeout.map_or(-1, |t| t.as_millis().min(i32::MAX as u128) as i32);
+            let mut poll = libc::pollfd {
+                fd: libc::STDIN_FILENO,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let mut buffer = [0u8; 8];
+            // SAFETY: one pollfd, and the read is bounded by the buffer's length
+            let read = unsafe {
+                if libc::poll(&mut poll, 1, millis) <= 0 {
+                    return None;
+                }
+                libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), buffer.len())
+            };
+            parse_key(&buffer[..read.max(0) as usize])
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            // SAFETY: puts back exactly what tcgetattr gave us
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod keys {
+    use super::Key;
+    use std::time::Duration;
+
+    /// No raw terminal support here, so playback never waits on keys
+    pub struct RawMode;
+
+    impl RawMode {
+        pub fn enable() -> Option<RawMode> {
+            None
+        }
+
+        pub fn wait_key(&self, _timeout: Option<Duration>) -> Option<Key> {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter(usize);
+
+    impl Grid for Counter {
+        fn size(&self) -> (usize, usize) {
+            (1, 3)
+        }
+
+        fn cell(&self, _row: usize, col: usize) -> Cell {
+            let digit = char::from_digit((self.0 / 10usize.pow(2 - col as u32) % 10) as u32, 10);
+            Cell::new(digit.unwrap(), Color::Green)
+        }
+    }
+
+    #[test]
+    fn test_frames() {
+        let grid = Array2D::from_rows(&[vec!['#', '.'], vec!['.', '#']]).unwrap();
+        let frame = Frame::capture(&grid, "start");
+        assert_eq!(frame.to_plain(), "#.\n.#\n");
+        assert_eq!(frame.to_ansi(), "#.\n.#\n");
+        assert_eq!(
+            Frame::capture(&Counter(42), "").to_ansi(),
+            "\x1b[92m042\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_recorder() {
+        let mut recorder = Recorder::new(10);
+        for step in 0..100 {
+            recorder.record(&Counter(step), &step.to_string());
+        }
+        let frames = recorder.finish(&Counter(100), "done");
+        assert!(frames.len() <= 11);
+        // Evenly spread from the first step, and the last one is always there
+        let steps = frames.iter().map(|f| f.caption.clone()).collect::<Vec<_>>();
+        assert_eq!(steps[..3], ["0", "16", "32"]);
+        assert_eq!(steps.last().unwrap(), "done");
+    }
+
+    #[test]
+    fn test_write_frames() {
+        let dir = std::env::temp_dir().join(format!("aoc-viz-{}", std::process::id()));
+        let frames = [
+            Frame::capture(&Counter(7), "a"),
+            Frame::capture(&Counter(8), "b"),
+        ];
+        write_frames(&frames, &dir).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("frame-00001.txt")).unwrap(),
+            "b\n008\n"
+        );
+        assert!(!dir.join("frame-00002.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(parse_key(b" "), Some(Key::Pause));
+        assert_eq!(parse_key(b"\x1b[C"), Some(Key::Forward));
+        assert_eq!(parse_key(b"\x1b[D"), Some(Key::Back));
+        assert_eq!(parse_key(b"+"), Some(Key::Faster));
+        assert_eq!(parse_key(b"\x03"), Some(Key::Quit));
+        assert_eq!(parse_key(b"x"), None);
+    }
+}